@@ -0,0 +1,170 @@
+-- Add up migration script here
+-- Same schema the SQLite migrations end up with. Timestamps stay unix seconds
+-- and enums stay text so both backends read into the same DTOs.
+
+CREATE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = EXTRACT(EPOCH FROM NOW())::BIGINT;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE guild (
+    id BIGINT NOT NULL PRIMARY KEY,
+    chat_channel_id BIGINT,
+    name TEXT NOT NULL,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+CREATE TRIGGER set_updated_at_guild BEFORE UPDATE ON guild
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE summoner (
+    id TEXT NOT NULL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guild (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    queue_type TEXT,
+    tier TEXT,
+    lp BIGINT,
+    division TEXT,
+    icon_url TEXT NOT NULL
+);
+CREATE INDEX summoner_lower_name ON summoner (LOWER(name));
+CREATE TRIGGER set_updated_at_summoner BEFORE UPDATE ON summoner
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE game (
+    id TEXT NOT NULL PRIMARY KEY,
+    summoner_id TEXT NOT NULL REFERENCES summoner (id) ON DELETE CASCADE,
+    game_created_at BIGINT NOT NULL, -- riot timestamp
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    assists BIGINT NOT NULL,
+    deaths BIGINT NOT NULL,
+    kills BIGINT NOT NULL,
+    result TEXT NOT NULL DEFAULT 'loss' CHECK (result IN ('win', 'loss', 'remake')),
+    notified BOOLEAN NOT NULL DEFAULT FALSE,
+    champion_name TEXT NOT NULL,
+    game_mode TEXT NOT NULL,
+    lp_change BIGINT,
+    promotion_text TEXT,
+    game_duration BIGINT, -- seconds
+    lp_pending BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE INDEX game_summoner_id_game_created_at ON game (summoner_id, game_created_at);
+CREATE TRIGGER set_updated_at_game BEFORE UPDATE ON game
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE active_game (
+    id TEXT NOT NULL PRIMARY KEY,
+    summoner_id TEXT NOT NULL REFERENCES summoner (id) ON DELETE CASCADE,
+    game_created_at BIGINT NOT NULL, -- riot timestamp
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    champion TEXT NOT NULL,
+    role TEXT NOT NULL,
+    spectate_link TEXT NOT NULL,
+    notified BOOLEAN NOT NULL,
+    game_mode TEXT NOT NULL
+);
+
+CREATE TABLE log (
+    id BIGSERIAL NOT NULL PRIMARY KEY,
+    message TEXT NOT NULL,
+    error_type TEXT NOT NULL,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    target TEXT,
+    summoner_id TEXT,
+    guild_id BIGINT,
+    strategy TEXT,
+    url TEXT
+);
+
+CREATE TABLE champion (
+    id TEXT NOT NULL PRIMARY KEY, -- data dragon id e.g. MonkeyKing
+    key BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+CREATE TRIGGER set_updated_at_champion BEFORE UPDATE ON champion
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE rank_snapshot (
+    id BIGSERIAL NOT NULL PRIMARY KEY,
+    summoner_id TEXT NOT NULL REFERENCES summoner (id) ON DELETE CASCADE,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    queue_type TEXT,
+    tier TEXT,
+    division TEXT,
+    lp BIGINT
+);
+CREATE INDEX rank_snapshot_summoner_id_created_at ON rank_snapshot (summoner_id, created_at);
+
+CREATE TABLE api_token (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guild (id) ON DELETE CASCADE,
+    -- sha256 of the token, the token itself is only shown once
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+
+CREATE TABLE guild_template (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guild (id) ON DELETE CASCADE,
+    victory_title TEXT,
+    defeat_title TEXT,
+    in_game_title TEXT,
+    victory_color TEXT,
+    defeat_color TEXT,
+    in_game_color TEXT,
+    victory_emoji TEXT,
+    defeat_emoji TEXT,
+    in_game_emoji TEXT,
+    game_fields TEXT,
+    active_game_fields TEXT,
+    demotion_text TEXT,
+    remake_title TEXT,
+    remake_color TEXT,
+    remake_emoji TEXT,
+    remake_notifications TEXT,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+CREATE TRIGGER set_updated_at_guild_template BEFORE UPDATE ON guild_template
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Per guild toggles for streak and milestone rules. Missing rows mean enabled.
+CREATE TABLE guild_rule (
+    guild_id BIGINT NOT NULL REFERENCES guild (id) ON DELETE CASCADE,
+    rule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    created_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+
+    PRIMARY KEY (guild_id, rule)
+);
+CREATE TRIGGER set_updated_at_guild_rule BEFORE UPDATE ON guild_rule
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE notification_outbox (
+    id BIGSERIAL NOT NULL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guild (id) ON DELETE CASCADE,
+    summoner_id TEXT NOT NULL REFERENCES summoner (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('game_finished', 'game_started')),
+    game_id TEXT NOT NULL, -- game.id or active_game.id depending on kind
+    highlights TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts BIGINT NOT NULL DEFAULT 0,
+    next_attempt_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    last_error TEXT,
+    message_id TEXT, -- Discord message id once sent
+    sent_at BIGINT,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    updated_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+
+    UNIQUE (kind, game_id)
+);
+CREATE INDEX notification_outbox_due ON notification_outbox (status, next_attempt_at);
+CREATE TRIGGER set_updated_at_notification_outbox BEFORE UPDATE ON notification_outbox
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Members with this role can run admin commands without Manage Server
+ALTER TABLE guild ADD COLUMN manager_role_id BIGINT;
+-- Discord user who linked the summoner, they can untrack it without being a manager.
+-- NULL for summoners added before this or from the command line.
+ALTER TABLE summoner ADD COLUMN added_by BIGINT;
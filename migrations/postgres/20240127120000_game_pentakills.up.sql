@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Pentakills in the game, NULL when the match history didn't say
+ALTER TABLE game ADD COLUMN pentakills BIGINT;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Summoner ids are case-insensitive like on SQLite (COLLATE NOCASE).
+-- Keep one row of ids that only differ in case, games of the others go with them.
+DELETE FROM summoner a USING summoner b
+WHERE LOWER(a.id) = LOWER(b.id) AND a.id > b.id;
+CREATE UNIQUE INDEX summoner_lower_id ON summoner (LOWER(id));
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Games missed while offline are queued as catch_up rows, one per game, and
+-- posted as one summary per guild
+ALTER TABLE notification_outbox DROP CONSTRAINT notification_outbox_kind_check;
+ALTER TABLE notification_outbox ADD CONSTRAINT notification_outbox_kind_check
+    CHECK (kind IN ('game_finished', 'game_started', 'catch_up'));
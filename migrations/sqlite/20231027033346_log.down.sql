@@ -0,0 +1 @@
+-- Add down migration script here
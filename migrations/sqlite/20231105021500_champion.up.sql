@@ -0,0 +1,17 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS champion (
+    id TEXT NOT NULL PRIMARY KEY, -- data dragon id e.g. MonkeyKing
+    key INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+);
+
+CREATE TRIGGER [SetUpdatedAt_champion]
+    AFTER UPDATE
+    ON champion
+    FOR EACH ROW
+BEGIN
+    UPDATE champion SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END
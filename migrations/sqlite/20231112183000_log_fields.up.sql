@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE log ADD COLUMN target TEXT;
+ALTER TABLE log ADD COLUMN summoner_id TEXT COLLATE NOCASE;
+ALTER TABLE log ADD COLUMN guild_id INTEGER;
+ALTER TABLE log ADD COLUMN strategy TEXT;
+ALTER TABLE log ADD COLUMN url TEXT;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS rank_snapshot (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    queue_type TEXT,
+    tier TEXT,
+    division TEXT,
+    lp INTEGER,
+
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id)
+);
+
+CREATE INDEX IF NOT EXISTS rank_snapshot_summoner_id_created_at
+    ON rank_snapshot (summoner_id, created_at);
@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS api_token (
+    guild_id INTEGER NOT NULL PRIMARY KEY,
+    -- sha256 of the token, the token itself is only shown once
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+
+    FOREIGN KEY (guild_id) REFERENCES guild (id)
+);
@@ -0,0 +1,29 @@
+-- Add up migration script here
+-- Per guild overrides for notification messages. NULL means use the default.
+CREATE TABLE IF NOT EXISTS guild_template (
+    guild_id INTEGER NOT NULL PRIMARY KEY,
+    victory_title TEXT,
+    defeat_title TEXT,
+    in_game_title TEXT,
+    victory_color TEXT,
+    defeat_color TEXT,
+    in_game_color TEXT,
+    victory_emoji TEXT,
+    defeat_emoji TEXT,
+    in_game_emoji TEXT,
+    game_fields TEXT,
+    active_game_fields TEXT,
+    demotion_text TEXT,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+
+    FOREIGN KEY (guild_id) REFERENCES guild (id)
+);
+
+CREATE TRIGGER [SetUpdatedAt_guild_template]
+    AFTER UPDATE
+    ON guild_template
+    FOR EACH ROW
+BEGIN
+    UPDATE guild_template SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END
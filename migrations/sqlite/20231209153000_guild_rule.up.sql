@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Per guild toggles for streak and milestone rules. Missing rows mean enabled.
+CREATE TABLE IF NOT EXISTS guild_rule (
+    guild_id INTEGER NOT NULL,
+    rule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+
+    PRIMARY KEY (guild_id, rule),
+    FOREIGN KEY (guild_id) REFERENCES guild (id)
+);
+
+CREATE TRIGGER [SetUpdatedAt_guild_rule]
+    AFTER UPDATE
+    ON guild_rule
+    FOR EACH ROW
+BEGIN
+    UPDATE guild_rule SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END
@@ -0,0 +1,13 @@
+-- Add up migration script here
+ALTER TABLE game ADD COLUMN result TEXT NOT NULL DEFAULT 'loss' CHECK (result IN ('win', 'loss', 'remake'));
+ALTER TABLE game ADD COLUMN game_duration INTEGER; -- seconds
+
+UPDATE game SET result = CASE WHEN win THEN 'win' ELSE 'loss' END;
+
+ALTER TABLE game DROP COLUMN win;
+
+-- Remake notifications are labelled by default, guilds can rename or suppress them
+ALTER TABLE guild_template ADD COLUMN remake_title TEXT;
+ALTER TABLE guild_template ADD COLUMN remake_color TEXT;
+ALTER TABLE guild_template ADD COLUMN remake_emoji TEXT;
+ALTER TABLE guild_template ADD COLUMN remake_notifications TEXT;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Ranked games scraped before League of Graphs shows their lp change wait here
+-- until a later poll finds it, or until they time out
+ALTER TABLE game ADD COLUMN lp_pending BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,33 @@
+-- Add up migration script here
+-- Notifications waiting to be delivered. Rows are inserted in the same
+-- transaction that marks the game as notified and delivered by the outbox worker.
+CREATE TABLE IF NOT EXISTS notification_outbox (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('game_finished', 'game_started')),
+    game_id TEXT NOT NULL, -- game.id or active_game.id depending on kind
+    highlights TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    last_error TEXT,
+    message_id TEXT, -- Discord message id once sent
+    sent_at INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+
+    UNIQUE (kind, game_id),
+    FOREIGN KEY (guild_id) REFERENCES guild (id),
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id)
+);
+
+CREATE INDEX IF NOT EXISTS notification_outbox_due ON notification_outbox (status, next_attempt_at);
+
+CREATE TRIGGER [SetUpdatedAt_notification_outbox]
+    AFTER UPDATE
+    ON notification_outbox
+    FOR EACH ROW
+BEGIN
+    UPDATE notification_outbox SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END
@@ -0,0 +1,226 @@
+-- Add up migration script here
+-- SQLite can't change constraints, so tables are rebuilt to add ON DELETE CASCADE.
+-- Migrations run with foreign keys off, see db::create_db.
+
+-- Rows left behind by deletes before foreign keys were enforced
+DELETE FROM summoner WHERE guild_id NOT IN (SELECT id FROM guild);
+DELETE FROM game WHERE summoner_id NOT IN (SELECT id FROM summoner);
+DELETE FROM active_game WHERE summoner_id NOT IN (SELECT id FROM summoner);
+DELETE FROM rank_snapshot WHERE summoner_id NOT IN (SELECT id FROM summoner);
+DELETE FROM notification_outbox
+WHERE summoner_id NOT IN (SELECT id FROM summoner) OR guild_id NOT IN (SELECT id FROM guild);
+DELETE FROM api_token WHERE guild_id NOT IN (SELECT id FROM guild);
+DELETE FROM guild_template WHERE guild_id NOT IN (SELECT id FROM guild);
+DELETE FROM guild_rule WHERE guild_id NOT IN (SELECT id FROM guild);
+
+-- summoner
+CREATE TABLE summoner_new (
+    id TEXT COLLATE NOCASE NOT NULL PRIMARY KEY,
+    guild_id INTEGER NOT NULL,
+    name TEXT COLLATE NOCASE NOT NULL,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+    queue_type TEXT,
+    tier TEXT,
+    lp INTEGER,
+    division TEXT,
+    icon_url TEXT NOT NULL,
+
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE
+);
+INSERT INTO summoner_new (id, guild_id, name, created_at, updated_at, queue_type, tier, lp, division, icon_url)
+SELECT id, guild_id, name, created_at, updated_at, queue_type, tier, lp, division, icon_url FROM summoner;
+DROP TABLE summoner;
+ALTER TABLE summoner_new RENAME TO summoner;
+
+CREATE TRIGGER [SetUpdatedAt_summoner]
+    AFTER UPDATE
+    ON summoner
+    FOR EACH ROW
+BEGIN
+    UPDATE summoner SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
+
+-- game
+CREATE TABLE game_new (
+    id TEXT NOT NULL PRIMARY KEY,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    game_created_at INTEGER NOT NULL, -- riot timestamp
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    assists INTEGER NOT NULL,
+    deaths INTEGER NOT NULL,
+    kills INTEGER NOT NULL,
+    result TEXT NOT NULL DEFAULT 'loss' CHECK (result IN ('win', 'loss', 'remake')),
+    notified BOOLEAN NOT NULL DEFAULT 0,
+    champion_name TEXT NOT NULL,
+    game_mode TEXT NOT NULL,
+    lp_change INTEGER,
+    promotion_text TEXT,
+    game_duration INTEGER, -- seconds
+    lp_pending BOOLEAN NOT NULL DEFAULT 0,
+
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id) ON DELETE CASCADE
+);
+INSERT INTO game_new (id, summoner_id, game_created_at, created_at, updated_at, assists, deaths, kills, result, notified, champion_name, game_mode, lp_change, promotion_text, game_duration, lp_pending)
+SELECT id, summoner_id, game_created_at, created_at, updated_at, assists, deaths, kills, result, notified, champion_name, game_mode, lp_change, promotion_text, game_duration, lp_pending FROM game;
+DROP TABLE game;
+ALTER TABLE game_new RENAME TO game;
+
+CREATE TRIGGER [SetUpdatedAt_game]
+    AFTER UPDATE
+    ON game
+    FOR EACH ROW
+BEGIN
+    UPDATE game SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
+
+-- active_game never had a foreign key
+CREATE TABLE active_game_new (
+    id TEXT NOT NULL PRIMARY KEY,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    game_created_at INTEGER NOT NULL, -- riot timestamp
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    champion TEXT NOT NULL,
+    role TEXT NOT NULL,
+    spectate_link TEXT NOT NULL,
+    notified boolean NOT NULL,
+    game_mode TEXT NOT NULL,
+
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id) ON DELETE CASCADE
+);
+INSERT INTO active_game_new (id, summoner_id, game_created_at, created_at, champion, role, spectate_link, notified, game_mode)
+SELECT id, summoner_id, game_created_at, created_at, champion, role, spectate_link, notified, game_mode FROM active_game;
+DROP TABLE active_game;
+ALTER TABLE active_game_new RENAME TO active_game;
+
+-- rank_snapshot
+CREATE TABLE rank_snapshot_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    queue_type TEXT,
+    tier TEXT,
+    division TEXT,
+    lp INTEGER,
+
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id) ON DELETE CASCADE
+);
+INSERT INTO rank_snapshot_new (id, summoner_id, created_at, queue_type, tier, division, lp)
+SELECT id, summoner_id, created_at, queue_type, tier, division, lp FROM rank_snapshot;
+DROP TABLE rank_snapshot;
+ALTER TABLE rank_snapshot_new RENAME TO rank_snapshot;
+
+CREATE INDEX IF NOT EXISTS rank_snapshot_summoner_id_created_at
+    ON rank_snapshot (summoner_id, created_at);
+
+-- notification_outbox
+CREATE TABLE notification_outbox_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('game_finished', 'game_started')),
+    game_id TEXT NOT NULL, -- game.id or active_game.id depending on kind
+    highlights TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    last_error TEXT,
+    message_id TEXT, -- Discord message id once sent
+    sent_at INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+
+    UNIQUE (kind, game_id),
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE,
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id) ON DELETE CASCADE
+);
+INSERT INTO notification_outbox_new (id, guild_id, summoner_id, kind, game_id, highlights, status, attempts, next_attempt_at, last_error, message_id, sent_at, created_at, updated_at)
+SELECT id, guild_id, summoner_id, kind, game_id, highlights, status, attempts, next_attempt_at, last_error, message_id, sent_at, created_at, updated_at FROM notification_outbox;
+DROP TABLE notification_outbox;
+ALTER TABLE notification_outbox_new RENAME TO notification_outbox;
+
+CREATE INDEX IF NOT EXISTS notification_outbox_due ON notification_outbox (status, next_attempt_at);
+
+CREATE TRIGGER [SetUpdatedAt_notification_outbox]
+    AFTER UPDATE
+    ON notification_outbox
+    FOR EACH ROW
+BEGIN
+    UPDATE notification_outbox SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
+
+-- api_token
+CREATE TABLE api_token_new (
+    guild_id INTEGER NOT NULL PRIMARY KEY,
+    -- sha256 of the token, the token itself is only shown once
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE
+);
+INSERT INTO api_token_new (guild_id, token_hash, created_at)
+SELECT guild_id, token_hash, created_at FROM api_token;
+DROP TABLE api_token;
+ALTER TABLE api_token_new RENAME TO api_token;
+
+-- guild_template
+CREATE TABLE guild_template_new (
+    guild_id INTEGER NOT NULL PRIMARY KEY,
+    victory_title TEXT,
+    defeat_title TEXT,
+    in_game_title TEXT,
+    victory_color TEXT,
+    defeat_color TEXT,
+    in_game_color TEXT,
+    victory_emoji TEXT,
+    defeat_emoji TEXT,
+    in_game_emoji TEXT,
+    game_fields TEXT,
+    active_game_fields TEXT,
+    demotion_text TEXT,
+    remake_title TEXT,
+    remake_color TEXT,
+    remake_emoji TEXT,
+    remake_notifications TEXT,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE
+);
+INSERT INTO guild_template_new (guild_id, victory_title, defeat_title, in_game_title, victory_color, defeat_color, in_game_color, victory_emoji, defeat_emoji, in_game_emoji, game_fields, active_game_fields, demotion_text, remake_title, remake_color, remake_emoji, remake_notifications, created_at, updated_at)
+SELECT guild_id, victory_title, defeat_title, in_game_title, victory_color, defeat_color, in_game_color, victory_emoji, defeat_emoji, in_game_emoji, game_fields, active_game_fields, demotion_text, remake_title, remake_color, remake_emoji, remake_notifications, created_at, updated_at FROM guild_template;
+DROP TABLE guild_template;
+ALTER TABLE guild_template_new RENAME TO guild_template;
+
+CREATE TRIGGER [SetUpdatedAt_guild_template]
+    AFTER UPDATE
+    ON guild_template
+    FOR EACH ROW
+BEGIN
+    UPDATE guild_template SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
+
+-- guild_rule
+CREATE TABLE guild_rule_new (
+    guild_id INTEGER NOT NULL,
+    rule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now')),
+
+    PRIMARY KEY (guild_id, rule),
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE
+);
+INSERT INTO guild_rule_new (guild_id, rule, enabled, created_at, updated_at)
+SELECT guild_id, rule, enabled, created_at, updated_at FROM guild_rule;
+DROP TABLE guild_rule;
+ALTER TABLE guild_rule_new RENAME TO guild_rule;
+
+CREATE TRIGGER [SetUpdatedAt_guild_rule]
+    AFTER UPDATE
+    ON guild_rule
+    FOR EACH ROW
+BEGIN
+    UPDATE guild_rule SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- Games missed while offline are queued as catch_up rows, one per game, and
+-- posted as one summary per guild. SQLite can't change a CHECK so the table is
+-- rebuilt, migrations run with foreign keys off, see db::create_db.
+CREATE TABLE notification_outbox_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    summoner_id TEXT COLLATE NOCASE NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('game_finished', 'game_started', 'catch_up')),
+    game_id TEXT NOT NULL, -- game.id or active_game.id depending on kind
+    highlights TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    last_error TEXT,
+    message_id TEXT, -- Discord message id once sent
+    sent_at INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+
+    UNIQUE (kind, game_id),
+    FOREIGN KEY (guild_id) REFERENCES guild (id) ON DELETE CASCADE,
+    FOREIGN KEY (summoner_id) REFERENCES summoner (id) ON DELETE CASCADE
+);
+INSERT INTO notification_outbox_new (id, guild_id, summoner_id, kind, game_id, highlights, status, attempts, next_attempt_at, last_error, message_id, sent_at, created_at, updated_at)
+SELECT id, guild_id, summoner_id, kind, game_id, highlights, status, attempts, next_attempt_at, last_error, message_id, sent_at, created_at, updated_at FROM notification_outbox;
+DROP TABLE notification_outbox;
+ALTER TABLE notification_outbox_new RENAME TO notification_outbox;
+
+CREATE INDEX IF NOT EXISTS notification_outbox_due ON notification_outbox (status, next_attempt_at);
+
+CREATE TRIGGER [SetUpdatedAt_notification_outbox]
+    AFTER UPDATE
+    ON notification_outbox
+    FOR EACH ROW
+BEGIN
+    UPDATE notification_outbox SET updated_at = (strftime('%s', 'now')) WHERE updated_at = old.updated_at;
+END;
@@ -1,4 +1,11 @@
-use crate::dtos::{active_game_dto::ActiveGameDto, game_dto::GameDto, summoner_dto::SummonerDto};
+use std::{fmt, sync::Arc};
+
+use crate::{
+    clock::Clock,
+    dtos::{active_game_dto::ActiveGameDto, game_dto::GameDto, summoner_dto::SummonerDto},
+    metrics::metrics,
+    scraper_health::ScraperHealth,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 
@@ -14,4 +21,104 @@ where
     ) -> Result<Option<ActiveGameDto>>;
     async fn get_summoner(&self, summoner_name: &str, guild_id: i64) -> Result<SummonerDto>;
     async fn get_games(&self, summoner_id: &str) -> Result<Vec<GameDto>>;
+
+    /// Older match history, page 1 is the most recent games.
+    /// Strategies that can't page only have page 1.
+    async fn get_games_page(&self, summoner_id: &str, page: u32) -> Result<Vec<GameDto>> {
+        if page <= 1 {
+            self.get_games(summoner_id).await
+        } else {
+            Ok(vec![])
+        }
+    }
+}
+
+/// A selector matched nothing, usually because the site changed its markup.
+/// Strategies attach it as context so it can be told apart from network errors.
+#[derive(Debug)]
+pub struct SelectorError(pub &'static str);
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unable to select {}", self.0)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// Wraps another strategy and
+/// - counts successes/failures per method, and selector failures per selector
+/// - reports results to the scraper's circuit breaker
+pub struct InstrumentedApiStrategy {
+    inner: Arc<dyn ApiStrategy>,
+    health: Arc<ScraperHealth>,
+    clock: Arc<dyn Clock>,
+}
+
+impl InstrumentedApiStrategy {
+    pub fn new(
+        inner: Arc<dyn ApiStrategy>,
+        health: Arc<ScraperHealth>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            inner,
+            health,
+            clock,
+        }
+    }
+
+    /// `summoner` is whatever the method was called with, the id or the name
+    fn record<T>(&self, method: &'static str, summoner: &str, result: Result<T>) -> Result<T> {
+        metrics().record_scrape(method, result.is_ok());
+        if let Some(SelectorError(selector)) = result
+            .as_ref()
+            .err()
+            .and_then(|e| e.downcast_ref::<SelectorError>())
+        {
+            metrics().record_selector_failure(selector);
+        }
+        self.health
+            .record(method, summoner, &result, self.clock.now());
+        result
+    }
+}
+
+#[async_trait]
+impl ApiStrategy for InstrumentedApiStrategy {
+    async fn get_active_game(
+        &self,
+        summoner_id: &str,
+        summoner_name: &str,
+    ) -> Result<Option<ActiveGameDto>> {
+        self.record(
+            "get_active_game",
+            summoner_id,
+            self.inner.get_active_game(summoner_id, summoner_name).await,
+        )
+    }
+
+    async fn get_summoner(&self, summoner_name: &str, guild_id: i64) -> Result<SummonerDto> {
+        self.record(
+            "get_summoner",
+            summoner_name,
+            self.inner.get_summoner(summoner_name, guild_id).await,
+        )
+    }
+
+    async fn get_games(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+        self.record(
+            "get_games",
+            summoner_id,
+            self.inner.get_games(summoner_id).await,
+        )
+    }
+
+    async fn get_games_page(&self, summoner_id: &str, page: u32) -> Result<Vec<GameDto>> {
+        self.record(
+            "get_games_page",
+            summoner_id,
+            self.inner.get_games_page(summoner_id, page).await,
+        )
+    }
 }
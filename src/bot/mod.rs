@@ -1,37 +1,73 @@
 use anyhow::{Context as Ctx, Result};
-use serenity::model::prelude::{Guild, GuildId, ReactionType, Ready};
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::gateway::ConnectionStage;
+use serenity::http::Http;
+use serenity::model::event::ResumedEvent;
+use serenity::model::prelude::{
+    AttachmentType, Guild, GuildId, Permissions, ReactionType, Ready, RoleId,
+};
 use serenity::prelude::{Context, EventHandler, GatewayIntents, TypeMapKey};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::env;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::watch;
 
-use serenity::framework::standard::macros::{command, group};
-use serenity::framework::standard::{Args, CommandResult, StandardFramework};
+use serenity::framework::standard::macros::{check, command, group, hook};
+use serenity::framework::standard::{
+    Args, CommandOptions, CommandResult, DispatchError, Reason, StandardFramework,
+};
 use serenity::model::channel::Message;
 use serenity::{async_trait, Client};
 
-use crate::facade::Facade;
+use crate::{
+    export,
+    facade::{BackfillProgress, Facade},
+    leaderboard::LeaderboardEntry,
+    metrics::metrics,
+    rank,
+    render::{Template, TEMPLATE_KEYS},
+    util,
+};
 
-// TODO: clean this shitty macro up
-macro_rules! generate_facade_code {
-    ($ctx:ident, $facade:ident) => {
-        $facade = {
-            let data_read = $ctx.data.read().await;
-            data_read.get::<FacadeContainer>().unwrap().clone()
-        };
+/// Most games `!addUser --backfill` will go back
+static BACKFILL_MAX_GAMES: usize = 1000;
 
-        let mut $facade = $facade.write().await;
-    };
-}
+/// Default and maximum days for `!stats`
+static STATS_DEFAULT_DAYS: i64 = 30;
+static STATS_MAX_DAYS: i64 = 365;
+
+/// Largest file `!import` will download
+static IMPORT_MAX_BYTES: u64 = 25 * 1024 * 1024;
 
 struct FacadeContainer;
 
 impl TypeMapKey for FacadeContainer {
-    type Value = Arc<RwLock<Facade>>;
+    type Value = Arc<Facade>;
+}
+
+async fn get_facade(ctx: &Context) -> Arc<Facade> {
+    let data_read = ctx.data.read().await;
+    data_read.get::<FacadeContainer>().unwrap().clone()
 }
 
 #[group]
-#[commands(delete_user, add_user, init)]
+#[commands(
+    delete_user,
+    add_user,
+    init,
+    status,
+    api_token,
+    revoke_api_token,
+    template,
+    leaderboard,
+    stats,
+    rules,
+    backup,
+    export,
+    import,
+    manager_role
+)]
 struct General;
 
 struct Handler;
@@ -39,19 +75,25 @@ struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn ready(&self, _ctx: Context, _data_about_bot: Ready) {
-        println!("{} is connected!", _data_about_bot.user.name);
+        tracing::info!("{} is connected!", _data_about_bot.user.name);
+        metrics().set_discord_connected(true);
     }
 
-    async fn cache_ready(&self, ctx: Context, _guilds: Vec<GuildId>) {
-        let facade;
-        generate_facade_code!(ctx, facade);
+    async fn resume(&self, _ctx: Context, _: ResumedEvent) {
+        metrics().set_discord_connected(true);
+    }
 
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        metrics().set_discord_connected(event.new == ConnectionStage::Connected);
+    }
+
+    async fn cache_ready(&self, ctx: Context, _guilds: Vec<GuildId>) {
+        let facade = get_facade(&ctx).await;
         facade.start_workers(ctx.http.clone());
     }
 
     async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: bool) {
-        let facade;
-        generate_facade_code!(ctx, facade);
+        let facade = get_facade(&ctx).await;
 
         // copy guild name String
         let guild_name = guild.name.clone();
@@ -59,22 +101,40 @@ impl EventHandler for Handler {
         match facade.init_guild(guild.id.0 as i64, None, guild.name).await {
             Ok(_) => {}
             Err(e) => {
-                let _ = facade
-                    .log_error(&format!("Error initializing guild: {} - {}", e, guild_name))
-                    .await;
+                tracing::error!(
+                    guild_id = guild.id.0,
+                    error = %e,
+                    "Error initializing guild: {}",
+                    guild_name
+                );
             }
         }
     }
 }
 
-pub async fn start(facade: Facade) -> Result<()> {
+/// Runs until the client stops or a shutdown signal (SIGTERM/ctrl-c) is received
+pub async fn start(facade: Arc<Facade>) -> Result<()> {
     let prefix = env::var("BOT_PREFIX").context("unable to parse BOT_PREFIX from env file")?;
-    let framework = StandardFramework::new()
-        .configure(|c| c.prefix(prefix))
-        .group(&GENERAL_GROUP);
 
     // Login with a bot token from the environment
     let token = env::var("DISCORD_TOKEN").context("unable to parse DISCORD_TOKEN from env file")?;
+
+    // Owners of the bot application can run `owners_only` commands
+    let owners = match Http::new(&token).get_current_application_info().await {
+        Ok(info) => match info.team {
+            Some(team) => team.members.iter().map(|m| m.user.id).collect(),
+            None => HashSet::from([info.owner.id]),
+        },
+        Err(e) => {
+            tracing::warn!(error = %e, "unable to fetch application owners");
+            HashSet::new()
+        }
+    };
+
+    let framework = StandardFramework::new()
+        .configure(|c| c.prefix(prefix).owners(owners))
+        .on_dispatch_error(dispatch_error)
+        .group(&GENERAL_GROUP);
     let intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
 
     let mut client = Client::builder(token, intents)
@@ -86,22 +146,86 @@ pub async fn start(facade: Facade) -> Result<()> {
     // Set the facade in the client's data
     {
         let mut data = client.data.write().await;
-        data.insert::<FacadeContainer>(Arc::new(RwLock::new(facade)));
+        data.insert::<FacadeContainer>(facade);
     }
 
+    let shard_manager = client.shard_manager.clone();
+
     // start listening for events by starting a single shard
-    if let Err(why) = client.start().await {
-        return Err(anyhow::anyhow!("Error starting client: {:?}", why));
+    tokio::select! {
+        result = client.start() => {
+            if let Err(why) = result {
+                return Err(anyhow::anyhow!("Error starting client: {:?}", why));
+            }
+        }
+        _ = util::shutdown_signal() => {
+            shard_manager.lock().await.shutdown_all().await;
+        }
     }
 
     Ok(())
 }
 
+/// Tell people why a command didn't run instead of ignoring them
+#[hook]
+async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError, _command_name: &str) {
+    let reply = match error {
+        DispatchError::LackingPermissions(_) => "You need the Manage Server permission for that",
+        DispatchError::CheckFailed(_, Reason::User(reason)) => {
+            let _ = msg.reply(ctx, reason).await;
+            return;
+        }
+        _ => return,
+    };
+    let _ = msg.reply(ctx, reply).await;
+}
+
+// Manage Server, or the server's bot manager role set with `managerRole`
+#[check]
+#[name = "Manager"]
+async fn manager_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    _: &CommandOptions,
+) -> Result<(), Reason> {
+    match is_manager(ctx, msg).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Reason::User(
+            "You need the Manage Server permission or the bot manager role for that".to_string(),
+        )),
+        Err(e) => Err(Reason::Log(format!(
+            "unable to check manager permissions: {:#}",
+            e
+        ))),
+    }
+}
+
+async fn is_manager(ctx: &Context, msg: &Message) -> Result<bool> {
+    let guild_id = msg.guild_id.context("No guild id found")?;
+    let member = msg.member(ctx).await?;
+    let permissions = member.permissions(ctx)?;
+    let guild = get_facade(ctx).await.get_guild(guild_id.0 as i64).await?;
+
+    Ok(can_manage(
+        permissions,
+        &member.roles,
+        guild.manager_role_id,
+    ))
+}
+
+/// Administrators always have Manage Server
+fn can_manage(permissions: Permissions, roles: &[RoleId], manager_role_id: Option<i64>) -> bool {
+    permissions.manage_guild()
+        || manager_role_id.is_some_and(|role_id| roles.iter().any(|r| r.0 as i64 == role_id))
+}
+
 #[command]
+#[only_in(guilds)]
+#[checks(Manager)]
 #[description("Initialize the guild chat channel")]
 async fn init(ctx: &Context, msg: &Message) -> CommandResult {
-    let facade;
-    generate_facade_code!(ctx, facade);
+    let facade = get_facade(ctx).await;
 
     let guild = msg.guild(&ctx.cache).context("No guild found")?;
     let channel_id = msg.channel_id.0;
@@ -124,34 +248,100 @@ async fn init(ctx: &Context, msg: &Message) -> CommandResult {
 
 #[command]
 #[aliases("addUser")]
+#[only_in(guilds)]
+#[usage("<summoner> [--backfill <games>]")]
 async fn add_user(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    let facade;
-    generate_facade_code!(ctx, facade);
+    let facade = get_facade(ctx).await;
 
     let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
 
+    let Some((name, backfill)) = parse_add_user_args(args.rest()) else {
+        msg.reply(ctx, "Usage: addUser <summoner> [--backfill <games>]")
+            .await?;
+        return Ok(());
+    };
+
     msg.react(&ctx.http, ReactionType::Unicode("👍".to_string()))
         .await?;
 
-    match facade.add_user(args.rest(), guild_id).await {
-        Ok(_) => {
-            msg.reply(ctx, "User added!").await?;
-        }
+    let added_by = msg.author.id.0 as i64;
+    let summoner = match facade.add_user(&name, guild_id, Some(added_by)).await {
+        Ok(summoner) => summoner,
         Err(e) => {
             msg.reply(ctx, format!("Error adding user: {}", e)).await?;
+            return Ok(());
         }
-    }
+    };
+
+    let Some(max_games) = backfill else {
+        msg.reply(ctx, "User added!").await?;
+        return Ok(());
+    };
+
+    let mut reply = msg
+        .reply(
+            ctx,
+            format!("User added! Backfilling up to {} games...", max_games),
+        )
+        .await?;
+
+    // Paging takes a while, keep editing the reply instead of blocking the command
+    let http = ctx.http.clone();
+    tokio::spawn(async move {
+        let (progress_tx, mut progress_rx) = watch::channel(BackfillProgress::default());
+        let backfill = facade.backfill_games(&summoner.id, max_games, &progress_tx);
+        tokio::pin!(backfill);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut backfill => break result,
+                Ok(()) = progress_rx.changed() => {
+                    let progress = *progress_rx.borrow_and_update();
+                    let content = format!(
+                        "User added! Backfilling... {}/{} games (page {})",
+                        progress.games, max_games, progress.pages
+                    );
+                    if let Err(e) = reply.edit(&http, |m| m.content(content)).await {
+                        tracing::warn!(error = %e, "Unable to update backfill progress");
+                    }
+                }
+            }
+        };
+
+        let content = match result {
+            Ok(progress) => format!(
+                "User added! Backfilled {} games, {} new",
+                progress.games, progress.inserted
+            ),
+            Err(e) => {
+                let progress = *progress_rx.borrow();
+                format!(
+                    "User added! Backfill stopped after {} games: {}",
+                    progress.games, e
+                )
+            }
+        };
+        if let Err(e) = reply.edit(&http, |m| m.content(content)).await {
+            tracing::warn!(error = %e, "Unable to update backfill progress");
+        }
+    });
 
     Ok(())
 }
 
 #[command]
 #[aliases("deleteUser")]
+#[only_in(guilds)]
+#[description("Stop tracking a summoner. Only managers can remove summoners someone else added.")]
 async fn delete_user(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    let facade;
-    generate_facade_code!(ctx, facade);
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+    let is_manager = is_manager(ctx, msg).await?;
 
-    match facade.delete_user(args.rest()).await {
+    match facade
+        .untrack_user(guild_id, args.rest(), msg.author.id.0 as i64, is_manager)
+        .await
+    {
         Ok(_) => {
             msg.reply(ctx, "User deleted!").await?;
         }
@@ -163,3 +353,646 @@ async fn delete_user(ctx: &Context, msg: &Message, args: Args) -> CommandResult
 
     Ok(())
 }
+
+#[command]
+#[description("Show worker health")]
+async fn status(ctx: &Context, msg: &Message) -> CommandResult {
+    let facade = get_facade(ctx).await;
+
+    let format_time = |t: Option<i64>| {
+        t.map(|t| format!("<t:{}:R>", t))
+            .unwrap_or("never".to_string())
+    };
+
+    let lines: Vec<String> = facade
+        .worker_statuses()
+        .iter()
+        .map(|s| {
+            let mut line = format!(
+                "{} **{}** - last success {}, last error {}, restarts {}",
+                if s.running { "🟢" } else { "🔴" },
+                s.name,
+                format_time(s.last_success_at),
+                format_time(s.last_error_at),
+                s.restarts
+            );
+            if let Some(e) = &s.last_error {
+                line.push_str(&format!("\n> {}", e));
+            }
+            line
+        })
+        .collect();
+
+    let mut content = if lines.is_empty() {
+        "Workers have not started yet".to_string()
+    } else {
+        lines.join("\n")
+    };
+
+    for pause in facade.scraping_paused() {
+        content.push_str(&format!(
+            "\n⏸️ Scraping paused {}, `{}` keeps failing\n> {}",
+            format_time(Some(pause.since)),
+            pause.method,
+            pause.error
+        ));
+    }
+
+    let (pending, dead) = facade.get_undelivered_counts().await?;
+    content.push_str(&format!(
+        "\nNotifications: {} pending, {} failed",
+        pending, dead
+    ));
+
+    msg.reply(ctx, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("apiToken")]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[description("Create a REST api token for this guild, replacing the old one. Sent by DM.")]
+async fn api_token(ctx: &Context, msg: &Message) -> CommandResult {
+    let facade = get_facade(ctx).await;
+
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+    let token = facade.create_api_token(guild_id).await?;
+
+    let dm = msg
+        .author
+        .direct_message(ctx, |m| {
+            m.content(format!(
+                "API token for this server (any previous token no longer works):\n`{}`",
+                token
+            ))
+        })
+        .await;
+
+    match dm {
+        Ok(_) => {
+            msg.reply(ctx, "API token sent by DM!").await?;
+        }
+        Err(e) => {
+            // Don't leave a token around that nobody has seen
+            facade.revoke_api_token(guild_id).await?;
+            msg.reply(ctx, format!("Unable to DM you the token: {}", e))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command]
+#[aliases("revokeApiToken")]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[description("Revoke this guild's REST api token")]
+async fn revoke_api_token(ctx: &Context, msg: &Message) -> CommandResult {
+    let facade = get_facade(ctx).await;
+
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+    facade.revoke_api_token(guild_id).await?;
+    msg.reply(ctx, "API token revoked!").await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[usage("[set <key> <value> | reset [key]]")]
+#[description("Show or customize how notifications look")]
+async fn template(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    let result = match action.as_str() {
+        "" => {
+            let overrides = facade.get_template_overrides(guild_id).await?;
+            let template = Template::from_overrides(&overrides);
+            msg.reply(ctx, format_template(&template)).await?;
+            return Ok(());
+        }
+        "set" => {
+            let key = args.single::<String>().unwrap_or_default();
+            facade
+                .set_template_override(guild_id, &key, Some(args.rest()))
+                .await
+        }
+        "reset" => match args.single::<String>() {
+            Ok(key) => facade.set_template_override(guild_id, &key, None).await,
+            Err(_) => facade.reset_template(guild_id).await,
+        },
+        _ => Err(anyhow::anyhow!(
+            "usage: template [set <key> <value> | reset [key]]. Keys: {}",
+            TEMPLATE_KEYS.join(", ")
+        )),
+    };
+
+    match result {
+        Ok(_) => {
+            msg.reply(ctx, "Template updated!").await?;
+        }
+        Err(e) => {
+            msg.reply(ctx, format!("Error updating template: {}", e))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_template(template: &Template) -> String {
+    let fields = |fields: &[crate::render::FieldKind]| {
+        fields
+            .iter()
+            .map(|f| f.name())
+            .collect::<Vec<&str>>()
+            .join(",")
+    };
+    let emoji = |emoji: &Option<String>| emoji.clone().unwrap_or("none".to_string());
+
+    [
+        format!("**victory_title** {}", template.victory_title),
+        format!("**defeat_title** {}", template.defeat_title),
+        format!("**remake_title** {}", template.remake_title),
+        format!("**in_game_title** {}", template.in_game_title),
+        format!("**victory_color** #{:06x}", template.victory_color),
+        format!("**defeat_color** #{:06x}", template.defeat_color),
+        format!("**remake_color** #{:06x}", template.remake_color),
+        format!("**in_game_color** #{:06x}", template.in_game_color),
+        format!("**victory_emoji** {}", emoji(&template.victory_emoji)),
+        format!("**defeat_emoji** {}", emoji(&template.defeat_emoji)),
+        format!("**remake_emoji** {}", emoji(&template.remake_emoji)),
+        format!("**in_game_emoji** {}", emoji(&template.in_game_emoji)),
+        format!("**game_fields** {}", fields(&template.game_fields)),
+        format!(
+            "**active_game_fields** {}",
+            fields(&template.active_game_fields)
+        ),
+        format!("**demotion_text** {}", template.demotion_text),
+        format!(
+            "**remake_notifications** {}",
+            if template.suppress_remakes {
+                "suppress"
+            } else {
+                "label"
+            }
+        ),
+    ]
+    .join("\n")
+}
+
+#[command]
+#[only_in(guilds)]
+#[usage("[queue]")]
+#[description("Tracked summoners ordered by rank, e.g. `leaderboard solo`")]
+async fn leaderboard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let queue = Some(args.rest().trim()).filter(|q| !q.is_empty());
+    let entries = facade.get_leaderboard(guild_id, queue).await?;
+
+    if entries.is_empty() {
+        msg.reply(ctx, "No tracked summoners found").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = entries.iter().map(format_leaderboard_entry).collect();
+    let title = match queue {
+        Some(queue) => format!("Leaderboard - {}", queue),
+        None => "Leaderboard".to_string(),
+    };
+
+    msg.channel_id
+        .send_message(ctx, |m| {
+            m.reference_message(msg)
+                .embed(|e| e.title(title).description(lines.join("\n")))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// e.g. "**1.** Faker - Challenger 1200 lp ▲2 (+35 lp since yesterday, +400 lp this season)"
+fn format_leaderboard_entry(entry: &LeaderboardEntry) -> String {
+    let summoner = &entry.summoner;
+    let position = entry
+        .position
+        .map(|p| format!("**{}.**", p))
+        .unwrap_or("-".to_string());
+    let rank = rank::format_rank(
+        summoner.tier.as_deref(),
+        summoner.division.as_deref(),
+        summoner.lp,
+    );
+
+    let mut line = format!("{} {} - {}", position, summoner.name, rank);
+
+    match entry.movement() {
+        Some(m) if m > 0 => line.push_str(&format!(" ▲{}", m)),
+        Some(m) if m < 0 => line.push_str(&format!(" ▼{}", -m)),
+        _ => {}
+    }
+
+    let mut changes = vec![];
+    if let Some(lp) = entry.lp_since_yesterday.filter(|lp| *lp != 0) {
+        changes.push(format!("{:+} lp since yesterday", lp));
+    }
+    if let Some(lp) = entry.lp_since_season_start {
+        changes.push(format!("{:+} lp this season", lp));
+    }
+    if !changes.is_empty() {
+        line.push_str(&format!(" ({})", changes.join(", ")));
+    }
+
+    line
+}
+
+#[command]
+#[only_in(guilds)]
+#[usage("<summoner> [days] [queue]")]
+#[description(
+    "Win rate, KDA, top champions, streaks and LP for a summoner, e.g. `stats Faker 7 solo`"
+)]
+async fn stats(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let Some((summoner_name, days, queue)) = parse_stats_args(args.rest()) else {
+        msg.reply(ctx, "Usage: stats <summoner> [days] [queue]")
+            .await?;
+        return Ok(());
+    };
+
+    let (summoner, stats) = match facade
+        .get_stats(guild_id, &summoner_name, days, queue.as_deref())
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            msg.reply(ctx, format!("Error getting stats: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut title = format!("Last {} days", days);
+    if let Some(queue) = &queue {
+        title.push_str(&format!(" - {}", queue));
+    }
+
+    if stats.games == 0 {
+        msg.reply(ctx, format!("{}: no games found", title)).await?;
+        return Ok(());
+    }
+
+    let (kills, deaths, assists) = stats.average_kda();
+    let champions: Vec<String> = stats
+        .champions
+        .iter()
+        .map(|c| format!("{} - {} games, {:.0}%", c.name, c.games, c.win_rate()))
+        .collect();
+    let mut record = format!("{}W {}L", stats.wins, stats.losses);
+    if stats.remakes > 0 {
+        record.push_str(&format!(", {} remakes", stats.remakes));
+    }
+    let author_url = util::get_author_url(&summoner.name)?;
+
+    msg.channel_id
+        .send_message(ctx, |m| {
+            m.reference_message(msg).embed(|e| {
+                e.author(|a| {
+                    a.name(&summoner.name)
+                        .icon_url(&summoner.icon_url)
+                        .url(author_url)
+                })
+                .title(title)
+                .field(
+                    "Win rate",
+                    format!("{:.0}% ({})", stats.win_rate(), record),
+                    true,
+                )
+                .field(
+                    "KDA",
+                    format!(
+                        "{:.1}/{:.1}/{:.1} ({:.2})",
+                        kills,
+                        deaths,
+                        assists,
+                        stats.kda()
+                    ),
+                    true,
+                )
+                .field("Net LP", format!("{:+}", stats.net_lp), true)
+                .field(
+                    "Longest streaks",
+                    format!(
+                        "{} wins, {} losses",
+                        stats.longest_win_streak, stats.longest_loss_streak
+                    ),
+                    true,
+                )
+                .field("Most played", champions.join("\n"), false)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Summoner names can contain spaces so the first number after the
+/// name is the days, anything after that is the queue.
+///
+/// e.g. "Hide on bush 7 solo" -> ("Hide on bush", 7, Some("solo"))
+fn parse_stats_args(args: &str) -> Option<(String, i64, Option<String>)> {
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let days_index = words
+        .iter()
+        .skip(1)
+        .position(|w| w.parse::<i64>().is_ok())
+        .map(|i| i + 1);
+
+    let (name, days, queue) = match days_index {
+        Some(i) => (
+            words[..i].join(" "),
+            words[i].parse::<i64>().ok()?,
+            Some(words[i + 1..].join(" ")).filter(|q| !q.is_empty()),
+        ),
+        None => (words.join(" "), STATS_DEFAULT_DAYS, None),
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((name, days.clamp(1, STATS_MAX_DAYS), queue))
+}
+
+/// The summoner name and how many games to backfill, if asked for.
+///
+/// e.g. "Hide on bush --backfill 100" -> ("Hide on bush", Some(100))
+pub(crate) fn parse_add_user_args(args: &str) -> Option<(String, Option<usize>)> {
+    let (name, backfill) = match args.split_once("--backfill") {
+        Some((name, games)) => (name, Some(games.trim().parse::<usize>().ok()?)),
+        None => (args, None),
+    };
+
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((
+        name.to_string(),
+        backfill
+            .filter(|&g| g > 0)
+            .map(|g| g.min(BACKFILL_MAX_GAMES)),
+    ))
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[usage("[enable|disable <rule>]")]
+#[description("Show or toggle streak and milestone highlights")]
+async fn rules(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    let rule = args.single::<String>().unwrap_or_default();
+
+    let enabled = match action.as_str() {
+        "" => {
+            let lines: Vec<String> = facade
+                .get_rules(guild_id)
+                .await?
+                .iter()
+                .map(|(rule, enabled)| {
+                    format!(
+                        "{} **{}** - {}",
+                        if *enabled { "🟢" } else { "🔴" },
+                        rule.name(),
+                        rule.description()
+                    )
+                })
+                .collect();
+            msg.reply(ctx, lines.join("\n")).await?;
+            return Ok(());
+        }
+        "enable" => true,
+        "disable" => false,
+        _ => {
+            msg.reply(ctx, "Usage: rules [enable|disable <rule>]")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    match facade.set_rule_enabled(guild_id, &rule, enabled).await {
+        Ok(_) => {
+            msg.reply(ctx, "Rule updated!").await?;
+        }
+        Err(e) => {
+            msg.reply(ctx, format!("Error updating rule: {}", e))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command]
+#[owners_only]
+#[description("Back up the whole database on the bot's server")]
+async fn backup(ctx: &Context, msg: &Message) -> CommandResult {
+    let facade = get_facade(ctx).await;
+
+    match facade.backup(None).await {
+        Ok(path) => {
+            msg.reply(ctx, format!("Backed up to `{}`", path)).await?;
+        }
+        Err(e) => {
+            msg.reply(ctx, format!("Error backing up: {:#}", e)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[usage("[json|csv]")]
+#[description("Export this server's summoners and games. Only JSON can be imported again.")]
+async fn export(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let format = args.single::<String>().unwrap_or_default().to_lowercase();
+    let guild_export = facade.export_guild(guild_id).await?;
+    let (data, filename) = match format.as_str() {
+        "" | "json" => (
+            serde_json::to_vec_pretty(&guild_export)?,
+            format!("lol-tracker-{}.json", guild_id),
+        ),
+        "csv" => (
+            export::to_csv(&guild_export).into_bytes(),
+            format!("lol-tracker-{}.csv", guild_id),
+        ),
+        _ => {
+            msg.reply(ctx, "Usage: export [json|csv]").await?;
+            return Ok(());
+        }
+    };
+
+    msg.channel_id
+        .send_message(ctx, |m| {
+            m.content(format!(
+                "Exported {} summoners",
+                guild_export.summoners.len()
+            ))
+            .add_file(AttachmentType::Bytes {
+                data: Cow::Owned(data),
+                filename,
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Manager)]
+#[description("Import summoners and games from a JSON export attached to the message")]
+async fn import(ctx: &Context, msg: &Message) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let Some(attachment) = msg.attachments.first() else {
+        msg.reply(ctx, "Attach a JSON file from `export`").await?;
+        return Ok(());
+    };
+    if attachment.size > IMPORT_MAX_BYTES {
+        msg.reply(ctx, "That file is too large to import").await?;
+        return Ok(());
+    }
+
+    let result = async {
+        let data = attachment.download().await?;
+        let guild_export = serde_json::from_slice(&data).context("not a JSON export")?;
+        facade.import_guild(guild_export, Some(guild_id)).await
+    }
+    .await;
+
+    match result {
+        Ok(summary) => {
+            let mut reply = format!("Imported {}", summary);
+            if summary.skipped_summoners > 0 {
+                reply.push_str(&format!(
+                    ", skipped {} summoners tracked by another server",
+                    summary.skipped_summoners
+                ));
+            }
+            msg.reply(ctx, reply).await?;
+        }
+        Err(e) => {
+            // The export can be imported again, what's already there is skipped
+            msg.reply(
+                ctx,
+                format!("Error importing: {:#}. Run it again to retry", e),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[command]
+#[aliases("managerRole")]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+#[usage("[@role | clear]")]
+#[description("Show or set the role that can run admin commands without Manage Server")]
+async fn manager_role(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let facade = get_facade(ctx).await;
+    let guild_id = msg.guild_id.context("No guild id found")?.0 as i64;
+
+    let role_id = match args.rest().trim() {
+        "" => {
+            let content = match facade.get_guild(guild_id).await?.manager_role_id {
+                Some(role_id) => format!("Members with <@&{}> can manage the bot", role_id),
+                None => "Only members with Manage Server can manage the bot".to_string(),
+            };
+            msg.reply(ctx, content).await?;
+            return Ok(());
+        }
+        "clear" => None,
+        _ => match msg.mention_roles.first() {
+            Some(role_id) => Some(role_id.0 as i64),
+            None => {
+                msg.reply(ctx, "Usage: managerRole [@role | clear]").await?;
+                return Ok(());
+            }
+        },
+    };
+
+    facade.set_manager_role(guild_id, role_id).await?;
+    msg.reply(ctx, "Manager role updated!").await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_shows_every_key() {
+        let shown = format_template(&Template::default());
+        for key in TEMPLATE_KEYS {
+            assert!(
+                shown.contains(&format!("**{}**", key)),
+                "{} is missing",
+                key
+            );
+        }
+        assert!(shown.contains("**remake_notifications** label"));
+    }
+
+    #[test]
+    fn parses_stats_args() {
+        let with = |name: &str, days, queue: Option<&str>| {
+            Some((name.to_string(), days, queue.map(str::to_string)))
+        };
+
+        assert_eq!(
+            parse_stats_args("Hide on bush"),
+            with("Hide on bush", STATS_DEFAULT_DAYS, None)
+        );
+        assert_eq!(
+            parse_stats_args("Hide on bush 7"),
+            with("Hide on bush", 7, None)
+        );
+        assert_eq!(
+            parse_stats_args("Hide on bush 7 solo"),
+            with("Hide on bush", 7, Some("solo"))
+        );
+        assert_eq!(
+            parse_stats_args("Faker 1000 ranked flex"),
+            with("Faker", STATS_MAX_DAYS, Some("ranked flex"))
+        );
+        assert_eq!(parse_stats_args("Faker 0"), with("Faker", 1, None));
+        // The first word is always part of the name, even if it's a number
+        assert_eq!(parse_stats_args("1234 5"), with("1234", 5, None));
+        assert_eq!(parse_stats_args("  "), None);
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::{bail, Context, Result};
+
+/// Games older than this are never caught up on
+static DEFAULT_MAX_AGE_HOURS: i64 = 24;
+
+/// What happens to games played while the bot was offline, `CATCH_UP` in the env file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUpPolicy {
+    /// Mark them as notified without posting anything
+    Drop,
+    /// One "while I was away" message per guild
+    Summary,
+}
+
+impl CatchUpPolicy {
+    /// `summary` unless set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("CATCH_UP")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "summary" => Ok(CatchUpPolicy::Summary),
+            "drop" => Ok(CatchUpPolicy::Drop),
+            other => bail!("invalid CATCH_UP: {}, expected summary or drop", other),
+        }
+    }
+}
+
+/// `CATCH_UP_MAX_AGE_HOURS` in seconds
+pub fn max_age() -> Result<i64> {
+    let hours = match std::env::var("CATCH_UP_MAX_AGE_HOURS")
+        .ok()
+        .filter(|h| !h.is_empty())
+    {
+        Some(hours) => hours
+            .parse::<i64>()
+            .context("unable to parse CATCH_UP_MAX_AGE_HOURS from env file")?,
+        None => DEFAULT_MAX_AGE_HOURS,
+    };
+
+    Ok(hours * 60 * 60)
+}
@@ -0,0 +1,363 @@
+use std::fs;
+
+use anyhow::{bail, Context, Result};
+use tokio::sync::watch;
+
+use crate::{
+    bot, export,
+    facade::{BackfillProgress, Facade},
+    rank,
+};
+
+pub static USAGE: &str = "\
+usage: lol-tracker [command]
+
+  run                                        start the bot (default)
+  migrate                                    apply database migrations and exit
+  add-user <guild id> <summoner> [--backfill <games>]
+  remove-user <guild id> <summoner>
+  list [guild id]                            tracked summoners per guild
+  poll-once <summoner>                       scrape a tracked summoner now and store new games
+  check-scraper [summoner]                   check the scraper still understands the site
+  backup [path]                              consistent copy of the database
+  export <guild id> <file>                   .csv files get CSV, anything else JSON
+  import <file> [guild id]                   a JSON export, into the exported guild unless given
+  help";
+
+/// What the binary was asked to do
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Migrate,
+    AddUser {
+        guild_id: i64,
+        summoner: String,
+        backfill: Option<usize>,
+    },
+    RemoveUser {
+        guild_id: i64,
+        summoner: String,
+    },
+    List {
+        guild_id: Option<i64>,
+    },
+    PollOnce {
+        summoner: String,
+    },
+    CheckScraper {
+        summoner: Option<String>,
+    },
+    Backup {
+        path: Option<String>,
+    },
+    Export {
+        guild_id: i64,
+        file: String,
+    },
+    Import {
+        file: String,
+        guild_id: Option<i64>,
+    },
+    Help,
+}
+
+impl Command {
+    /// Arguments after the binary name, summoner names may span several
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let command = match args.as_slice() {
+            [] | ["run"] => Command::Run,
+            ["migrate"] => Command::Migrate,
+            ["add-user", guild_id, rest @ ..] if !rest.is_empty() => {
+                let Some((summoner, backfill)) = bot::parse_add_user_args(&rest.join(" ")) else {
+                    bail!("usage: add-user <guild id> <summoner> [--backfill <games>]");
+                };
+                Command::AddUser {
+                    guild_id: parse_guild_id(guild_id)?,
+                    summoner,
+                    backfill,
+                }
+            }
+            ["remove-user", guild_id, rest @ ..] if !rest.is_empty() => Command::RemoveUser {
+                guild_id: parse_guild_id(guild_id)?,
+                summoner: rest.join(" "),
+            },
+            ["list"] => Command::List { guild_id: None },
+            ["list", guild_id] => Command::List {
+                guild_id: Some(parse_guild_id(guild_id)?),
+            },
+            ["poll-once", rest @ ..] if !rest.is_empty() => Command::PollOnce {
+                summoner: rest.join(" "),
+            },
+            ["check-scraper", rest @ ..] => Command::CheckScraper {
+                summoner: (!rest.is_empty()).then(|| rest.join(" ")),
+            },
+            ["backup"] => Command::Backup { path: None },
+            ["backup", path] => Command::Backup {
+                path: Some(path.to_string()),
+            },
+            ["export", guild_id, file] => Command::Export {
+                guild_id: parse_guild_id(guild_id)?,
+                file: file.to_string(),
+            },
+            ["import", file] => Command::Import {
+                file: file.to_string(),
+                guild_id: None,
+            },
+            ["import", file, guild_id] => Command::Import {
+                file: file.to_string(),
+                guild_id: Some(parse_guild_id(guild_id)?),
+            },
+            ["help" | "--help" | "-h"] => Command::Help,
+            _ => bail!("{}", USAGE),
+        };
+
+        Ok(command)
+    }
+}
+
+/// Everything except `run`, `migrate` and `help`, which main handles
+/// before or instead of building the facade
+pub async fn run(facade: &Facade, command: Command) -> Result<()> {
+    match command {
+        Command::AddUser {
+            guild_id,
+            summoner,
+            backfill,
+        } => add_user(facade, guild_id, &summoner, backfill).await,
+        Command::RemoveUser { guild_id, summoner } => {
+            facade.delete_user(guild_id, &summoner).await?;
+            println!("Removed {}", summoner);
+            Ok(())
+        }
+        Command::List { guild_id } => list(facade, guild_id).await,
+        Command::PollOnce { summoner } => poll_once(facade, &summoner).await,
+        Command::CheckScraper { summoner } => check_scraper(facade, summoner.as_deref()).await,
+        Command::Backup { path } => {
+            let path = facade.backup(path.as_deref()).await?;
+            println!("Backed up database to {}", path);
+            Ok(())
+        }
+        Command::Export { guild_id, file } => {
+            let export = facade.export_guild(guild_id).await?;
+            let contents = if file.to_lowercase().ends_with(".csv") {
+                export::to_csv(&export)
+            } else {
+                serde_json::to_string_pretty(&export)?
+            };
+            fs::write(&file, contents).with_context(|| format!("unable to write {}", file))?;
+            println!(
+                "Exported {} summoners of guild {} to {}",
+                export.summoners.len(),
+                guild_id,
+                file
+            );
+            Ok(())
+        }
+        Command::Import { file, guild_id } => import(facade, &file, guild_id).await,
+        Command::Run | Command::Migrate | Command::Help => unreachable!("handled in main"),
+    }
+}
+
+async fn add_user(
+    facade: &Facade,
+    guild_id: i64,
+    summoner_name: &str,
+    backfill: Option<usize>,
+) -> Result<()> {
+    // The bot creates guilds when it joins them, summoners need one to belong to
+    facade.get_guild(guild_id).await.with_context(|| {
+        format!(
+            "guild {} isn't known yet, invite the bot to it first",
+            guild_id
+        )
+    })?;
+
+    let summoner = facade.add_user(summoner_name, guild_id, None).await?;
+    println!(
+        "Added {} ({})",
+        summoner.name,
+        rank::format_rank(
+            summoner.tier.as_deref(),
+            summoner.division.as_deref(),
+            summoner.lp
+        )
+    );
+
+    if let Some(max_games) = backfill {
+        let (progress, _) = watch::channel(BackfillProgress::default());
+        let result = facade
+            .backfill_games(&summoner.id, max_games, &progress)
+            .await?;
+        println!(
+            "Backfilled {} games from {} pages, {} were new",
+            result.games, result.pages, result.inserted
+        );
+    }
+
+    Ok(())
+}
+
+async fn list(facade: &Facade, guild_id: Option<i64>) -> Result<()> {
+    let guilds = match guild_id {
+        Some(guild_id) => vec![facade.get_guild(guild_id).await?],
+        None => facade.get_guilds().await?,
+    };
+
+    for guild in guilds {
+        let channel = guild
+            .chat_channel_id
+            .map(|c| format!("channel {}", c))
+            .unwrap_or("no channel, run init".to_string());
+        println!("{} ({}), {}", guild.name, guild.id, channel);
+
+        for summoner in facade.get_summoners_for_guild(guild.id).await? {
+            println!(
+                "  {} - {}",
+                summoner.name,
+                rank::format_rank(
+                    summoner.tier.as_deref(),
+                    summoner.division.as_deref(),
+                    summoner.lp
+                )
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn poll_once(facade: &Facade, summoner_name: &str) -> Result<()> {
+    let report = facade.poll_once(summoner_name).await?;
+
+    println!(
+        "{} ({})",
+        report.summoner.name,
+        rank::format_rank(
+            report.summoner.tier.as_deref(),
+            report.summoner.division.as_deref(),
+            report.summoner.lp
+        )
+    );
+    for game in &report.games {
+        let lp = game
+            .lp_change
+            .map(|lp| format!(" {:+} lp", lp))
+            .unwrap_or_default();
+        println!(
+            "  {} {} {} {}/{}/{}{}",
+            game.game_mode,
+            String::from(game.result),
+            game.champion_name,
+            game.kills,
+            game.deaths,
+            game.assists,
+            lp
+        );
+    }
+    println!(
+        "{} games scraped, {} new",
+        report.games.len(),
+        report.new_games
+    );
+    match report.active_game {
+        Some(active_game) => println!(
+            "In game as {} ({})",
+            active_game.champion, active_game.game_mode
+        ),
+        None => println!("Not in game"),
+    }
+
+    Ok(())
+}
+
+async fn check_scraper(facade: &Facade, summoner_name: Option<&str>) -> Result<()> {
+    let checks = facade.check_scraper(summoner_name).await?;
+
+    let mut failed = 0;
+    for check in checks {
+        match check.result {
+            Ok(found) => println!("ok    {}: {}", check.name, found),
+            Err(e) => {
+                failed += 1;
+                println!("FAIL  {}: {:#}", check.name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} scraper checks failed", failed);
+    }
+    Ok(())
+}
+
+async fn import(facade: &Facade, file: &str, guild_id: Option<i64>) -> Result<()> {
+    let contents = fs::read_to_string(file).with_context(|| format!("unable to read {}", file))?;
+    let export = serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not a JSON export", file))?;
+    let summary = facade.import_guild(export, guild_id).await?;
+    println!(
+        "Imported {}, skipped {} summoners tracked by another guild",
+        summary, summary.skipped_summoners
+    );
+    Ok(())
+}
+
+fn parse_guild_id(guild_id: &str) -> Result<i64> {
+    guild_id
+        .parse()
+        .with_context(|| format!("invalid guild id: {}", guild_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command> {
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        Command::parse(&args)
+    }
+
+    #[test]
+    fn parses_commands() -> Result<()> {
+        assert_eq!(parse("")?, Command::Run);
+        assert_eq!(parse("run")?, Command::Run);
+        assert_eq!(
+            parse("add-user 42 Hide on bush --backfill 50")?,
+            Command::AddUser {
+                guild_id: 42,
+                summoner: "Hide on bush".to_string(),
+                backfill: Some(50),
+            }
+        );
+        assert_eq!(
+            parse("poll-once Faker")?,
+            Command::PollOnce {
+                summoner: "Faker".to_string()
+            }
+        );
+        assert_eq!(
+            parse("check-scraper")?,
+            Command::CheckScraper { summoner: None }
+        );
+        assert_eq!(
+            parse("import guild.json 42")?,
+            Command::Import {
+                file: "guild.json".to_string(),
+                guild_id: Some(42),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse("add-user").is_err());
+        assert!(parse("add-user not-a-guild Faker").is_err());
+        assert!(parse("remove-user Faker").is_err());
+        assert!(parse("poll-once").is_err());
+        assert!(parse("export 42").is_err());
+        assert!(parse("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use url::Url;
+
+use crate::dtos::champion_dto::ChampionDto;
+
+static DATA_DRAGON_URL: &str = "https://ddragon.leagueoflegends.com";
+
+/// Offline fallback used until the first successful Data Dragon sync
+static BUNDLED_CHAMPION_JSON: &str = include_str!("../../assets/champion.json");
+
+/// Data Dragon champion.json
+#[derive(Deserialize)]
+struct ChampionJson {
+    version: String,
+    data: HashMap<String, ChampionJsonEntry>,
+}
+
+#[derive(Deserialize)]
+struct ChampionJsonEntry {
+    id: String,
+    key: String,
+    name: String,
+}
+
+/// Champion metadata lookup keyed by display name and Data Dragon id.
+///
+/// Scraped champion names ("Wukong", "Nunu & Willump", "Renata Glasc")
+/// don't always match the Data Dragon id ("MonkeyKing", "Nunu", "Renata"),
+/// so both are normalized and indexed.
+pub struct ChampionRegistry {
+    version: String,
+    champions: Vec<ChampionDto>,
+    index: HashMap<String, usize>,
+}
+
+impl ChampionRegistry {
+    pub fn new(champions: Vec<ChampionDto>) -> Result<Self> {
+        let version = champions
+            .iter()
+            .map(|c| c.version.clone())
+            .max_by(|a, b| compare_versions(a, b))
+            .context("champion registry is empty")?;
+
+        let mut index = HashMap::new();
+        for (i, champion) in champions.iter().enumerate() {
+            index.insert(normalize(&champion.id), i);
+            index.insert(normalize(&champion.name), i);
+        }
+
+        Ok(Self {
+            version,
+            champions,
+            index,
+        })
+    }
+
+    /// Registry built from the champion.json shipped with the binary
+    pub fn bundled() -> Result<Self> {
+        Self::new(parse_champion_json(BUNDLED_CHAMPION_JSON)?)
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn get(&self, champion_name: &str) -> Option<&ChampionDto> {
+        self.index
+            .get(&normalize(champion_name))
+            .and_then(|i| self.champions.get(*i))
+    }
+
+    pub fn get_image_url(&self, champion_name: &str) -> Result<String> {
+        let thumbnail_url = match self.get(champion_name) {
+            Some(champion) => format!(
+                "{}/cdn/{}/img/champion/{}.png",
+                DATA_DRAGON_URL, champion.version, champion.id
+            ),
+            // Champion is newer than our registry - best effort guess
+            None => format!(
+                "https://cdn.communitydragon.org/latest/champion/{}/square",
+                normalize(champion_name)
+            ),
+        };
+
+        Ok(Url::parse(&thumbnail_url)?.to_string())
+    }
+}
+
+/// Fetch the most recent patch version from versions.json
+pub async fn get_latest_version() -> Result<String> {
+    let versions: Vec<String> = reqwest::get(format!("{}/api/versions.json", DATA_DRAGON_URL))
+        .await
+        .context("get_latest_version failed")?
+        .json()
+        .await
+        .context("get_latest_version failed to parse json")?;
+
+    versions
+        .into_iter()
+        .next()
+        .context("versions.json is empty")
+}
+
+/// Fetch all champions for a patch version from champion.json
+pub async fn get_champions(version: &str) -> Result<Vec<ChampionDto>> {
+    let body = reqwest::get(format!(
+        "{}/cdn/{}/data/en_US/champion.json",
+        DATA_DRAGON_URL, version
+    ))
+    .await
+    .context("get_champions failed")?
+    .text()
+    .await
+    .context("get_champions failed to get text")?;
+
+    parse_champion_json(&body)
+}
+
+fn parse_champion_json(body: &str) -> Result<Vec<ChampionDto>> {
+    let json: ChampionJson = serde_json::from_str(body).context("unable to parse champion.json")?;
+
+    json.data
+        .into_values()
+        .map(|c| {
+            let key = c
+                .key
+                .parse::<i64>()
+                .with_context(|| format!("invalid champion key: {}", c.key))?;
+            Ok(ChampionDto::new(c.id, key, c.name, json.version.clone()))
+        })
+        .collect()
+}
+
+/// Lowercase and strip everything but letters and digits
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Compare patch versions numerically e.g. "13.9.1" < "13.21.1"
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |v: &str| -> Vec<u64> { v.split('.').filter_map(|p| p.parse().ok()).collect() };
+    parse(a).cmp(&parse(b))
+}
@@ -0,0 +1,704 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{
+    repository::{
+        ActiveGameRepository, ApiTokenRepository, Backend, ChampionRepository, GameRepository,
+        GuildRepository, GuildRuleRepository, GuildTemplateRepository, LogRepository,
+        OutboxRepository, RankSnapshotRepository, SummonerRepository,
+    },
+    Database,
+};
+use crate::{
+    clock::Clock,
+    dtos::{
+        active_game_dto::ActiveGameDto,
+        api_token_dto::ApiTokenDto,
+        champion_dto::ChampionDto,
+        game_dto::GameDto,
+        guild_dto::GuildDto,
+        guild_rule_dto::GuildRuleDto,
+        guild_template_dto::GuildTemplateDto,
+        log_dto::LogDto,
+        notification_outbox_dto::{NotificationOutboxDto, OutboxKind, OutboxStatus},
+        rank_snapshot_dto::RankSnapshotDto,
+        summoner_dto::SummonerDto,
+    },
+};
+
+/// Keeps everything in memory for tests. Behaves like the SQL backends where
+/// the workers rely on it: defaults for timestamps, case insensitive summoner
+/// names, cascading deletes and `RowNotFound` for missing rows. Timestamps
+/// come from the clock so tests that move it get consistent rows.
+pub struct MemoryRepository {
+    state: Mutex<State>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Default)]
+struct State {
+    guilds: BTreeMap<i64, GuildDto>,
+    summoners: BTreeMap<String, SummonerDto>,
+    games: BTreeMap<String, GameDto>,
+    active_games: BTreeMap<String, ActiveGameDto>,
+    logs: Vec<LogDto>,
+    champions: BTreeMap<String, ChampionDto>,
+    rank_snapshots: Vec<RankSnapshotDto>,
+    api_tokens: BTreeMap<i64, ApiTokenDto>,
+    templates: BTreeMap<i64, GuildTemplateDto>,
+    rules: BTreeMap<(i64, String), GuildRuleDto>,
+    outbox: Vec<NotificationOutboxDto>,
+    next_id: i64,
+}
+
+impl State {
+    fn next_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+impl Database {
+    pub fn in_memory(clock: Arc<dyn Clock>) -> Self {
+        Self::new(MemoryRepository {
+            state: Mutex::default(),
+            clock,
+        })
+    }
+}
+
+impl MemoryRepository {
+    fn now(&self) -> i64 {
+        self.clock.now()
+    }
+}
+
+fn not_found<T>() -> Result<T> {
+    Err(sqlx::Error::RowNotFound.into())
+}
+
+#[async_trait]
+impl Backend for MemoryRepository {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn backup(&self, _path: &str) -> Result<()> {
+        anyhow::bail!("nothing to back up in memory")
+    }
+
+    async fn close(&self) {}
+}
+
+#[async_trait]
+impl SummonerRepository for MemoryRepository {
+    async fn insert_or_ignore(&self, summoner: &SummonerDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.summoners.contains_key(&summoner.id) {
+            let summoner = SummonerDto {
+                created_at: Some(self.now()),
+                updated_at: Some(self.now()),
+                ..summoner.clone()
+            };
+            state.summoners.insert(summoner.id.clone(), summoner);
+        }
+        Ok(())
+    }
+
+    async fn upsert(&self, summoner: &SummonerDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let existing = state.summoners.get(&summoner.id);
+        let created_at = existing.and_then(|s| s.created_at).unwrap_or(self.now());
+        let added_by = existing.map_or(summoner.added_by, |s| s.added_by);
+        let summoner = SummonerDto {
+            created_at: Some(created_at),
+            updated_at: Some(self.now()),
+            added_by,
+            ..summoner.clone()
+        };
+        state.summoners.insert(summoner.id.clone(), summoner);
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<SummonerDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .summoners
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_all_for_guild(&self, guild_id: i64) -> Result<Vec<SummonerDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .summoners
+            .values()
+            .filter(|s| s.guild_id == guild_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn count(&self) -> Result<i64> {
+        Ok(self.state.lock().unwrap().summoners.len() as i64)
+    }
+
+    async fn get(&self, summoner_id: &str) -> Result<SummonerDto> {
+        match self.state.lock().unwrap().summoners.get(summoner_id) {
+            Some(summoner) => Ok(summoner.clone()),
+            None => not_found(),
+        }
+    }
+
+    async fn get_by_name(&self, guild_id: i64, summoner_name: &str) -> Result<SummonerDto> {
+        let state = self.state.lock().unwrap();
+        match state
+            .summoners
+            .values()
+            .find(|s| s.guild_id == guild_id && s.name.eq_ignore_ascii_case(summoner_name))
+        {
+            Some(summoner) => Ok(summoner.clone()),
+            None => not_found(),
+        }
+    }
+
+    async fn delete(&self, summoner_id: &str, guild_id: i64) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let ids: Vec<String> = state
+            .summoners
+            .values()
+            .filter(|s| s.id.eq_ignore_ascii_case(summoner_id) && s.guild_id == guild_id)
+            .map(|s| s.id.clone())
+            .collect();
+
+        for id in &ids {
+            state.summoners.remove(id);
+        }
+        state.games.retain(|_, g| !ids.contains(&g.summoner_id));
+        state
+            .active_games
+            .retain(|_, g| !ids.contains(&g.summoner_id));
+        state
+            .rank_snapshots
+            .retain(|s| !ids.contains(&s.summoner_id));
+        state.outbox.retain(|o| !ids.contains(&o.summoner_id));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameRepository for MemoryRepository {
+    async fn insert_or_ignore(&self, game: &GameDto) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        if state.games.contains_key(&game.id) {
+            return Ok(false);
+        }
+
+        let game = GameDto {
+            created_at: Some(game.created_at.unwrap_or(self.now())),
+            updated_at: Some(self.now()),
+            ..game.clone()
+        };
+        state.games.insert(game.id.clone(), game);
+        Ok(true)
+    }
+
+    async fn upsert(&self, game: &GameDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let created_at = state
+            .games
+            .get(&game.id)
+            .and_then(|g| g.created_at)
+            .or(game.created_at)
+            .unwrap_or(self.now());
+        let game = GameDto {
+            created_at: Some(created_at),
+            updated_at: Some(self.now()),
+            ..game.clone()
+        };
+        state.games.insert(game.id.clone(), game);
+        Ok(())
+    }
+
+    async fn resolve_pending_lp(&self, game: &GameDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(stored) = state.games.get_mut(&game.id).filter(|g| g.lp_pending) {
+            stored.lp_change = game.lp_change;
+            stored.promotion_text = game.promotion_text.clone();
+            stored.lp_pending = false;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, game_id: &str) -> Result<GameDto> {
+        match self.state.lock().unwrap().games.get(game_id) {
+            Some(game) => Ok(game.clone()),
+            None => not_found(),
+        }
+    }
+
+    async fn get_unnotified_games_for_summoner(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .games
+            .values()
+            .filter(|g| g.summoner_id == summoner_id && !g.notified)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_recent_for_summoner(&self, summoner_id: &str, limit: i64) -> Result<Vec<GameDto>> {
+        self.get_before_for_summoner(summoner_id, i64::MAX, limit)
+            .await
+    }
+
+    async fn get_before_for_summoner(
+        &self,
+        summoner_id: &str,
+        before: i64,
+        limit: i64,
+    ) -> Result<Vec<GameDto>> {
+        let mut games: Vec<GameDto> = self
+            .state
+            .lock()
+            .unwrap()
+            .games
+            .values()
+            .filter(|g| g.summoner_id == summoner_id && g.game_created_at < before)
+            .cloned()
+            .collect();
+        games.sort_by_key(|g| std::cmp::Reverse(g.game_created_at));
+        games.truncate(limit.max(0) as usize);
+        Ok(games)
+    }
+
+    async fn count_pentakills_before(&self, summoner_id: &str, before: i64) -> Result<i64> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .games
+            .values()
+            .filter(|g| g.summoner_id == summoner_id && g.game_created_at < before)
+            .filter_map(|g| g.pentakills)
+            .sum())
+    }
+
+    async fn get_for_summoner_since(&self, summoner_id: &str, since: i64) -> Result<Vec<GameDto>> {
+        let mut games: Vec<GameDto> = self
+            .state
+            .lock()
+            .unwrap()
+            .games
+            .values()
+            .filter(|g| g.summoner_id == summoner_id && g.game_created_at >= since)
+            .cloned()
+            .collect();
+        games.sort_by_key(|g| g.game_created_at);
+        Ok(games)
+    }
+}
+
+#[async_trait]
+impl ActiveGameRepository for MemoryRepository {
+    async fn insert_or_ignore(&self, active_game: &ActiveGameDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.active_games.contains_key(&active_game.id) {
+            let active_game = ActiveGameDto {
+                created_at: Some(self.now()),
+                ..active_game.clone()
+            };
+            state
+                .active_games
+                .insert(active_game.id.clone(), active_game);
+        }
+        Ok(())
+    }
+
+    async fn get_unnotified_active_games(&self) -> Result<Vec<ActiveGameDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .active_games
+            .values()
+            .filter(|g| !g.notified)
+            .cloned()
+            .collect())
+    }
+
+    async fn get(&self, active_game_id: &str) -> Result<ActiveGameDto> {
+        match self.state.lock().unwrap().active_games.get(active_game_id) {
+            Some(active_game) => Ok(active_game.clone()),
+            None => not_found(),
+        }
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<ActiveGameDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .active_games
+            .values()
+            .filter(|g| g.summoner_id == summoner_id)
+            .max_by_key(|g| g.game_created_at)
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl GuildRepository for MemoryRepository {
+    async fn insert_or_ignore(&self, guild: &GuildDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.guilds.contains_key(&guild.id) {
+            let guild = GuildDto {
+                created_at: Some(self.now()),
+                updated_at: Some(self.now()),
+                ..guild.clone()
+            };
+            state.guilds.insert(guild.id, guild);
+        }
+        Ok(())
+    }
+
+    async fn update(&self, guild: &GuildDto) -> Result<()> {
+        if let Some(stored) = self.state.lock().unwrap().guilds.get_mut(&guild.id) {
+            stored.chat_channel_id = guild.chat_channel_id;
+            stored.name = guild.name.clone();
+            stored.updated_at = Some(self.now());
+        }
+        Ok(())
+    }
+
+    async fn set_manager_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<()> {
+        if let Some(stored) = self.state.lock().unwrap().guilds.get_mut(&guild_id) {
+            stored.manager_role_id = role_id;
+            stored.updated_at = Some(self.now());
+        }
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<GuildDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .guilds
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn get(&self, guild_id: i64) -> Result<GuildDto> {
+        match self.state.lock().unwrap().guilds.get(&guild_id) {
+            Some(guild) => Ok(guild.clone()),
+            None => not_found(),
+        }
+    }
+}
+
+#[async_trait]
+impl LogRepository for MemoryRepository {
+    async fn create(&self, log: &LogDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let log = LogDto {
+            id: Some(state.next_id()),
+            created_at: Some(self.now()),
+            ..log.clone()
+        };
+        state.logs.push(log);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChampionRepository for MemoryRepository {
+    async fn upsert(&self, champion: &ChampionDto) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .champions
+            .insert(champion.id.clone(), champion.clone());
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<ChampionDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .champions
+            .values()
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl RankSnapshotRepository for MemoryRepository {
+    async fn insert(&self, snapshot: &RankSnapshotDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let snapshot = RankSnapshotDto {
+            id: Some(state.next_id()),
+            ..snapshot.clone()
+        };
+        state.rank_snapshots.push(snapshot);
+        Ok(())
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<RankSnapshotDto>> {
+        self.get_latest_before(summoner_id, i64::MAX).await
+    }
+
+    async fn get_latest_before(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        Ok(self
+            .get_all_for_summoner(summoner_id)
+            .await?
+            .into_iter()
+            .rev()
+            .find(|s| s.created_at <= timestamp))
+    }
+
+    async fn get_first_after(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        Ok(self
+            .get_all_for_summoner(summoner_id)
+            .await?
+            .into_iter()
+            .find(|s| s.created_at >= timestamp))
+    }
+
+    async fn get_all_for_summoner(&self, summoner_id: &str) -> Result<Vec<RankSnapshotDto>> {
+        let mut snapshots: Vec<RankSnapshotDto> = self
+            .state
+            .lock()
+            .unwrap()
+            .rank_snapshots
+            .iter()
+            .filter(|s| s.summoner_id == summoner_id)
+            .cloned()
+            .collect();
+        snapshots.sort_by_key(|s| (s.created_at, s.id));
+        Ok(snapshots)
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for MemoryRepository {
+    async fn upsert(&self, api_token: &ApiTokenDto) -> Result<()> {
+        let api_token = ApiTokenDto {
+            created_at: Some(self.now()),
+            ..api_token.clone()
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .api_tokens
+            .insert(api_token.guild_id, api_token);
+        Ok(())
+    }
+
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .api_tokens
+            .values()
+            .find(|t| t.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        self.state.lock().unwrap().api_tokens.remove(&guild_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildTemplateRepository for MemoryRepository {
+    async fn upsert(&self, template: &GuildTemplateDto) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .templates
+            .insert(template.guild_id, template.clone());
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<GuildTemplateDto> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .templates
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_else(|| GuildTemplateDto::new(guild_id)))
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        self.state.lock().unwrap().templates.remove(&guild_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildRuleRepository for MemoryRepository {
+    async fn upsert(&self, rule: &GuildRuleDto) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .rules
+            .insert((rule.guild_id, rule.rule.clone()), rule.clone());
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<Vec<GuildRuleDto>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .rules
+            .values()
+            .filter(|r| r.guild_id == guild_id)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for MemoryRepository {
+    async fn enqueue(&self, outbox: &NotificationOutboxDto) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        let queued = state
+            .outbox
+            .iter()
+            .any(|o| o.kind == outbox.kind && o.game_id == outbox.game_id);
+        if !queued {
+            let outbox = NotificationOutboxDto {
+                id: Some(state.next_id()),
+                created_at: Some(self.now()),
+                updated_at: Some(self.now()),
+                ..outbox.clone()
+            };
+            state.outbox.push(outbox);
+        }
+
+        match outbox.kind {
+            OutboxKind::GameFinished | OutboxKind::CatchUp => {
+                if let Some(game) = state.games.get_mut(&outbox.game_id) {
+                    game.notified = true;
+                    game.lp_pending = false;
+                }
+            }
+            OutboxKind::GameStarted => {
+                if let Some(active_game) = state.active_games.get_mut(&outbox.game_id) {
+                    active_game.notified = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn insert_missed_game(
+        &self,
+        game: &GameDto,
+        outbox: &NotificationOutboxDto,
+    ) -> Result<bool> {
+        // Nothing else runs in between in tests, no need for a transaction
+        if !GameRepository::insert_or_ignore(self, game).await? {
+            return Ok(false);
+        }
+        self.enqueue(outbox).await?;
+        Ok(true)
+    }
+
+    async fn claim_due(&self, now: i64, lease_until: i64) -> Result<Vec<NotificationOutboxDto>> {
+        let mut state = self.state.lock().unwrap();
+        let mut due = vec![];
+        for outbox in state.outbox.iter_mut() {
+            if outbox.status == OutboxStatus::Pending && outbox.next_attempt_at <= now {
+                outbox.next_attempt_at = lease_until;
+                due.push(outbox.clone());
+            }
+        }
+        Ok(due)
+    }
+
+    async fn mark_sent(
+        &self,
+        outbox: &NotificationOutboxDto,
+        message_id: Option<&str>,
+        sent_at: i64,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(stored) = state.outbox.iter_mut().find(|o| o.id == outbox.id) {
+            stored.status = OutboxStatus::Sent;
+            stored.attempts += 1;
+            stored.message_id = message_id.map(|m| m.to_string());
+            stored.sent_at = Some(sent_at);
+            stored.last_error = None;
+        }
+        Ok(())
+    }
+
+    async fn mark_failed(
+        &self,
+        outbox: &NotificationOutboxDto,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(stored) = state.outbox.iter_mut().find(|o| o.id == outbox.id) {
+            stored.status = match next_attempt_at {
+                Some(_) => OutboxStatus::Pending,
+                None => OutboxStatus::Dead,
+            };
+            stored.attempts += 1;
+            stored.next_attempt_at = next_attempt_at.unwrap_or(outbox.next_attempt_at);
+            stored.last_error = Some(error.to_string());
+        }
+        Ok(())
+    }
+
+    async fn count_undelivered(&self) -> Result<(i64, i64)> {
+        let state = self.state.lock().unwrap();
+        let count = |status| state.outbox.iter().filter(|o| o.status == status).count() as i64;
+        Ok((count(OutboxStatus::Pending), count(OutboxStatus::Dead)))
+    }
+
+    async fn delete_sent_before(&self, before: i64) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .outbox
+            .retain(|o| !(o.status == OutboxStatus::Sent && o.sent_at.is_some_and(|s| s < before)));
+        Ok(())
+    }
+}
@@ -1,18 +1,109 @@
+use std::{sync::Arc, time::Duration};
+
 use anyhow::{Context, Result};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
 
-pub async fn create_db() -> Result<Pool<Sqlite>> {
-    // check if db.sqlite file exists
-    if let Err(_) = std::fs::File::open("db.sqlite") {
-        std::fs::File::create("db.sqlite").context("failed to create db.sqlite")?;
+#[cfg(test)]
+pub mod memory;
+mod postgres;
+pub mod repository;
+mod sqlite;
+
+use repository::{
+    ActiveGameRepository, ApiTokenRepository, Backend, ChampionRepository, GameRepository,
+    GuildRepository, GuildRuleRepository, GuildTemplateRepository, LogRepository, OutboxRepository,
+    RankSnapshotRepository, SummonerRepository,
+};
+
+static DEFAULT_DATABASE_URL: &str = "sqlite:db.sqlite";
+/// Workers, the bot and the http server all share the pool
+static DEFAULT_MAX_CONNECTIONS: u32 = 8;
+static ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Every repository, all backed by the same database
+#[derive(Clone)]
+pub struct Database {
+    pub summoners: Arc<dyn SummonerRepository>,
+    pub games: Arc<dyn GameRepository>,
+    pub active_games: Arc<dyn ActiveGameRepository>,
+    pub guilds: Arc<dyn GuildRepository>,
+    pub logs: Arc<dyn LogRepository>,
+    pub champions: Arc<dyn ChampionRepository>,
+    pub rank_snapshots: Arc<dyn RankSnapshotRepository>,
+    pub api_tokens: Arc<dyn ApiTokenRepository>,
+    pub templates: Arc<dyn GuildTemplateRepository>,
+    pub rules: Arc<dyn GuildRuleRepository>,
+    pub outbox: Arc<dyn OutboxRepository>,
+    pub backend: Arc<dyn Backend>,
+}
+
+impl Database {
+    fn new<R>(repository: R) -> Self
+    where
+        R: SummonerRepository
+            + GameRepository
+            + ActiveGameRepository
+            + GuildRepository
+            + LogRepository
+            + ChampionRepository
+            + RankSnapshotRepository
+            + ApiTokenRepository
+            + GuildTemplateRepository
+            + GuildRuleRepository
+            + OutboxRepository
+            + Backend
+            + 'static,
+    {
+        let repository = Arc::new(repository);
+        Self {
+            summoners: repository.clone(),
+            games: repository.clone(),
+            active_games: repository.clone(),
+            guilds: repository.clone(),
+            logs: repository.clone(),
+            champions: repository.clone(),
+            rank_snapshots: repository.clone(),
+            api_tokens: repository.clone(),
+            templates: repository.clone(),
+            rules: repository.clone(),
+            outbox: repository.clone(),
+            backend: repository,
+        }
     }
+}
+
+/// - `DATABASE_URL` (default `sqlite:db.sqlite`), `postgres://` urls use Postgres
+/// - `DATABASE_MAX_CONNECTIONS` (default 8)
+/// - run the backend's migrations
+pub async fn create_db() -> Result<Database> {
+    let url = std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|u| !u.is_empty())
+        .unwrap_or(DEFAULT_DATABASE_URL.to_string());
+    let max_connections = match std::env::var("DATABASE_MAX_CONNECTIONS")
+        .ok()
+        .filter(|c| !c.is_empty())
+    {
+        Some(c) => c
+            .parse::<u32>()
+            .context("unable to parse DATABASE_MAX_CONNECTIONS from env file")?,
+        None => DEFAULT_MAX_CONNECTIONS,
+    };
 
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect("sqlite:db.sqlite")
-        .await?;
+    let db = if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+        Database::new(
+            postgres::PostgresRepository::connect(&url, max_connections, ACQUIRE_TIMEOUT).await?,
+        )
+    } else {
+        Database::new(
+            sqlite::SqliteRepository::connect(&url, max_connections, ACQUIRE_TIMEOUT).await?,
+        )
+    };
 
-    sqlx::migrate!().run(&pool).await?;
+    tracing::info!(
+        backend = db.backend.name(),
+        max_connections,
+        "Connected to database"
+    );
 
-    Ok(pool)
+    Ok(db)
 }
@@ -0,0 +1,1261 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use sqlx::{postgres::PgPoolOptions, PgExecutor, Pool, Postgres};
+
+use super::repository::{
+    ActiveGameRepository, ApiTokenRepository, Backend, ChampionRepository, GameRepository,
+    GuildRepository, GuildRuleRepository, GuildTemplateRepository, LogRepository, OutboxRepository,
+    RankSnapshotRepository, SummonerRepository,
+};
+use crate::dtos::{
+    active_game_dto::ActiveGameDto,
+    api_token_dto::ApiTokenDto,
+    champion_dto::ChampionDto,
+    game_dto::GameDto,
+    guild_dto::GuildDto,
+    guild_rule_dto::GuildRuleDto,
+    guild_template_dto::GuildTemplateDto,
+    log_dto::LogDto,
+    notification_outbox_dto::{NotificationOutboxDto, OutboxKind, OutboxStatus},
+    rank_snapshot_dto::RankSnapshotDto,
+    summoner_dto::SummonerDto,
+};
+
+// The query macros need a database of each kind at compile time, so these
+// are checked at runtime instead. Keep them in sync with `sqlite.rs`.
+//
+// SQLite compares summoner names with COLLATE NOCASE, here that's LOWER().
+
+pub struct PostgresRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    /// - run migrations, several instances starting at once wait on each other
+    pub async fn connect(
+        url: &str,
+        max_connections: u32,
+        acquire_timeout: Duration,
+    ) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .min_connections(1)
+            .acquire_timeout(acquire_timeout)
+            .connect(url)
+            .await?;
+
+        sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Backend for PostgresRepository {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn backup(&self, _path: &str) -> Result<()> {
+        bail!("backups aren't supported for Postgres, use pg_dump instead")
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+#[async_trait]
+impl SummonerRepository for PostgresRepository {
+    async fn insert_or_ignore(&self, summoner: &SummonerDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO summoner (
+                id,
+                name,
+                guild_id,
+                queue_type,
+                tier,
+                lp,
+                division,
+                icon_url,
+                added_by
+                )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .bind(&summoner.id)
+        .bind(&summoner.name)
+        .bind(summoner.guild_id)
+        .bind(&summoner.queue_type)
+        .bind(&summoner.tier)
+        .bind(summoner.lp)
+        .bind(&summoner.division)
+        .bind(&summoner.icon_url)
+        .bind(summoner.added_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upsert(&self, summoner: &SummonerDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO summoner (
+                id,
+                name,
+                guild_id,
+                queue_type,
+                tier,
+                lp,
+                division,
+                icon_url,
+                added_by
+                )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT ((LOWER(id))) DO UPDATE SET
+                name = excluded.name,
+                guild_id = excluded.guild_id,
+                queue_type = excluded.queue_type,
+                tier = excluded.tier,
+                lp = excluded.lp,
+                division = excluded.division,
+                icon_url = excluded.icon_url;
+            "#,
+        )
+        .bind(&summoner.id)
+        .bind(&summoner.name)
+        .bind(summoner.guild_id)
+        .bind(&summoner.queue_type)
+        .bind(&summoner.tier)
+        .bind(summoner.lp)
+        .bind(&summoner.division)
+        .bind(&summoner.icon_url)
+        .bind(summoner.added_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<SummonerDto>> {
+        let summoners = sqlx::query_as("SELECT * FROM summoner")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(summoners)
+    }
+
+    async fn get_all_for_guild(&self, guild_id: i64) -> Result<Vec<SummonerDto>> {
+        let summoners = sqlx::query_as("SELECT * FROM summoner WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(summoners)
+    }
+
+    async fn count(&self) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM summoner")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn get(&self, summoner_id: &str) -> Result<SummonerDto> {
+        let summoner = sqlx::query_as("SELECT * FROM summoner WHERE LOWER(id) = LOWER($1)")
+            .bind(summoner_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(summoner)
+    }
+
+    async fn get_by_name(&self, guild_id: i64, summoner_name: &str) -> Result<SummonerDto> {
+        let summoner = sqlx::query_as(
+            "SELECT * FROM summoner WHERE guild_id = $1 AND LOWER(name) = LOWER($2)",
+        )
+        .bind(guild_id)
+        .bind(summoner_name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(summoner)
+    }
+
+    async fn delete(&self, summoner_id: &str, guild_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM summoner WHERE LOWER(id) = LOWER($1) AND guild_id = $2")
+            .bind(summoner_id)
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Shared by `insert_or_ignore` and `insert_missed_game`, true if it was inserted
+async fn insert_game(executor: impl PgExecutor<'_>, game: &GameDto) -> Result<bool> {
+    let result: String = game.result.into();
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO game (
+            id,
+            summoner_id,
+            game_created_at,
+            assists,
+            deaths,
+            kills,
+            result,
+            notified,
+            lp_change,
+            champion_name,
+            game_mode,
+            promotion_text,
+            game_duration,
+            lp_pending,
+            pentakills
+            )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT DO NOTHING;
+        "#,
+    )
+    .bind(&game.id)
+    .bind(&game.summoner_id)
+    .bind(game.game_created_at)
+    .bind(game.assists)
+    .bind(game.deaths)
+    .bind(game.kills)
+    .bind(result)
+    .bind(game.notified)
+    .bind(game.lp_change)
+    .bind(&game.champion_name)
+    .bind(&game.game_mode)
+    .bind(&game.promotion_text)
+    .bind(game.game_duration)
+    .bind(game.lp_pending)
+    .bind(game.pentakills)
+    .execute(executor)
+    .await?
+    .rows_affected();
+
+    Ok(inserted > 0)
+}
+
+#[async_trait]
+impl GameRepository for PostgresRepository {
+    async fn insert_or_ignore(&self, game: &GameDto) -> Result<bool> {
+        insert_game(&self.pool, game).await
+    }
+
+    async fn upsert(&self, game: &GameDto) -> Result<()> {
+        let result: String = game.result.into();
+        sqlx::query(
+            r#"
+            INSERT INTO game (
+                id,
+                summoner_id,
+                game_created_at,
+                assists,
+                deaths,
+                kills,
+                result,
+                notified,
+                lp_change,
+                champion_name,
+                game_mode,
+                promotion_text,
+                game_duration,
+                lp_pending,
+                pentakills
+                )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (id) DO UPDATE SET
+                summoner_id = excluded.summoner_id,
+                game_created_at = excluded.game_created_at,
+                assists = excluded.assists,
+                deaths = excluded.deaths,
+                kills = excluded.kills,
+                result = excluded.result,
+                notified = excluded.notified,
+                lp_change = excluded.lp_change,
+                champion_name = excluded.champion_name,
+                game_mode = excluded.game_mode,
+                promotion_text = excluded.promotion_text,
+                game_duration = excluded.game_duration,
+                lp_pending = excluded.lp_pending,
+                pentakills = excluded.pentakills;
+            "#,
+        )
+        .bind(&game.id)
+        .bind(&game.summoner_id)
+        .bind(game.game_created_at)
+        .bind(game.assists)
+        .bind(game.deaths)
+        .bind(game.kills)
+        .bind(result)
+        .bind(game.notified)
+        .bind(game.lp_change)
+        .bind(&game.champion_name)
+        .bind(&game.game_mode)
+        .bind(&game.promotion_text)
+        .bind(game.game_duration)
+        .bind(game.lp_pending)
+        .bind(game.pentakills)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn resolve_pending_lp(&self, game: &GameDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE game
+            SET lp_change = $1, promotion_text = $2, lp_pending = FALSE
+            WHERE id = $3 AND lp_pending;
+            "#,
+        )
+        .bind(game.lp_change)
+        .bind(&game.promotion_text)
+        .bind(&game.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, game_id: &str) -> Result<GameDto> {
+        let game = sqlx::query_as("SELECT * FROM game WHERE id = $1")
+            .bind(game_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(game)
+    }
+
+    async fn get_unnotified_games_for_summoner(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as("SELECT * FROM game WHERE summoner_id = $1 AND NOT notified")
+            .bind(summoner_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(games)
+    }
+
+    async fn get_recent_for_summoner(&self, summoner_id: &str, limit: i64) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as(
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = $1
+            ORDER BY game_created_at DESC
+            LIMIT $2;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(games)
+    }
+
+    async fn get_before_for_summoner(
+        &self,
+        summoner_id: &str,
+        before: i64,
+        limit: i64,
+    ) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as(
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = $1 AND game_created_at < $2
+            ORDER BY game_created_at DESC
+            LIMIT $3;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(games)
+    }
+
+    async fn count_pentakills_before(&self, summoner_id: &str, before: i64) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(SUM(pentakills), 0)::BIGINT FROM game
+            WHERE summoner_id = $1 AND game_created_at < $2;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(before)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_for_summoner_since(&self, summoner_id: &str, since: i64) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as(
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = $1 AND game_created_at >= $2
+            ORDER BY game_created_at;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(games)
+    }
+}
+
+#[async_trait]
+impl ActiveGameRepository for PostgresRepository {
+    async fn insert_or_ignore(&self, active_game: &ActiveGameDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO active_game (
+                id,
+                summoner_id,
+                game_created_at,
+                champion,
+                role,
+                spectate_link,
+                notified,
+                game_mode
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&active_game.id)
+        .bind(&active_game.summoner_id)
+        .bind(active_game.game_created_at)
+        .bind(&active_game.champion)
+        .bind(&active_game.role)
+        .bind(&active_game.spectate_link)
+        .bind(active_game.notified)
+        .bind(&active_game.game_mode)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_unnotified_active_games(&self) -> Result<Vec<ActiveGameDto>> {
+        let active_games = sqlx::query_as("SELECT * FROM active_game WHERE NOT notified")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(active_games)
+    }
+
+    async fn get(&self, active_game_id: &str) -> Result<ActiveGameDto> {
+        let active_game = sqlx::query_as("SELECT * FROM active_game WHERE id = $1")
+            .bind(active_game_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(active_game)
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<ActiveGameDto>> {
+        let active_game = sqlx::query_as(
+            r#"
+            SELECT *
+            FROM active_game
+            WHERE summoner_id = $1
+            ORDER BY game_created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(summoner_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(active_game)
+    }
+}
+
+#[async_trait]
+impl GuildRepository for PostgresRepository {
+    async fn insert_or_ignore(&self, guild: &GuildDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO guild (
+               id,
+               chat_channel_id,
+               name
+            )
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .bind(guild.id)
+        .bind(guild.chat_channel_id)
+        .bind(&guild.name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update(&self, guild: &GuildDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE guild
+            SET chat_channel_id = $1,
+                name = $2
+            WHERE id = $3;
+            "#,
+        )
+        .bind(guild.chat_channel_id)
+        .bind(&guild.name)
+        .bind(guild.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_manager_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<()> {
+        sqlx::query("UPDATE guild SET manager_role_id = $1 WHERE id = $2")
+            .bind(role_id)
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<GuildDto>> {
+        let guilds = sqlx::query_as("SELECT * FROM guild")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(guilds)
+    }
+
+    async fn get(&self, guild_id: i64) -> Result<GuildDto> {
+        let guild = sqlx::query_as("SELECT * FROM guild WHERE id = $1")
+            .bind(guild_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(guild)
+    }
+}
+
+#[async_trait]
+impl LogRepository for PostgresRepository {
+    async fn create(&self, log: &LogDto) -> Result<()> {
+        let error_type: String = log.error_type.into();
+        sqlx::query(
+            r#"
+            INSERT INTO log (
+                message,
+                error_type,
+                target,
+                summoner_id,
+                guild_id,
+                strategy,
+                url
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7);
+            "#,
+        )
+        .bind(&log.message)
+        .bind(error_type)
+        .bind(&log.target)
+        .bind(&log.summoner_id)
+        .bind(log.guild_id)
+        .bind(&log.strategy)
+        .bind(&log.url)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChampionRepository for PostgresRepository {
+    async fn upsert(&self, champion: &ChampionDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO champion (
+                id,
+                key,
+                name,
+                version
+                )
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (id) DO UPDATE
+            SET key = excluded.key,
+                name = excluded.name,
+                version = excluded.version;
+            "#,
+        )
+        .bind(&champion.id)
+        .bind(champion.key)
+        .bind(&champion.name)
+        .bind(&champion.version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<ChampionDto>> {
+        let champions = sqlx::query_as("SELECT * FROM champion")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(champions)
+    }
+}
+
+#[async_trait]
+impl RankSnapshotRepository for PostgresRepository {
+    async fn insert(&self, snapshot: &RankSnapshotDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO rank_snapshot (
+                summoner_id,
+                created_at,
+                queue_type,
+                tier,
+                division,
+                lp
+                )
+            VALUES ($1, $2, $3, $4, $5, $6);
+            "#,
+        )
+        .bind(&snapshot.summoner_id)
+        .bind(snapshot.created_at)
+        .bind(&snapshot.queue_type)
+        .bind(&snapshot.tier)
+        .bind(&snapshot.division)
+        .bind(snapshot.lp)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as(
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1;
+            "#,
+        )
+        .bind(summoner_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_latest_before(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as(
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = $1 AND created_at <= $2
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_first_after(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as(
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = $1 AND created_at >= $2
+            ORDER BY created_at, id
+            LIMIT 1;
+            "#,
+        )
+        .bind(summoner_id)
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_all_for_summoner(&self, summoner_id: &str) -> Result<Vec<RankSnapshotDto>> {
+        let snapshots = sqlx::query_as(
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = $1
+            ORDER BY created_at, id;
+            "#,
+        )
+        .bind(summoner_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for PostgresRepository {
+    async fn upsert(&self, api_token: &ApiTokenDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_token (guild_id, token_hash)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE SET
+                token_hash = excluded.token_hash,
+                created_at = EXTRACT(EPOCH FROM NOW())::BIGINT;
+            "#,
+        )
+        .bind(api_token.guild_id)
+        .bind(&api_token.token_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenDto>> {
+        let token = sqlx::query_as("SELECT * FROM api_token WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM api_token WHERE guild_id = $1")
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildTemplateRepository for PostgresRepository {
+    async fn upsert(&self, template: &GuildTemplateDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO guild_template (
+                guild_id,
+                victory_title,
+                defeat_title,
+                in_game_title,
+                victory_color,
+                defeat_color,
+                in_game_color,
+                victory_emoji,
+                defeat_emoji,
+                in_game_emoji,
+                game_fields,
+                active_game_fields,
+                demotion_text,
+                remake_title,
+                remake_color,
+                remake_emoji,
+                remake_notifications
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            ON CONFLICT (guild_id) DO UPDATE SET
+                victory_title = excluded.victory_title,
+                defeat_title = excluded.defeat_title,
+                in_game_title = excluded.in_game_title,
+                victory_color = excluded.victory_color,
+                defeat_color = excluded.defeat_color,
+                in_game_color = excluded.in_game_color,
+                victory_emoji = excluded.victory_emoji,
+                defeat_emoji = excluded.defeat_emoji,
+                in_game_emoji = excluded.in_game_emoji,
+                game_fields = excluded.game_fields,
+                active_game_fields = excluded.active_game_fields,
+                demotion_text = excluded.demotion_text,
+                remake_title = excluded.remake_title,
+                remake_color = excluded.remake_color,
+                remake_emoji = excluded.remake_emoji,
+                remake_notifications = excluded.remake_notifications;
+            "#,
+        )
+        .bind(template.guild_id)
+        .bind(&template.victory_title)
+        .bind(&template.defeat_title)
+        .bind(&template.in_game_title)
+        .bind(&template.victory_color)
+        .bind(&template.defeat_color)
+        .bind(&template.in_game_color)
+        .bind(&template.victory_emoji)
+        .bind(&template.defeat_emoji)
+        .bind(&template.in_game_emoji)
+        .bind(&template.game_fields)
+        .bind(&template.active_game_fields)
+        .bind(&template.demotion_text)
+        .bind(&template.remake_title)
+        .bind(&template.remake_color)
+        .bind(&template.remake_emoji)
+        .bind(&template.remake_notifications)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<GuildTemplateDto> {
+        let template = sqlx::query_as("SELECT * FROM guild_template WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(template.unwrap_or_else(|| GuildTemplateDto::new(guild_id)))
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM guild_template WHERE guild_id = $1")
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildRuleRepository for PostgresRepository {
+    async fn upsert(&self, rule: &GuildRuleDto) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO guild_rule (guild_id, rule, enabled)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id, rule) DO UPDATE SET
+                enabled = excluded.enabled;
+            "#,
+        )
+        .bind(rule.guild_id)
+        .bind(&rule.rule)
+        .bind(rule.enabled)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<Vec<GuildRuleDto>> {
+        let rules = sqlx::query_as("SELECT * FROM guild_rule WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rules)
+    }
+}
+
+/// Shared by `enqueue` and `insert_missed_game`
+async fn insert_outbox(
+    executor: impl PgExecutor<'_>,
+    outbox: &NotificationOutboxDto,
+) -> Result<()> {
+    let kind: String = outbox.kind.into();
+    sqlx::query(
+        r#"
+        INSERT INTO notification_outbox (
+            guild_id,
+            summoner_id,
+            kind,
+            game_id,
+            highlights,
+            next_attempt_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT DO NOTHING;
+        "#,
+    )
+    .bind(outbox.guild_id)
+    .bind(&outbox.summoner_id)
+    .bind(kind)
+    .bind(&outbox.game_id)
+    .bind(&outbox.highlights)
+    .bind(outbox.next_attempt_at)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl OutboxRepository for PostgresRepository {
+    async fn enqueue(&self, outbox: &NotificationOutboxDto) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        insert_outbox(&mut *tx, outbox).await?;
+
+        match outbox.kind {
+            OutboxKind::GameFinished | OutboxKind::CatchUp => {
+                sqlx::query("UPDATE game SET notified = TRUE, lp_pending = FALSE WHERE id = $1")
+                    .bind(&outbox.game_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            OutboxKind::GameStarted => {
+                sqlx::query("UPDATE active_game SET notified = TRUE WHERE id = $1")
+                    .bind(&outbox.game_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_missed_game(
+        &self,
+        game: &GameDto,
+        outbox: &NotificationOutboxDto,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let inserted = insert_game(&mut *tx, game).await?;
+        if inserted {
+            insert_outbox(&mut *tx, outbox).await?;
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn claim_due(&self, now: i64, lease_until: i64) -> Result<Vec<NotificationOutboxDto>> {
+        // SKIP LOCKED so two instances claiming at the same time get different rows
+        let mut outbox: Vec<NotificationOutboxDto> = sqlx::query_as(
+            r#"
+            UPDATE notification_outbox
+            SET next_attempt_at = $1
+            WHERE id IN (
+                SELECT id FROM notification_outbox
+                WHERE status = 'pending' AND next_attempt_at <= $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *;
+            "#,
+        )
+        .bind(lease_until)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        outbox.sort_by_key(|o| o.id);
+        Ok(outbox)
+    }
+
+    async fn mark_sent(
+        &self,
+        outbox: &NotificationOutboxDto,
+        message_id: Option<&str>,
+        sent_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE notification_outbox
+            SET status = 'sent', attempts = attempts + 1, message_id = $1, sent_at = $2, last_error = NULL
+            WHERE id = $3;
+            "#,
+        )
+        .bind(message_id)
+        .bind(sent_at)
+        .bind(outbox.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(
+        &self,
+        outbox: &NotificationOutboxDto,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> Result<()> {
+        let status: String = match next_attempt_at {
+            Some(_) => OutboxStatus::Pending.into(),
+            None => OutboxStatus::Dead.into(),
+        };
+        let next_attempt_at = next_attempt_at.unwrap_or(outbox.next_attempt_at);
+
+        sqlx::query(
+            r#"
+            UPDATE notification_outbox
+            SET status = $1, attempts = attempts + 1, next_attempt_at = $2, last_error = $3
+            WHERE id = $4;
+            "#,
+        )
+        .bind(status)
+        .bind(next_attempt_at)
+        .bind(error)
+        .bind(outbox.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_undelivered(&self) -> Result<(i64, i64)> {
+        let counts = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'pending'),
+                COUNT(*) FILTER (WHERE status = 'dead')
+            FROM notification_outbox;
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    async fn delete_sent_before(&self, before: i64) -> Result<()> {
+        sqlx::query("DELETE FROM notification_outbox WHERE status = 'sent' AND sent_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtos::game_dto::GameResult;
+
+    /// Needs a throwaway database, e.g.
+    /// `POSTGRES_TEST_URL=postgres://localhost/lol_test cargo test -- --ignored`
+    async fn repository() -> PostgresRepository {
+        let url = std::env::var("POSTGRES_TEST_URL").expect("POSTGRES_TEST_URL not set");
+        let repository = PostgresRepository::connect(&url, 2, Duration::from_secs(5))
+            .await
+            .unwrap();
+        sqlx::query("TRUNCATE guild, log, champion CASCADE")
+            .execute(&repository.pool)
+            .await
+            .unwrap();
+        repository
+    }
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "hide-on-bush".to_string(),
+            name: "Hide on bush".to_string(),
+            guild_id: 1,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Gold".to_string()),
+            lp: Some(45),
+            division: Some("II".to_string()),
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    fn game() -> GameDto {
+        GameDto {
+            id: "/match/na/4812345678".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 7,
+            deaths: 3,
+            kills: 10,
+            result: GameResult::Remake,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Soloqueue".to_string(),
+            lp_change: None,
+            promotion_text: None,
+            game_duration: Some(180),
+            lp_pending: true,
+            pentakills: None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn round_trips_every_table() {
+        let repository = repository().await;
+        GuildRepository::insert_or_ignore(&repository, &GuildDto::new(1, Some(2), "Guild".into()))
+            .await
+            .unwrap();
+        SummonerRepository::insert_or_ignore(&repository, &summoner())
+            .await
+            .unwrap();
+
+        let found = repository.get_by_name(1, "HIDE ON BUSH").await.unwrap();
+        assert_eq!(found.id, "hide-on-bush");
+        assert!(found.created_at.is_some());
+        assert_eq!(SummonerRepository::count(&repository).await.unwrap(), 1);
+
+        assert!(GameRepository::insert_or_ignore(&repository, &game())
+            .await
+            .unwrap());
+        assert!(!GameRepository::insert_or_ignore(&repository, &game())
+            .await
+            .unwrap());
+        let stored = GameRepository::get(&repository, &game().id).await.unwrap();
+        assert_eq!(stored.result, GameResult::Remake);
+        assert!(stored.lp_pending);
+
+        let snapshot = RankSnapshotDto::from_summoner(&summoner(), 1700000000);
+        repository.insert_if_changed(&snapshot).await.unwrap();
+        repository.insert_if_changed(&snapshot).await.unwrap();
+        assert_eq!(
+            repository
+                .get_all_for_summoner("hide-on-bush")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let mut template = GuildTemplateDto::new(1);
+        template.victory_title = Some("gg".to_string());
+        GuildTemplateRepository::upsert(&repository, &template)
+            .await
+            .unwrap();
+        let template = GuildTemplateRepository::get_for_guild(&repository, 1)
+            .await
+            .unwrap();
+        assert_eq!(template.victory_title.as_deref(), Some("gg"));
+
+        GuildRuleRepository::upsert(&repository, &GuildRuleDto::new(1, "win_streak", false))
+            .await
+            .unwrap();
+        ApiTokenRepository::upsert(&repository, &ApiTokenDto::new(1, "hash".to_string()))
+            .await
+            .unwrap();
+        assert!(repository.get_by_hash("hash").await.unwrap().is_some());
+        LogRepository::create(
+            &repository,
+            &LogDto::new("oops", crate::dtos::log_dto::ErrorType::Warn),
+        )
+        .await
+        .unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        let outbox =
+            NotificationOutboxDto::game_finished(1, "hide-on-bush", &game().id, &[], now).unwrap();
+        repository.enqueue(&outbox).await.unwrap();
+        repository.enqueue(&outbox).await.unwrap();
+        assert!(
+            GameRepository::get(&repository, &game().id)
+                .await
+                .unwrap()
+                .notified
+        );
+
+        let claimed = repository.claim_due(now, now + 60).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].kind, OutboxKind::GameFinished);
+        assert!(repository
+            .claim_due(now, now + 60)
+            .await
+            .unwrap()
+            .is_empty());
+        repository
+            .mark_failed(&claimed[0], "boom", None)
+            .await
+            .unwrap();
+        assert_eq!(repository.count_undelivered().await.unwrap(), (0, 1));
+
+        let missed = GameDto {
+            id: "/match/na/2".to_string(),
+            notified: true,
+            ..game()
+        };
+        let catch_up = NotificationOutboxDto::catch_up(1, "hide-on-bush", &missed.id, now);
+        assert!(repository
+            .insert_missed_game(&missed, &catch_up)
+            .await
+            .unwrap());
+        assert!(!repository
+            .insert_missed_game(&missed, &catch_up)
+            .await
+            .unwrap());
+        assert_eq!(repository.count_undelivered().await.unwrap(), (1, 1));
+
+        // Ids ignore case like on SQLite, and deleting only touches the given guild
+        GuildRepository::insert_or_ignore(&repository, &GuildDto::new(2, None, "Other".into()))
+            .await
+            .unwrap();
+        let shouted = SummonerDto {
+            id: "HIDE-ON-BUSH".to_string(),
+            guild_id: 2,
+            ..summoner()
+        };
+        SummonerRepository::insert_or_ignore(&repository, &shouted)
+            .await
+            .unwrap();
+        SummonerRepository::upsert(
+            &repository,
+            &SummonerDto {
+                lp: Some(50),
+                guild_id: 1,
+                ..shouted.clone()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(SummonerRepository::count(&repository).await.unwrap(), 1);
+        let found = SummonerRepository::get(&repository, "Hide-On-Bush")
+            .await
+            .unwrap();
+        assert_eq!((found.id.as_str(), found.lp), ("hide-on-bush", Some(50)));
+        SummonerRepository::delete(&repository, "HIDE-ON-BUSH", 2)
+            .await
+            .unwrap();
+        assert_eq!(SummonerRepository::count(&repository).await.unwrap(), 1);
+
+        // Everything hanging off the summoner goes with it
+        SummonerRepository::delete(&repository, "Hide-On-Bush", 1)
+            .await
+            .unwrap();
+        assert!(GameRepository::get(&repository, &game().id).await.is_err());
+        assert_eq!(repository.count_undelivered().await.unwrap(), (0, 0));
+    }
+}
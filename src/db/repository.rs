@@ -0,0 +1,181 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::dtos::{
+    active_game_dto::ActiveGameDto, api_token_dto::ApiTokenDto, champion_dto::ChampionDto,
+    game_dto::GameDto, guild_dto::GuildDto, guild_rule_dto::GuildRuleDto,
+    guild_template_dto::GuildTemplateDto, log_dto::LogDto,
+    notification_outbox_dto::NotificationOutboxDto, rank_snapshot_dto::RankSnapshotDto,
+    summoner_dto::SummonerDto,
+};
+
+// Lookups of a single row fail with `sqlx::Error::RowNotFound` when it doesn't exist,
+// the http server turns that into a 404.
+
+#[async_trait]
+pub trait SummonerRepository: Send + Sync {
+    async fn insert_or_ignore(&self, summoner: &SummonerDto) -> Result<()>;
+    /// Keeps who added the summoner
+    async fn upsert(&self, summoner: &SummonerDto) -> Result<()>;
+    async fn get_all(&self) -> Result<Vec<SummonerDto>>;
+    async fn get_all_for_guild(&self, guild_id: i64) -> Result<Vec<SummonerDto>>;
+    async fn count(&self) -> Result<i64>;
+    async fn get(&self, summoner_id: &str) -> Result<SummonerDto>;
+    /// Names are case insensitive
+    async fn get_by_name(&self, guild_id: i64, summoner_name: &str) -> Result<SummonerDto>;
+    /// Only if the summoner belongs to `guild_id`. Games, active games, rank
+    /// snapshots and queued notifications cascade.
+    async fn delete(&self, summoner_id: &str, guild_id: i64) -> Result<()>;
+}
+
+#[async_trait]
+pub trait GameRepository: Send + Sync {
+    /// True if the game wasn't stored yet
+    async fn insert_or_ignore(&self, game: &GameDto) -> Result<bool>;
+    async fn upsert(&self, game: &GameDto) -> Result<()>;
+    /// Fill in the lp of a pending game once a later poll found it
+    async fn resolve_pending_lp(&self, game: &GameDto) -> Result<()>;
+    async fn get(&self, game_id: &str) -> Result<GameDto>;
+    async fn get_unnotified_games_for_summoner(&self, summoner_id: &str) -> Result<Vec<GameDto>>;
+    /// Most recent first
+    async fn get_recent_for_summoner(&self, summoner_id: &str, limit: i64) -> Result<Vec<GameDto>>;
+    /// Games played before `before`, most recent first
+    async fn get_before_for_summoner(
+        &self,
+        summoner_id: &str,
+        before: i64,
+        limit: i64,
+    ) -> Result<Vec<GameDto>>;
+    /// Pentakills in games played before `before`, games that don't say count as none
+    async fn count_pentakills_before(&self, summoner_id: &str, before: i64) -> Result<i64>;
+    /// Games played since `since`, oldest first
+    async fn get_for_summoner_since(&self, summoner_id: &str, since: i64) -> Result<Vec<GameDto>>;
+}
+
+#[async_trait]
+pub trait ActiveGameRepository: Send + Sync {
+    async fn insert_or_ignore(&self, active_game: &ActiveGameDto) -> Result<()>;
+    async fn get_unnotified_active_games(&self) -> Result<Vec<ActiveGameDto>>;
+    async fn get(&self, active_game_id: &str) -> Result<ActiveGameDto>;
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<ActiveGameDto>>;
+}
+
+#[async_trait]
+pub trait GuildRepository: Send + Sync {
+    async fn insert_or_ignore(&self, guild: &GuildDto) -> Result<()>;
+    /// Channel and name only, the manager role is set on its own
+    async fn update(&self, guild: &GuildDto) -> Result<()>;
+    async fn set_manager_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<()>;
+    async fn get_all(&self) -> Result<Vec<GuildDto>>;
+    async fn get(&self, guild_id: i64) -> Result<GuildDto>;
+}
+
+#[async_trait]
+pub trait LogRepository: Send + Sync {
+    async fn create(&self, log: &LogDto) -> Result<()>;
+}
+
+#[async_trait]
+pub trait ChampionRepository: Send + Sync {
+    async fn upsert(&self, champion: &ChampionDto) -> Result<()>;
+    async fn get_all(&self) -> Result<Vec<ChampionDto>>;
+}
+
+#[async_trait]
+pub trait RankSnapshotRepository: Send + Sync {
+    async fn insert(&self, snapshot: &RankSnapshotDto) -> Result<()>;
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<RankSnapshotDto>>;
+    /// Rank the summoner had at `timestamp`
+    async fn get_latest_before(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>>;
+    /// First rank recorded at or after `timestamp`
+    async fn get_first_after(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>>;
+    /// Oldest first
+    async fn get_all_for_summoner(&self, summoner_id: &str) -> Result<Vec<RankSnapshotDto>>;
+
+    /// Only store a snapshot when the rank actually changed
+    async fn insert_if_changed(&self, snapshot: &RankSnapshotDto) -> Result<()> {
+        let latest = self.get_latest_for_summoner(&snapshot.summoner_id).await?;
+
+        if !latest.is_some_and(|latest| latest.is_same_rank(snapshot)) {
+            self.insert(snapshot).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    /// Replaces any existing token for the guild
+    async fn upsert(&self, api_token: &ApiTokenDto) -> Result<()>;
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenDto>>;
+    async fn delete(&self, guild_id: i64) -> Result<()>;
+}
+
+#[async_trait]
+pub trait GuildTemplateRepository: Send + Sync {
+    async fn upsert(&self, template: &GuildTemplateDto) -> Result<()>;
+    /// Empty overrides if the guild never customized anything
+    async fn get_for_guild(&self, guild_id: i64) -> Result<GuildTemplateDto>;
+    async fn delete(&self, guild_id: i64) -> Result<()>;
+}
+
+#[async_trait]
+pub trait GuildRuleRepository: Send + Sync {
+    async fn upsert(&self, rule: &GuildRuleDto) -> Result<()>;
+    async fn get_for_guild(&self, guild_id: i64) -> Result<Vec<GuildRuleDto>>;
+}
+
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    /// Insert and mark the game as notified in one transaction, so a game is
+    /// never queued twice or marked without being queued
+    async fn enqueue(&self, outbox: &NotificationOutboxDto) -> Result<()>;
+    /// Insert a game missed while offline together with its catch up in one
+    /// transaction. Nothing is queued if the game was already stored, returns
+    /// whether it was inserted.
+    async fn insert_missed_game(
+        &self,
+        game: &GameDto,
+        outbox: &NotificationOutboxDto,
+    ) -> Result<bool>;
+    /// Pending notifications whose next attempt is due, oldest first. Their next
+    /// attempt is pushed to `lease_until` so other instances leave them alone.
+    async fn claim_due(&self, now: i64, lease_until: i64) -> Result<Vec<NotificationOutboxDto>>;
+    async fn mark_sent(
+        &self,
+        outbox: &NotificationOutboxDto,
+        message_id: Option<&str>,
+        sent_at: i64,
+    ) -> Result<()>;
+    /// Try again at `next_attempt_at`, or give up if that's `None`
+    async fn mark_failed(
+        &self,
+        outbox: &NotificationOutboxDto,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> Result<()>;
+    /// (pending, dead)
+    async fn count_undelivered(&self) -> Result<(i64, i64)>;
+    /// Sent rows are only kept around for debugging
+    async fn delete_sent_before(&self, before: i64) -> Result<()>;
+}
+
+/// Things that aren't about a single table
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// e.g. `sqlite`, shown in logs
+    fn name(&self) -> &'static str;
+    async fn ping(&self) -> Result<()>;
+    /// Consistent copy of the whole database at `path`, while it's in use
+    async fn backup(&self, path: &str) -> Result<()>;
+    async fn close(&self);
+}
@@ -0,0 +1,1201 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    ConnectOptions, Connection, Pool, Sqlite, SqliteExecutor,
+};
+
+use super::repository::{
+    ActiveGameRepository, ApiTokenRepository, Backend, ChampionRepository, GameRepository,
+    GuildRepository, GuildRuleRepository, GuildTemplateRepository, LogRepository, OutboxRepository,
+    RankSnapshotRepository, SummonerRepository,
+};
+use crate::dtos::{
+    active_game_dto::ActiveGameDto,
+    api_token_dto::ApiTokenDto,
+    champion_dto::ChampionDto,
+    game_dto::GameDto,
+    guild_dto::GuildDto,
+    guild_rule_dto::GuildRuleDto,
+    guild_template_dto::GuildTemplateDto,
+    log_dto::LogDto,
+    notification_outbox_dto::{NotificationOutboxDto, OutboxKind, OutboxStatus},
+    rank_snapshot_dto::RankSnapshotDto,
+    summoner_dto::SummonerDto,
+};
+
+/// How long a write waits for the lock instead of failing with "database is locked"
+static BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct SqliteRepository {
+    pool: Pool<Sqlite>,
+}
+
+/// A `game` row as stored. `query_as!` can only convert columns with `Into`,
+/// so `result` is checked when it's turned into a `GameDto`.
+struct GameRow {
+    id: String,
+    summoner_id: String,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    game_created_at: i64,
+    assists: i64,
+    deaths: i64,
+    kills: i64,
+    result: String,
+    notified: bool,
+    champion_name: String,
+    game_mode: String,
+    lp_change: Option<i64>,
+    promotion_text: Option<String>,
+    game_duration: Option<i64>,
+    lp_pending: bool,
+    pentakills: Option<i64>,
+}
+
+impl TryFrom<GameRow> for GameDto {
+    type Error = anyhow::Error;
+
+    fn try_from(row: GameRow) -> Result<Self> {
+        Ok(GameDto {
+            result: row
+                .result
+                .try_into()
+                .with_context(|| format!("unable to read game {}", row.id))?,
+            id: row.id,
+            summoner_id: row.summoner_id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            game_created_at: row.game_created_at,
+            assists: row.assists,
+            deaths: row.deaths,
+            kills: row.kills,
+            notified: row.notified,
+            champion_name: row.champion_name,
+            game_mode: row.game_mode,
+            lp_change: row.lp_change,
+            promotion_text: row.promotion_text,
+            game_duration: row.game_duration,
+            lp_pending: row.lp_pending,
+            pentakills: row.pentakills,
+        })
+    }
+}
+
+impl SqliteRepository {
+    /// - create the file if it's missing
+    /// - WAL so readers don't block the workers writing
+    /// - run migrations
+    pub async fn connect(
+        url: &str,
+        max_connections: u32,
+        acquire_timeout: Duration,
+    ) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)
+            .with_context(|| format!("unable to parse DATABASE_URL: {}", url))?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            // Safe with WAL, only the last transactions can be lost on power loss
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        run_migrations(&options).await?;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .min_connections(1)
+            .acquire_timeout(acquire_timeout)
+            .connect_with(options.foreign_keys(true))
+            .await?;
+
+        Ok(Self { pool })
+    }
+}
+
+/// Migrations that rebuild tables drop tables other tables point to, which
+/// only works with foreign keys off. They're checked once everything ran.
+async fn run_migrations(options: &SqliteConnectOptions) -> Result<()> {
+    let mut conn = options.clone().foreign_keys(false).connect().await?;
+
+    sqlx::migrate!("./migrations/sqlite").run(&mut conn).await?;
+
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut conn)
+        .await?;
+    if !violations.is_empty() {
+        bail!(
+            "database has {} rows with broken foreign keys",
+            violations.len()
+        );
+    }
+
+    conn.close().await?;
+    Ok(())
+}
+
+#[async_trait]
+impl Backend for SqliteRepository {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn backup(&self, path: &str) -> Result<()> {
+        // Fails if the file already exists, so an old backup is never overwritten
+        sqlx::query("VACUUM INTO ?")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+#[async_trait]
+impl SummonerRepository for SqliteRepository {
+    async fn insert_or_ignore(&self, summoner: &SummonerDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO summoner (
+                id,
+                name,
+                guild_id,
+                queue_type,
+                tier,
+                lp,
+                division,
+                icon_url,
+                added_by
+                )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+            "#,
+            summoner.id,
+            summoner.name,
+            summoner.guild_id,
+            summoner.queue_type,
+            summoner.tier,
+            summoner.lp,
+            summoner.division,
+            summoner.icon_url,
+            summoner.added_by
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upsert(&self, summoner: &SummonerDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO summoner (
+                id,
+                name,
+                guild_id,
+                queue_type,
+                tier,
+                lp,
+                division,
+                icon_url,
+                added_by
+                )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name,
+                guild_id = excluded.guild_id,
+                queue_type = excluded.queue_type,
+                tier = excluded.tier,
+                lp = excluded.lp,
+                division = excluded.division,
+                icon_url = excluded.icon_url;
+            "#,
+            summoner.id,
+            summoner.name,
+            summoner.guild_id,
+            summoner.queue_type,
+            summoner.tier,
+            summoner.lp,
+            summoner.division,
+            summoner.icon_url,
+            summoner.added_by
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<SummonerDto>> {
+        let summoners = sqlx::query_as!(SummonerDto, "SELECT * FROM summoner")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(summoners)
+    }
+
+    async fn get_all_for_guild(&self, guild_id: i64) -> Result<Vec<SummonerDto>> {
+        let summoners = sqlx::query_as!(
+            SummonerDto,
+            r#"
+            SELECT * FROM summoner
+            WHERE guild_id = ?;
+            "#,
+            guild_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summoners)
+    }
+
+    async fn count(&self) -> Result<i64> {
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!: i64" FROM summoner"#)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn get(&self, summoner_id: &str) -> Result<SummonerDto> {
+        let summoner = sqlx::query_as!(
+            SummonerDto,
+            r#"
+            SELECT * FROM summoner
+            WHERE id = ?;
+            "#,
+            summoner_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(summoner)
+    }
+
+    async fn get_by_name(&self, guild_id: i64, summoner_name: &str) -> Result<SummonerDto> {
+        let summoner = sqlx::query_as!(
+            SummonerDto,
+            r#"
+            SELECT * FROM summoner
+            WHERE guild_id = ? AND name = ?;
+            "#,
+            guild_id,
+            summoner_name
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(summoner)
+    }
+
+    async fn delete(&self, summoner_id: &str, guild_id: i64) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM summoner WHERE id = ? AND guild_id = ?",
+            summoner_id,
+            guild_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Shared by `insert_or_ignore` and `insert_missed_game`, true if it was inserted
+async fn insert_game(executor: impl SqliteExecutor<'_>, game: &GameDto) -> Result<bool> {
+    let result: String = game.result.into();
+    let inserted = sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO game (
+            id,
+            summoner_id,
+            game_created_at,
+            assists,
+            deaths,
+            kills,
+            result,
+            notified,
+            lp_change,
+            champion_name,
+            game_mode,
+            promotion_text,
+            game_duration,
+            lp_pending,
+            pentakills
+            )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
+        "#,
+        game.id,
+        game.summoner_id,
+        game.game_created_at,
+        game.assists,
+        game.deaths,
+        game.kills,
+        result,
+        game.notified,
+        game.lp_change,
+        game.champion_name,
+        game.game_mode,
+        game.promotion_text,
+        game.game_duration,
+        game.lp_pending,
+        game.pentakills
+    )
+    .execute(executor)
+    .await?
+    .rows_affected();
+
+    Ok(inserted > 0)
+}
+
+#[async_trait]
+impl GameRepository for SqliteRepository {
+    async fn insert_or_ignore(&self, game: &GameDto) -> Result<bool> {
+        insert_game(&self.pool, game).await
+    }
+
+    async fn upsert(&self, game: &GameDto) -> Result<()> {
+        let result: String = game.result.into();
+        sqlx::query!(
+            r#"
+            INSERT INTO game (
+                id,
+                summoner_id,
+                game_created_at,
+                assists,
+                deaths,
+                kills,
+                result,
+                notified,
+                lp_change,
+                champion_name,
+                game_mode,
+                promotion_text,
+                game_duration,
+                lp_pending,
+                pentakills
+                )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                summoner_id = excluded.summoner_id,
+                game_created_at = excluded.game_created_at,
+                assists = excluded.assists,
+                deaths = excluded.deaths,
+                kills = excluded.kills,
+                result = excluded.result,
+                notified = excluded.notified,
+                lp_change = excluded.lp_change,
+                champion_name = excluded.champion_name,
+                game_mode = excluded.game_mode,
+                promotion_text = excluded.promotion_text,
+                game_duration = excluded.game_duration,
+                lp_pending = excluded.lp_pending,
+                pentakills = excluded.pentakills;
+            "#,
+            game.id,
+            game.summoner_id,
+            game.game_created_at,
+            game.assists,
+            game.deaths,
+            game.kills,
+            result,
+            game.notified,
+            game.lp_change,
+            game.champion_name,
+            game.game_mode,
+            game.promotion_text,
+            game.game_duration,
+            game.lp_pending,
+            game.pentakills
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn resolve_pending_lp(&self, game: &GameDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE game
+            SET lp_change = ?, promotion_text = ?, lp_pending = 0
+            WHERE id = ? AND lp_pending = 1;
+            "#,
+            game.lp_change,
+            game.promotion_text,
+            game.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, game_id: &str) -> Result<GameDto> {
+        let game = sqlx::query_as!(GameRow, "SELECT * FROM game WHERE id = ?", game_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        game.try_into()
+    }
+
+    async fn get_unnotified_games_for_summoner(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as!(
+            GameRow,
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = ? AND notified = 0;
+            "#,
+            summoner_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        games.into_iter().map(GameDto::try_from).collect()
+    }
+
+    async fn get_recent_for_summoner(&self, summoner_id: &str, limit: i64) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as!(
+            GameRow,
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = ?
+            ORDER BY game_created_at DESC
+            LIMIT ?;
+            "#,
+            summoner_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        games.into_iter().map(GameDto::try_from).collect()
+    }
+
+    async fn get_before_for_summoner(
+        &self,
+        summoner_id: &str,
+        before: i64,
+        limit: i64,
+    ) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as!(
+            GameRow,
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = ? AND game_created_at < ?
+            ORDER BY game_created_at DESC
+            LIMIT ?;
+            "#,
+            summoner_id,
+            before,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        games.into_iter().map(GameDto::try_from).collect()
+    }
+
+    async fn count_pentakills_before(&self, summoner_id: &str, before: i64) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(pentakills), 0) as "count!: i64" FROM game
+            WHERE summoner_id = ? AND game_created_at < ?;
+            "#,
+            summoner_id,
+            before
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_for_summoner_since(&self, summoner_id: &str, since: i64) -> Result<Vec<GameDto>> {
+        let games = sqlx::query_as!(
+            GameRow,
+            r#"
+            SELECT * FROM game
+            WHERE summoner_id = ? AND game_created_at >= ?
+            ORDER BY game_created_at;
+            "#,
+            summoner_id,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        games.into_iter().map(GameDto::try_from).collect()
+    }
+}
+
+#[async_trait]
+impl ActiveGameRepository for SqliteRepository {
+    async fn insert_or_ignore(&self, active_game: &ActiveGameDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO active_game (
+                id,
+                summoner_id,
+                game_created_at,
+                champion,
+                role,
+                spectate_link,
+                notified,
+                game_mode
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            active_game.id,
+            active_game.summoner_id,
+            active_game.game_created_at,
+            active_game.champion,
+            active_game.role,
+            active_game.spectate_link,
+            active_game.notified,
+            active_game.game_mode,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_unnotified_active_games(&self) -> Result<Vec<ActiveGameDto>> {
+        let active_games = sqlx::query_as!(
+            ActiveGameDto,
+            r#"
+            SELECT *
+            FROM active_game
+            WHERE notified = 0
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(active_games)
+    }
+
+    async fn get(&self, active_game_id: &str) -> Result<ActiveGameDto> {
+        let active_game = sqlx::query_as!(
+            ActiveGameDto,
+            "SELECT * FROM active_game WHERE id = ?",
+            active_game_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(active_game)
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<ActiveGameDto>> {
+        let active_game = sqlx::query_as!(
+            ActiveGameDto,
+            r#"
+            SELECT *
+            FROM active_game
+            WHERE summoner_id = ?
+            ORDER BY game_created_at DESC
+            LIMIT 1
+            "#,
+            summoner_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(active_game)
+    }
+}
+
+#[async_trait]
+impl GuildRepository for SqliteRepository {
+    async fn insert_or_ignore(&self, guild: &GuildDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO guild (
+               id,
+               chat_channel_id,
+               name
+            )
+            VALUES (?, ?, ?);
+            "#,
+            guild.id,
+            guild.chat_channel_id,
+            guild.name
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update(&self, guild: &GuildDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE guild
+            SET chat_channel_id = ?,
+                name = ?
+            WHERE id = ?;
+            "#,
+            guild.chat_channel_id,
+            guild.name,
+            guild.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_manager_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE guild SET manager_role_id = ? WHERE id = ?",
+            role_id,
+            guild_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<GuildDto>> {
+        let guilds = sqlx::query_as!(GuildDto, "SELECT * FROM guild")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(guilds)
+    }
+
+    async fn get(&self, guild_id: i64) -> Result<GuildDto> {
+        let guild = sqlx::query_as!(
+            GuildDto,
+            r#"
+            SELECT * FROM guild
+            WHERE id = ?;
+            "#,
+            guild_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(guild)
+    }
+}
+
+#[async_trait]
+impl LogRepository for SqliteRepository {
+    async fn create(&self, log: &LogDto) -> Result<()> {
+        let error_type: String = log.error_type.into();
+        sqlx::query!(
+            r#"
+            INSERT INTO log (
+                message,
+                error_type,
+                target,
+                summoner_id,
+                guild_id,
+                strategy,
+                url
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?);
+            "#,
+            log.message,
+            error_type,
+            log.target,
+            log.summoner_id,
+            log.guild_id,
+            log.strategy,
+            log.url
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChampionRepository for SqliteRepository {
+    async fn upsert(&self, champion: &ChampionDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO champion (
+                id,
+                key,
+                name,
+                version
+                )
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE
+            SET key = excluded.key,
+                name = excluded.name,
+                version = excluded.version;
+            "#,
+            champion.id,
+            champion.key,
+            champion.name,
+            champion.version
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_all(&self) -> Result<Vec<ChampionDto>> {
+        let champions = sqlx::query_as!(ChampionDto, "SELECT * FROM champion")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(champions)
+    }
+}
+
+#[async_trait]
+impl RankSnapshotRepository for SqliteRepository {
+    async fn insert(&self, snapshot: &RankSnapshotDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO rank_snapshot (
+                summoner_id,
+                created_at,
+                queue_type,
+                tier,
+                division,
+                lp
+                )
+            VALUES (?, ?, ?, ?, ?, ?);
+            "#,
+            snapshot.summoner_id,
+            snapshot.created_at,
+            snapshot.queue_type,
+            snapshot.tier,
+            snapshot.division,
+            snapshot.lp
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_latest_for_summoner(&self, summoner_id: &str) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as!(
+            RankSnapshotDto,
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1;
+            "#,
+            summoner_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_latest_before(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as!(
+            RankSnapshotDto,
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = ? AND created_at <= ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1;
+            "#,
+            summoner_id,
+            timestamp
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_first_after(
+        &self,
+        summoner_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<RankSnapshotDto>> {
+        let snapshot = sqlx::query_as!(
+            RankSnapshotDto,
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = ? AND created_at >= ?
+            ORDER BY created_at, id
+            LIMIT 1;
+            "#,
+            summoner_id,
+            timestamp
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    async fn get_all_for_summoner(&self, summoner_id: &str) -> Result<Vec<RankSnapshotDto>> {
+        let snapshots = sqlx::query_as!(
+            RankSnapshotDto,
+            r#"
+            SELECT * FROM rank_snapshot
+            WHERE summoner_id = ?
+            ORDER BY created_at, id;
+            "#,
+            summoner_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for SqliteRepository {
+    async fn upsert(&self, api_token: &ApiTokenDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO api_token (guild_id, token_hash)
+            VALUES (?, ?)
+            ON CONFLICT (guild_id) DO UPDATE SET
+                token_hash = excluded.token_hash,
+                created_at = strftime('%s', 'now');
+            "#,
+            api_token.guild_id,
+            api_token.token_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenDto>> {
+        let token = sqlx::query_as!(
+            ApiTokenDto,
+            "SELECT * FROM api_token WHERE token_hash = ?",
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM api_token WHERE guild_id = ?", guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildTemplateRepository for SqliteRepository {
+    async fn upsert(&self, template: &GuildTemplateDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO guild_template (
+                guild_id,
+                victory_title,
+                defeat_title,
+                in_game_title,
+                victory_color,
+                defeat_color,
+                in_game_color,
+                victory_emoji,
+                defeat_emoji,
+                in_game_emoji,
+                game_fields,
+                active_game_fields,
+                demotion_text,
+                remake_title,
+                remake_color,
+                remake_emoji,
+                remake_notifications
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (guild_id) DO UPDATE SET
+                victory_title = excluded.victory_title,
+                defeat_title = excluded.defeat_title,
+                in_game_title = excluded.in_game_title,
+                victory_color = excluded.victory_color,
+                defeat_color = excluded.defeat_color,
+                in_game_color = excluded.in_game_color,
+                victory_emoji = excluded.victory_emoji,
+                defeat_emoji = excluded.defeat_emoji,
+                in_game_emoji = excluded.in_game_emoji,
+                game_fields = excluded.game_fields,
+                active_game_fields = excluded.active_game_fields,
+                demotion_text = excluded.demotion_text,
+                remake_title = excluded.remake_title,
+                remake_color = excluded.remake_color,
+                remake_emoji = excluded.remake_emoji,
+                remake_notifications = excluded.remake_notifications;
+            "#,
+            template.guild_id,
+            template.victory_title,
+            template.defeat_title,
+            template.in_game_title,
+            template.victory_color,
+            template.defeat_color,
+            template.in_game_color,
+            template.victory_emoji,
+            template.defeat_emoji,
+            template.in_game_emoji,
+            template.game_fields,
+            template.active_game_fields,
+            template.demotion_text,
+            template.remake_title,
+            template.remake_color,
+            template.remake_emoji,
+            template.remake_notifications
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<GuildTemplateDto> {
+        let template = sqlx::query_as!(
+            GuildTemplateDto,
+            "SELECT * FROM guild_template WHERE guild_id = ?",
+            guild_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(template.unwrap_or_else(|| GuildTemplateDto::new(guild_id)))
+    }
+
+    async fn delete(&self, guild_id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM guild_template WHERE guild_id = ?", guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GuildRuleRepository for SqliteRepository {
+    async fn upsert(&self, rule: &GuildRuleDto) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO guild_rule (guild_id, rule, enabled)
+            VALUES (?, ?, ?)
+            ON CONFLICT (guild_id, rule) DO UPDATE SET
+                enabled = excluded.enabled;
+            "#,
+            rule.guild_id,
+            rule.rule,
+            rule.enabled
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_for_guild(&self, guild_id: i64) -> Result<Vec<GuildRuleDto>> {
+        let rules = sqlx::query_as!(
+            GuildRuleDto,
+            "SELECT * FROM guild_rule WHERE guild_id = ?",
+            guild_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rules)
+    }
+}
+
+/// Shared by `enqueue` and `insert_missed_game`
+async fn insert_outbox(
+    executor: impl SqliteExecutor<'_>,
+    outbox: &NotificationOutboxDto,
+) -> Result<()> {
+    let kind: String = outbox.kind.into();
+    sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO notification_outbox (
+            guild_id,
+            summoner_id,
+            kind,
+            game_id,
+            highlights,
+            next_attempt_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?);
+        "#,
+        outbox.guild_id,
+        outbox.summoner_id,
+        kind,
+        outbox.game_id,
+        outbox.highlights,
+        outbox.next_attempt_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl OutboxRepository for SqliteRepository {
+    async fn enqueue(&self, outbox: &NotificationOutboxDto) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        insert_outbox(&mut *tx, outbox).await?;
+
+        match outbox.kind {
+            OutboxKind::GameFinished | OutboxKind::CatchUp => {
+                sqlx::query!(
+                    "UPDATE game SET notified = 1, lp_pending = 0 WHERE id = ?",
+                    outbox.game_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            OutboxKind::GameStarted => {
+                sqlx::query!(
+                    "UPDATE active_game SET notified = 1 WHERE id = ?",
+                    outbox.game_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_missed_game(
+        &self,
+        game: &GameDto,
+        outbox: &NotificationOutboxDto,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let inserted = insert_game(&mut *tx, game).await?;
+        if inserted {
+            insert_outbox(&mut *tx, outbox).await?;
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn claim_due(&self, now: i64, lease_until: i64) -> Result<Vec<NotificationOutboxDto>> {
+        let mut outbox = sqlx::query_as!(
+            NotificationOutboxDto,
+            r#"
+            UPDATE notification_outbox
+            SET next_attempt_at = ?
+            WHERE status = 'pending' AND next_attempt_at <= ?
+            RETURNING *;
+            "#,
+            lease_until,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // RETURNING doesn't keep any order
+        outbox.sort_by_key(|o| o.id);
+        Ok(outbox)
+    }
+
+    async fn mark_sent(
+        &self,
+        outbox: &NotificationOutboxDto,
+        message_id: Option<&str>,
+        sent_at: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE notification_outbox
+            SET status = 'sent', attempts = attempts + 1, message_id = ?, sent_at = ?, last_error = NULL
+            WHERE id = ?;
+            "#,
+            message_id,
+            sent_at,
+            outbox.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(
+        &self,
+        outbox: &NotificationOutboxDto,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> Result<()> {
+        let status: String = match next_attempt_at {
+            Some(_) => OutboxStatus::Pending.into(),
+            None => OutboxStatus::Dead.into(),
+        };
+        let next_attempt_at = next_attempt_at.unwrap_or(outbox.next_attempt_at);
+
+        sqlx::query!(
+            r#"
+            UPDATE notification_outbox
+            SET status = ?, attempts = attempts + 1, next_attempt_at = ?, last_error = ?
+            WHERE id = ?;
+            "#,
+            status,
+            next_attempt_at,
+            error,
+            outbox.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_undelivered(&self) -> Result<(i64, i64)> {
+        let counts = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'pending') AS "pending!: i64",
+                COUNT(*) FILTER (WHERE status = 'dead') AS "dead!: i64"
+            FROM notification_outbox;
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((counts.pending, counts.dead))
+    }
+
+    async fn delete_sent_before(&self, before: i64) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM notification_outbox WHERE status = 'sent' AND sent_at < ?",
+            before
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
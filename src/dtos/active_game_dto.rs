@@ -1,7 +1,6 @@
-use anyhow::Result;
-use sqlx::{Pool, Sqlite};
+use serde::Serialize;
 
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 pub struct ActiveGameDto {
     pub id: String,
     pub summoner_id: String,
@@ -13,71 +12,3 @@ pub struct ActiveGameDto {
     pub notified: bool,
     pub game_mode: String,
 }
-
-impl ActiveGameDto {
-    pub async fn upsert(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        sqlx::query!(
-            r#"
-            INSERT OR REPLACE INTO active_game (id, summoner_id, game_created_at, champion, role, spectate_link, notified, game_mode)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-            self.id,
-            self.summoner_id,
-            self.game_created_at,
-            self.champion,
-            self.role,
-            self.spectate_link,
-            self.notified,
-            self.game_mode,
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn insert_or_ignore(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        sqlx::query!(
-            r#"
-            INSERT OR IGNORE INTO active_game (
-                id,
-                summoner_id,
-                game_created_at,
-                champion,
-                role,
-                spectate_link,
-                notified,
-                game_mode
-            )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-            self.id,
-            self.summoner_id,
-            self.game_created_at,
-            self.champion,
-            self.role,
-            self.spectate_link,
-            self.notified,
-            self.game_mode,
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn get_unnotified_active_games(pool: &Pool<Sqlite>) -> Result<Vec<ActiveGameDto>> {
-        let active_games = sqlx::query_as!(
-            ActiveGameDto,
-            r#"
-            SELECT *
-            FROM active_game
-            WHERE notified = 0
-            "#,
-        )
-        .fetch_all(pool)
-        .await?;
-
-        Ok(active_games)
-    }
-}
@@ -0,0 +1,17 @@
+/// A guild's REST api token. Only the hash is stored.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiTokenDto {
+    pub guild_id: i64,
+    pub token_hash: String,
+    pub created_at: Option<i64>,
+}
+
+impl ApiTokenDto {
+    pub fn new(guild_id: i64, token_hash: String) -> Self {
+        Self {
+            guild_id,
+            token_hash,
+            created_at: None,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ChampionDto {
+    /// Data Dragon id e.g. "MonkeyKing"
+    pub id: String,
+    /// Riot numeric champion id
+    pub key: i64,
+    /// Display name e.g. "Wukong"
+    pub name: String,
+    /// Data Dragon patch version this record was loaded from
+    pub version: String,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl ChampionDto {
+    pub fn new(id: String, key: i64, name: String, version: String) -> Self {
+        Self {
+            id,
+            key,
+            name,
+            version,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
@@ -1,7 +1,6 @@
-use anyhow::Result;
-use sqlx::{Pool, Sqlite};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct GameDto {
     pub id: String,
     pub summoner_id: String,
@@ -11,127 +10,61 @@ pub struct GameDto {
     pub assists: i64,
     pub deaths: i64,
     pub kills: i64,
-    pub win: bool,
+    #[sqlx(try_from = "String")]
+    pub result: GameResult,
     pub notified: bool,
     pub champion_name: String,
     pub game_mode: String,
     pub lp_change: Option<i64>,
     pub promotion_text: Option<String>,
+    /// Seconds, `None` if the scraper couldn't find it
+    pub game_duration: Option<i64>,
+    /// Ranked game scraped without its lp change, see `is_missing_lp`
+    pub lp_pending: bool,
+    /// `None` if the match history didn't say
+    #[serde(default)]
+    pub pentakills: Option<i64>,
 }
 
-impl GameDto {
-    pub async fn insert_or_ignore(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        sqlx::query!(
-            r#"
-            INSERT OR IGNORE INTO game (
-                id,
-                summoner_id,
-                game_created_at,
-                assists,
-                deaths,
-                kills,
-                win,
-                notified,
-                lp_change,
-                champion_name,
-                game_mode,
-                promotion_text
-                )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
-            "#,
-            self.id,
-            self.summoner_id,
-            self.game_created_at,
-            self.assists,
-            self.deaths,
-            self.kills,
-            self.win,
-            self.notified,
-            self.lp_change,
-            self.champion_name,
-            self.game_mode,
-            self.promotion_text
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn upsert(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        sqlx::query!(
-            r#"
-            INSERT OR REPLACE INTO game (
-                id,
-                summoner_id,
-                game_created_at,
-                assists,
-                deaths,
-                kills,
-                win,
-                notified,
-                lp_change,
-                champion_name,
-                game_mode,
-                promotion_text
-                )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
-            "#,
-            self.id,
-            self.summoner_id,
-            self.game_created_at,
-            self.assists,
-            self.deaths,
-            self.kills,
-            self.win,
-            self.notified,
-            self.lp_change,
-            self.champion_name,
-            self.game_mode,
-            self.promotion_text
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn get_all(pool: &Pool<Sqlite>) -> Result<Vec<GameDto>> {
-        let games = sqlx::query_as!(GameDto, "SELECT * FROM game")
-            .fetch_all(pool)
-            .await?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Win,
+    Loss,
+    /// Game ended early because someone never connected, no lp is lost
+    Remake,
+}
 
-        Ok(games)
+impl From<GameResult> for String {
+    fn from(result: GameResult) -> Self {
+        match result {
+            GameResult::Win => "win".to_string(),
+            GameResult::Loss => "loss".to_string(),
+            GameResult::Remake => "remake".to_string(),
+        }
     }
+}
 
-    pub async fn get_unnotified_games_for_summoner(
-        pool: &Pool<Sqlite>,
-        summoner_id: &str,
-    ) -> Result<Vec<GameDto>> {
-        let games = sqlx::query_as!(
-            GameDto,
-            r#"
-            SELECT * FROM game
-            WHERE summoner_id = ? AND notified = 0;
-            "#,
-            summoner_id
-        )
-        .fetch_all(pool)
-        .await?;
+impl TryFrom<String> for GameResult {
+    type Error = anyhow::Error;
 
-        Ok(games)
+    fn try_from(result: String) -> anyhow::Result<Self> {
+        match result.as_str() {
+            "win" => Ok(GameResult::Win),
+            "loss" => Ok(GameResult::Loss),
+            "remake" => Ok(GameResult::Remake),
+            _ => anyhow::bail!("invalid game result: {}", result),
+        }
     }
+}
 
-    pub async fn set_all_notified(pool: &Pool<Sqlite>) -> Result<()> {
-        sqlx::query!(
-            r#"
-            UPDATE game
-            SET notified = 1;
-            "#
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
+impl GameDto {
+    /// Ranked games always change lp unless they were remade, League of Graphs
+    /// sometimes shows them a few minutes before the lp
+    pub fn is_missing_lp(&self) -> bool {
+        self.game_mode.to_lowercase().contains("ranked")
+            && self.result != GameResult::Remake
+            && self.lp_change.is_none()
+            && self.promotion_text.is_none()
     }
 }
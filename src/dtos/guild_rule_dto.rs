@@ -0,0 +1,21 @@
+/// Whether a streak/milestone rule is on for a guild
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GuildRuleDto {
+    pub guild_id: i64,
+    pub rule: String,
+    pub enabled: bool,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+impl GuildRuleDto {
+    pub fn new(guild_id: i64, rule: &str, enabled: bool) -> Self {
+        Self {
+            guild_id,
+            rule: rule.to_string(),
+            enabled,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
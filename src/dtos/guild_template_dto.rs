@@ -0,0 +1,35 @@
+/// Per guild notification overrides, `None` means use the default
+#[derive(Debug, Default, Clone, sqlx::FromRow)]
+pub struct GuildTemplateDto {
+    pub guild_id: i64,
+    pub victory_title: Option<String>,
+    pub defeat_title: Option<String>,
+    pub in_game_title: Option<String>,
+    pub victory_color: Option<String>,
+    pub defeat_color: Option<String>,
+    pub in_game_color: Option<String>,
+    pub victory_emoji: Option<String>,
+    pub defeat_emoji: Option<String>,
+    pub in_game_emoji: Option<String>,
+    /// Comma separated field names
+    pub game_fields: Option<String>,
+    /// Comma separated field names
+    pub active_game_fields: Option<String>,
+    pub demotion_text: Option<String>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+    pub remake_title: Option<String>,
+    pub remake_color: Option<String>,
+    pub remake_emoji: Option<String>,
+    /// `label` or `suppress`
+    pub remake_notifications: Option<String>,
+}
+
+impl GuildTemplateDto {
+    pub fn new(guild_id: i64) -> Self {
+        Self {
+            guild_id,
+            ..Default::default()
+        }
+    }
+}
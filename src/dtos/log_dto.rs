@@ -1,32 +1,40 @@
-use anyhow::Result;
-use sqlx::{Pool, Sqlite};
-
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct LogDto {
     pub id: Option<i64>,
     pub message: String,
+    #[sqlx(try_from = "String")]
     pub error_type: ErrorType,
     pub created_at: Option<i64>,
+    /// Rust module the event came from
+    pub target: Option<String>,
+    pub summoner_id: Option<String>,
+    pub guild_id: Option<i64>,
+    pub strategy: Option<String>,
+    pub url: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum ErrorType {
     Info,
+    Warn,
     Error,
 }
 
-impl Into<String> for ErrorType {
-    fn into(self) -> String {
-        match self {
+impl From<ErrorType> for String {
+    fn from(error_type: ErrorType) -> Self {
+        match error_type {
             ErrorType::Info => "info".to_string(),
+            ErrorType::Warn => "warn".to_string(),
             ErrorType::Error => "error".to_string(),
         }
     }
 }
 
-impl Into<ErrorType> for String {
-    fn into(self) -> ErrorType {
-        match self.as_str() {
+impl From<String> for ErrorType {
+    fn from(error_type: String) -> Self {
+        match error_type.as_str() {
             "info" => ErrorType::Info,
+            "warn" => ErrorType::Warn,
             "error" => ErrorType::Error,
             _ => panic!("invalid error type"),
         }
@@ -40,53 +48,11 @@ impl LogDto {
             message: message.to_string(),
             error_type,
             created_at: None,
+            target: None,
+            summoner_id: None,
+            guild_id: None,
+            strategy: None,
+            url: None,
         }
     }
-
-    pub async fn info(pool: &Pool<Sqlite>, message: &str) {
-        match Self::new(message, ErrorType::Info).create(pool).await {
-            Ok(_) => {}
-            Err(e) => {
-                // Fallback to console logging
-                println!("log info: {}", e.to_string());
-            }
-        };
-    }
-
-    pub async fn error(pool: &Pool<Sqlite>, message: &str) {
-        match Self::new(message, ErrorType::Error).create(pool).await {
-            Ok(_) => {}
-            Err(e) => {
-                // Fallback to console logging
-                println!("log error: {}", e.to_string());
-            }
-        };
-    }
-
-    pub async fn create(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        let error_type: String = self.error_type.into();
-        sqlx::query!(
-            r#"
-            INSERT INTO log (
-                message,
-                error_type
-            )
-            VALUES (?, ?);
-            "#,
-            self.message,
-            error_type
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn get_all(pool: &Pool<Sqlite>) -> Result<Vec<LogDto>> {
-        let logs = sqlx::query_as!(LogDto, "SELECT * FROM log")
-            .fetch_all(pool)
-            .await?;
-
-        Ok(logs)
-    }
 }
@@ -1,5 +1,14 @@
+// DTOs mirror the database tables so not every column is read
+#![allow(dead_code)]
+
 pub mod active_game_dto;
+pub mod api_token_dto;
+pub mod champion_dto;
 pub mod game_dto;
 pub mod guild_dto;
+pub mod guild_rule_dto;
+pub mod guild_template_dto;
 pub mod log_dto;
+pub mod notification_outbox_dto;
+pub mod rank_snapshot_dto;
 pub mod summoner_dto;
@@ -0,0 +1,143 @@
+use anyhow::Result;
+
+/// A notification waiting to be delivered, see the outbox worker in `Facade`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct NotificationOutboxDto {
+    pub id: Option<i64>,
+    pub guild_id: i64,
+    pub summoner_id: String,
+    #[sqlx(try_from = "String")]
+    pub kind: OutboxKind,
+    /// `game.id` or `active_game.id` depending on `kind`
+    pub game_id: String,
+    /// JSON array, only used for finished games
+    pub highlights: String,
+    #[sqlx(try_from = "String")]
+    pub status: OutboxStatus,
+    pub attempts: i64,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    /// Discord message id once sent
+    pub message_id: Option<String>,
+    pub sent_at: Option<i64>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxKind {
+    GameFinished,
+    GameStarted,
+    /// Played while the bot was offline, delivered with the other catch ups of
+    /// the guild as one summary
+    CatchUp,
+}
+
+impl From<OutboxKind> for String {
+    fn from(kind: OutboxKind) -> Self {
+        match kind {
+            OutboxKind::GameFinished => "game_finished".to_string(),
+            OutboxKind::GameStarted => "game_started".to_string(),
+            OutboxKind::CatchUp => "catch_up".to_string(),
+        }
+    }
+}
+
+impl From<String> for OutboxKind {
+    fn from(kind: String) -> Self {
+        match kind.as_str() {
+            "game_finished" => OutboxKind::GameFinished,
+            "game_started" => OutboxKind::GameStarted,
+            "catch_up" => OutboxKind::CatchUp,
+            _ => panic!("invalid outbox kind"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    /// Gave up after too many attempts
+    Dead,
+}
+
+impl From<OutboxStatus> for String {
+    fn from(status: OutboxStatus) -> Self {
+        match status {
+            OutboxStatus::Pending => "pending".to_string(),
+            OutboxStatus::Sent => "sent".to_string(),
+            OutboxStatus::Dead => "dead".to_string(),
+        }
+    }
+}
+
+impl From<String> for OutboxStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "pending" => OutboxStatus::Pending,
+            "sent" => OutboxStatus::Sent,
+            "dead" => OutboxStatus::Dead,
+            _ => panic!("invalid outbox status"),
+        }
+    }
+}
+
+impl NotificationOutboxDto {
+    /// Due right away, `now` comes from the caller's clock
+    fn new(guild_id: i64, summoner_id: &str, kind: OutboxKind, game_id: &str, now: i64) -> Self {
+        Self {
+            id: None,
+            guild_id,
+            summoner_id: summoner_id.to_string(),
+            kind,
+            game_id: game_id.to_string(),
+            highlights: "[]".to_string(),
+            status: OutboxStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            message_id: None,
+            sent_at: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    pub fn game_finished(
+        guild_id: i64,
+        summoner_id: &str,
+        game_id: &str,
+        highlights: &[String],
+        now: i64,
+    ) -> Result<Self> {
+        Ok(Self {
+            highlights: serde_json::to_string(highlights)?,
+            ..Self::new(
+                guild_id,
+                summoner_id,
+                OutboxKind::GameFinished,
+                game_id,
+                now,
+            )
+        })
+    }
+
+    pub fn game_started(guild_id: i64, summoner_id: &str, active_game_id: &str, now: i64) -> Self {
+        Self::new(
+            guild_id,
+            summoner_id,
+            OutboxKind::GameStarted,
+            active_game_id,
+            now,
+        )
+    }
+
+    pub fn catch_up(guild_id: i64, summoner_id: &str, game_id: &str, now: i64) -> Self {
+        Self::new(guild_id, summoner_id, OutboxKind::CatchUp, game_id, now)
+    }
+
+    pub fn highlights(&self) -> Result<Vec<String>> {
+        Ok(serde_json::from_str(&self.highlights)?)
+    }
+}
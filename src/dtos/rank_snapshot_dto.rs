@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use super::summoner_dto::SummonerDto;
+
+/// A summoner's rank at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RankSnapshotDto {
+    pub id: Option<i64>,
+    pub summoner_id: String,
+    pub created_at: i64,
+    pub queue_type: Option<String>,
+    pub tier: Option<String>,
+    pub division: Option<String>,
+    pub lp: Option<i64>,
+}
+
+impl RankSnapshotDto {
+    pub fn from_summoner(summoner: &SummonerDto, now: i64) -> Self {
+        Self {
+            id: None,
+            summoner_id: summoner.id.clone(),
+            created_at: now,
+            queue_type: summoner.queue_type.clone(),
+            tier: summoner.tier.clone(),
+            division: summoner.division.clone(),
+            lp: summoner.lp,
+        }
+    }
+
+    pub fn is_same_rank(&self, other: &RankSnapshotDto) -> bool {
+        self.queue_type == other.queue_type
+            && self.tier == other.tier
+            && self.division == other.division
+            && self.lp == other.lp
+    }
+}
@@ -0,0 +1,358 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::Database,
+    dtos::{
+        game_dto::GameDto, guild_dto::GuildDto, rank_snapshot_dto::RankSnapshotDto,
+        summoner_dto::SummonerDto,
+    },
+};
+
+/// Bumped when the format changes in a way older versions can't read
+static EXPORT_VERSION: u32 = 1;
+
+static CSV_HEADER: &[&str] = &[
+    "summoner",
+    "game_id",
+    "played_at",
+    "queue",
+    "champion",
+    "result",
+    "kills",
+    "deaths",
+    "assists",
+    "lp_change",
+    "duration_seconds",
+];
+
+/// Everything a guild tracks, enough to move it to another instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuildExport {
+    pub version: u32,
+    pub exported_at: i64,
+    pub guild: GuildDto,
+    pub summoners: Vec<SummonerExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummonerExport {
+    pub summoner: SummonerDto,
+    /// Oldest first
+    pub games: Vec<GameDto>,
+    /// Oldest first
+    #[serde(default)]
+    pub rank_snapshots: Vec<RankSnapshotDto>,
+}
+
+/// What an import actually added, rows that were already there aren't counted
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub summoners: usize,
+    pub games: usize,
+    pub rank_snapshots: usize,
+    /// Tracked by a different guild on this instance, left alone
+    pub skipped_summoners: usize,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} summoners, {} games and {} rank snapshots",
+            self.summoners, self.games, self.rank_snapshots
+        )
+    }
+}
+
+/// Summoners of the guild with all their games and rank history
+pub async fn export_guild(db: &Database, guild_id: i64, now: i64) -> Result<GuildExport> {
+    let guild = db.guilds.get(guild_id).await?;
+
+    let mut summoners = vec![];
+    for summoner in db.summoners.get_all_for_guild(guild_id).await? {
+        let games = db.games.get_for_summoner_since(&summoner.id, 0).await?;
+        let rank_snapshots = db.rank_snapshots.get_all_for_summoner(&summoner.id).await?;
+        summoners.push(SummonerExport {
+            summoner,
+            games,
+            rank_snapshots,
+        });
+    }
+
+    Ok(GuildExport {
+        version: EXPORT_VERSION,
+        exported_at: now,
+        guild,
+        summoners,
+    })
+}
+
+/// - create the guild if needed, into `guild_id` if given, otherwise the exported guild
+/// - add summoners and games that don't exist yet, games count as notified
+/// - add rank history for summoners that don't have any yet
+///
+/// Importing the same export twice doesn't add anything the second time, so
+/// an import that failed halfway can simply be run again. The error says what
+/// was already added.
+pub async fn import_guild(
+    db: &Database,
+    export: GuildExport,
+    guild_id: Option<i64>,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    match import_into(db, export, guild_id, &mut summary).await {
+        Ok(()) => Ok(summary),
+        Err(e) if summary == ImportSummary::default() => Err(e),
+        Err(e) => Err(e.context(format!("import stopped after adding {}", summary))),
+    }
+}
+
+async fn import_into(
+    db: &Database,
+    export: GuildExport,
+    guild_id: Option<i64>,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    if export.version > EXPORT_VERSION {
+        bail!(
+            "export version {} is newer than this instance understands ({})",
+            export.version,
+            EXPORT_VERSION
+        );
+    }
+
+    let guild_id = guild_id.unwrap_or(export.guild.id);
+    // The channel only means something in the same guild
+    let chat_channel_id = export
+        .guild
+        .chat_channel_id
+        .filter(|_| guild_id == export.guild.id);
+    db.guilds
+        .insert_or_ignore(&GuildDto::new(guild_id, chat_channel_id, export.guild.name))
+        .await?;
+
+    for SummonerExport {
+        summoner,
+        games,
+        rank_snapshots,
+    } in export.summoners
+    {
+        match db.summoners.get(&summoner.id).await {
+            Ok(existing) if existing.guild_id != guild_id => {
+                tracing::warn!(
+                    summoner_id = summoner.id,
+                    guild_id = existing.guild_id,
+                    "Summoner is tracked by another guild, skipping"
+                );
+                summary.skipped_summoners += 1;
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) if !matches!(e.downcast_ref(), Some(sqlx::Error::RowNotFound)) => {
+                return Err(e);
+            }
+            Err(_) => {
+                db.summoners
+                    .insert_or_ignore(&SummonerDto {
+                        guild_id,
+                        ..summoner.clone()
+                    })
+                    .await?;
+                summary.summoners += 1;
+            }
+        }
+
+        for game in games {
+            // Old games must never show up as new notifications
+            let game = GameDto {
+                summoner_id: summoner.id.clone(),
+                notified: true,
+                lp_pending: false,
+                ..game
+            };
+            if db.games.insert_or_ignore(&game).await? {
+                summary.games += 1;
+            }
+        }
+
+        let has_history = db
+            .rank_snapshots
+            .get_latest_for_summoner(&summoner.id)
+            .await?
+            .is_some();
+        if !has_history {
+            for snapshot in rank_snapshots {
+                db.rank_snapshots
+                    .insert(&RankSnapshotDto {
+                        id: None,
+                        summoner_id: summoner.id.clone(),
+                        ..snapshot
+                    })
+                    .await?;
+                summary.rank_snapshots += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One row per game for spreadsheets. Only JSON exports can be imported.
+pub fn to_csv(export: &GuildExport) -> String {
+    let mut csv = CSV_HEADER.join(",");
+    csv.push('\n');
+
+    for SummonerExport {
+        summoner, games, ..
+    } in &export.summoners
+    {
+        for game in games {
+            let played_at = chrono::DateTime::from_timestamp(game.game_created_at, 0)
+                .map(|d| d.to_rfc3339())
+                .unwrap_or_default();
+            let result: String = game.result.into();
+            let row = [
+                summoner.name.clone(),
+                game.id.clone(),
+                played_at,
+                game.game_mode.clone(),
+                game.champion_name.clone(),
+                result,
+                game.kills.to_string(),
+                game.deaths.to_string(),
+                game.assists.to_string(),
+                game.lp_change.map(|l| l.to_string()).unwrap_or_default(),
+                game.game_duration
+                    .map(|d| d.to_string())
+                    .unwrap_or_default(),
+            ];
+            csv.push_str(&row.map(|f| csv_field(&f)).join(","));
+            csv.push('\n');
+        }
+    }
+
+    csv
+}
+
+/// Quote fields that contain a separator, quote or newline
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{clock::ManualClock, dtos::game_dto::GameResult};
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "hide-on-bush".to_string(),
+            name: "Hide, on bush".to_string(),
+            guild_id: 1,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Gold".to_string()),
+            lp: Some(45),
+            division: Some("II".to_string()),
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    fn game() -> GameDto {
+        GameDto {
+            id: "/match/na/4812345678".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 7,
+            deaths: 3,
+            kills: 10,
+            result: GameResult::Win,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Ranked Solo/Duo".to_string(),
+            lp_change: Some(18),
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: None,
+        }
+    }
+
+    async fn source() -> Result<Database> {
+        let db = Database::in_memory(Arc::new(ManualClock::at(1700003600)));
+        db.guilds
+            .insert_or_ignore(&GuildDto::new(1, Some(10), "guild".to_string()))
+            .await?;
+        db.summoners.insert_or_ignore(&summoner()).await?;
+        db.games.insert_or_ignore(&game()).await?;
+        db.rank_snapshots
+            .insert(&RankSnapshotDto::from_summoner(&summoner(), 1700000000))
+            .await?;
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn import_round_trips_through_json() -> Result<()> {
+        let export = export_guild(&source().await?, 1, 1700003600).await?;
+        let json = serde_json::to_string(&export)?;
+
+        let target = Database::in_memory(Arc::new(ManualClock::at(1700003600)));
+        let summary = import_guild(&target, serde_json::from_str(&json)?, Some(2)).await?;
+        assert_eq!(
+            summary,
+            ImportSummary {
+                summoners: 1,
+                games: 1,
+                rank_snapshots: 1,
+                skipped_summoners: 0,
+            }
+        );
+
+        let guild = target.guilds.get(2).await?;
+        assert_eq!(guild.chat_channel_id, None);
+        assert_eq!(target.summoners.get("hide-on-bush").await?.guild_id, 2);
+        assert!(target.games.get("/match/na/4812345678").await?.notified);
+
+        // Nothing new the second time
+        let summary = import_guild(&target, serde_json::from_str(&json)?, Some(2)).await?;
+        assert_eq!(summary, ImportSummary::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_skips_summoners_of_other_guilds() -> Result<()> {
+        let export = export_guild(&source().await?, 1, 1700003600).await?;
+
+        let target = source().await?;
+        let summary = import_guild(&target, export, Some(2)).await?;
+
+        assert_eq!(summary.skipped_summoners, 1);
+        assert_eq!(target.summoners.get("hide-on-bush").await?.guild_id, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn csv_has_a_row_per_game() -> Result<()> {
+        let export = export_guild(&source().await?, 1, 1700003600).await?;
+
+        assert_eq!(
+            to_csv(&export),
+            "summoner,game_id,played_at,queue,champion,result,kills,deaths,assists,lp_change,duration_seconds\n\
+             \"Hide, on bush\",/match/na/4812345678,2023-11-14T22:13:20+00:00,Ranked Solo/Duo,Ahri,win,10,3,7,18,1800\n"
+        );
+        Ok(())
+    }
+}
@@ -1,54 +1,148 @@
-use std::sync::Arc;
-
-use anyhow::Result;
-use serenity::{
-    builder::CreateEmbed,
-    http::Http,
-    model::{prelude::ChannelId, Timestamp},
-    utils::Colour,
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
-use sqlx::{Pool, Sqlite};
-use tokio::task::JoinSet;
-use url::Url;
+
+use anyhow::{Context, Result};
+use serenity::http::Http;
+use tokio::{
+    sync::{watch, RwLock},
+    task::JoinSet,
+    time::Duration,
+};
+use tracing::Instrument;
 
 use crate::{
-    api_strategy::ApiStrategy,
-    db,
+    api_strategy::{ApiStrategy, InstrumentedApiStrategy},
+    catch_up::{self, CatchUpPolicy},
+    clock::Clock,
+    data_dragon::{self, ChampionRegistry},
+    db::Database,
     dtos::{
-        active_game_dto::ActiveGameDto, game_dto::GameDto, guild_dto::GuildDto, log_dto::LogDto,
+        active_game_dto::ActiveGameDto,
+        api_token_dto::ApiTokenDto,
+        game_dto::{GameDto, GameResult},
+        guild_dto::GuildDto,
+        guild_template_dto::GuildTemplateDto,
+        notification_outbox_dto::{NotificationOutboxDto, OutboxKind},
+        rank_snapshot_dto::RankSnapshotDto,
         summoner_dto::SummonerDto,
     },
+    export::{self, GuildExport, ImportSummary},
+    leaderboard::{self, LeaderboardEntry},
+    metrics::metrics,
+    notifier::{
+        self, DiscordNotifier, DiscordSink, FanoutNotifier, HttpSink, MissedGame, Notification,
+        Notifier,
+    },
+    rank,
+    render::{self, Template},
+    rules::{self, Rule},
+    scraper_health::{Pause, ScraperHealth},
+    stats::{self, SummonerStats},
+    supervisor::{Shutdown, Supervisor, WorkerStatus},
     util,
 };
 
 static GAME_WATCHER_INTERVAL: u64 = 60;
 static SUMMONER_API_INTERVAL: u64 = 180;
 static ACTIVE_GAME_INTERVAL: u64 = 60;
+static DATA_DRAGON_INTERVAL: u64 = 60 * 60 * 6;
+static OUTBOX_INTERVAL: u64 = 10;
+/// `ApiStrategy` methods each scraping worker calls, a paused method only holds back its own worker
+static SUMMONER_API_METHODS: [&str; 2] = ["get_summoner", "get_games"];
+static ACTIVE_GAME_API_METHODS: [&str; 1] = ["get_active_game"];
+/// Failed notifications are retried this many times before they're dead-lettered
+static OUTBOX_MAX_ATTEMPTS: i64 = 8;
+/// Doubles after every attempt, up to `OUTBOX_MAX_BACKOFF`
+static OUTBOX_INITIAL_BACKOFF: i64 = 30;
+static OUTBOX_MAX_BACKOFF: i64 = 60 * 60;
+static OUTBOX_SENT_RETENTION: i64 = 60 * 60 * 24 * 7;
+/// Claimed notifications are left alone by other instances for this long
+static OUTBOX_CLAIM_LEASE: i64 = 60 * 2;
+/// Games longer than this are assumed to be over
+static MAX_LIVE_GAME_AGE: i64 = 60 * 90;
+/// How long a ranked game waits for its lp change before it's posted without it
+static LP_PENDING_TIMEOUT: i64 = 60 * 30;
+/// Upper bound for `!addUser --backfill`, at roughly 10 games per page
+static BACKFILL_MAX_PAGES: u32 = 100;
+/// Be nice to League of Graphs between pages
+static BACKFILL_PAGE_DELAY: u64 = 2;
+/// Docker sends SIGKILL 10 seconds after SIGTERM by default
+static SHUTDOWN_TIMEOUT: u64 = 8;
+/// Where backups go unless `BACKUP_DIR` or a path is given
+static DEFAULT_BACKUP_DIR: &str = "backups";
+static SCRAPER_ALERT_INTERVAL: u64 = 30;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BackfillProgress {
+    pub pages: u32,
+    /// Games seen so far, including ones we already had
+    pub games: usize,
+    pub inserted: usize,
+}
+
+/// What one poll of a summoner found
+#[derive(Debug)]
+pub struct PollReport {
+    pub summoner: SummonerDto,
+    /// As scraped, most recent first
+    pub games: Vec<GameDto>,
+    /// Games that weren't stored yet
+    pub new_games: usize,
+    pub active_game: Option<ActiveGameDto>,
+}
+
+/// One step of `check_scraper`, `Ok` holds what was found
+pub struct ScraperCheck {
+    pub name: &'static str,
+    pub result: Result<String>,
+}
 
 /// Facade to interact with database and op.gg api
 pub struct Facade {
-    pool: Pool<Sqlite>,
-    join_set: JoinSet<Result<()>>,
+    db: Database,
+    supervisor: Supervisor,
     api_strategy: Arc<dyn ApiStrategy>,
+    champion_registry: Arc<RwLock<ChampionRegistry>>,
+    /// Sent every notification alongside Discord
+    notifiers: Vec<Arc<dyn Notifier>>,
+    clock: Arc<dyn Clock>,
+    scraper_health: Arc<ScraperHealth>,
 }
 
 impl Facade {
     /// Create a new Facade
-    pub async fn new(api_strategy: Arc<dyn ApiStrategy>) -> Result<Self> {
-        let pool = db::create_db().await?;
-        Ok(Self {
-            pool,
-            join_set: JoinSet::new(),
-            api_strategy,
-        })
-    }
+    pub async fn new(
+        api_strategy: Arc<dyn ApiStrategy>,
+        db: Database,
+        notifiers: Vec<Arc<dyn Notifier>>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self> {
+        // Use the cached Data Dragon champions if we have them
+        let champions = db.champions.get_all().await?;
+        let champion_registry = if champions.is_empty() {
+            ChampionRegistry::bundled()?
+        } else {
+            ChampionRegistry::new(champions)?
+        };
 
-    pub async fn log_info(&self, message: &str) {
-        LogDto::info(&self.pool, message).await;
-    }
+        let scraper_health = Arc::new(ScraperHealth::default());
 
-    pub async fn log_error(&self, message: &str) {
-        LogDto::error(&self.pool, message).await;
+        Ok(Self {
+            supervisor: Supervisor::new(clock.clone()),
+            db,
+            api_strategy: Arc::new(InstrumentedApiStrategy::new(
+                api_strategy,
+                scraper_health.clone(),
+                clock.clone(),
+            )),
+            champion_registry: Arc::new(RwLock::new(champion_registry)),
+            notifiers,
+            clock,
+            scraper_health,
+        })
     }
 
     pub async fn init_guild(
@@ -57,8 +151,9 @@ impl Facade {
         chat_channel_id: Option<i64>,
         name: String,
     ) -> Result<()> {
-        GuildDto::new(guild_id, chat_channel_id, name)
-            .insert_or_ignore(&self.pool)
+        self.db
+            .guilds
+            .insert_or_ignore(&GuildDto::new(guild_id, chat_channel_id, name))
             .await?;
         Ok(())
     }
@@ -69,371 +164,1885 @@ impl Facade {
         chat_channel_id: Option<i64>,
         name: String,
     ) -> Result<()> {
-        GuildDto::new(guild_id, chat_channel_id, name)
-            .update(&self.pool)
+        self.db
+            .guilds
+            .update(&GuildDto::new(guild_id, chat_channel_id, name))
             .await?;
         Ok(())
     }
 
     /// - refresh games for all users
-    /// - set all games to notified
+    /// - store games played while offline as notified
+    /// - queue the recent ones for a summary, posted by the outbox worker
     ///
     /// This makes it so that we don't spam notifications from stale games.
     pub async fn startup_tasks(&self) -> Result<()> {
-        let summoners = SummonerDto::get_all(&self.pool).await?;
+        let policy = CatchUpPolicy::from_env()?;
+        let now = self.clock.now();
+        let since = now - catch_up::max_age()?;
+        let summoners = self.db.summoners.get_all().await?;
 
         let mut join_set = JoinSet::new();
 
         // TODO: maybe limit this to a specific amount of threads
         for summoner in summoners {
-            let pool = self.pool.clone();
+            let db = self.db.clone();
             let api_strategy = self.api_strategy.clone();
+            let span = tracing::info_span!(
+                "summoner",
+                summoner_id = %summoner.id,
+                guild_id = summoner.guild_id
+            );
             // spawn a new thread for each user
-            join_set.spawn(async move {
-                match api_strategy.get_games(summoner.id.as_str()).await {
-                    Ok(games) => {
-                        // Fetch and store new games
-                        for mut game in games {
-                            game.notified = true;
-                            let _ = game.upsert(&pool).await;
+            join_set.spawn(
+                async move {
+                    let games = match api_strategy.get_games(summoner.id.as_str()).await {
+                        Ok(games) => games,
+                        Err(e) => {
+                            tracing::warn!(error = %e, "unable to refresh games on startup");
+                            return 0;
+                        }
+                    };
+
+                    // Played while we were offline, only ever posted as a summary.
+                    // Games that were already stored are left to the outbox, another
+                    // instance may be about to post them.
+                    let mut queued = 0;
+                    for game in games {
+                        let game = GameDto {
+                            notified: true,
+                            ..game
+                        };
+                        let result = if policy == CatchUpPolicy::Summary
+                            && game.game_created_at >= since
+                        {
+                            let outbox = NotificationOutboxDto::catch_up(
+                                summoner.guild_id,
+                                &summoner.id,
+                                &game.id,
+                                now,
+                            );
+                            db.outbox.insert_missed_game(&game, &outbox).await
+                        } else {
+                            db.games.insert_or_ignore(&game).await.map(|_| false)
+                        };
+                        match result {
+                            Ok(true) => queued += 1,
+                            Ok(false) => {}
+                            Err(e) => {
+                                tracing::warn!(game_id = %game.id, error = %e, "unable to store game on startup");
+                            }
                         }
                     }
-                    Err(e) => {
-                        println!("startup_tasks: {}", e.to_string().as_str());
-                    }
+                    queued
                 }
-            });
+                .instrument(span),
+            );
         }
 
+        let mut queued = 0;
         while let Some(result) = join_set.join_next().await {
-            result?
+            queued += result?;
+        }
+        if queued > 0 {
+            tracing::info!(games = queued, "Catching up on missed games");
         }
 
-        // Set all games to "notified"
-        GameDto::set_all_notified(&self.pool).await?;
         Ok(())
     }
 
     /// - fetch user from api
     /// - insert user into database
+    /// - record initial rank snapshot
     /// - fetch all games for user
     /// - set all games to notified
     /// - insert games into database
-    pub async fn add_user(&self, summoner_name: &str, guild_id: i64) -> Result<()> {
-        let summoner = self
-            .api_strategy
-            .get_summoner(summoner_name, guild_id)
-            .await?;
+    ///
+    /// `added_by` is the Discord user linking the summoner, they can untrack
+    /// it again without being a manager
+    pub async fn add_user(
+        &self,
+        summoner_name: &str,
+        guild_id: i64,
+        added_by: Option<i64>,
+    ) -> Result<SummonerDto> {
+        let summoner = SummonerDto {
+            added_by,
+            ..self
+                .api_strategy
+                .get_summoner(summoner_name, guild_id)
+                .await?
+        };
 
-        summoner.insert_or_ignore(&self.pool).await?;
+        self.db.summoners.insert_or_ignore(&summoner).await?;
+        self.db
+            .rank_snapshots
+            .insert_if_changed(&RankSnapshotDto::from_summoner(&summoner, self.clock.now()))
+            .await?;
 
         // Fetch all games for the user and set to notified
         let games = self.api_strategy.get_games(summoner.id.as_str()).await?;
         for mut game in games {
             game.notified = true;
-            game.upsert(&self.pool).await?;
+            self.db.games.upsert(&game).await?;
         }
 
-        Ok(())
+        Ok(summoner)
     }
 
-    /// - delete user from database
-    pub async fn delete_user(&self, summoner_name: &str) -> Result<()> {
-        SummonerDto::delete(&self.pool, summoner_name).await?;
-        Ok(())
+    /// - page through older match history until `max_games` games were seen
+    /// - insert them as already notified
+    /// - send progress after every page
+    pub async fn backfill_games(
+        &self,
+        summoner_id: &str,
+        max_games: usize,
+        progress: &watch::Sender<BackfillProgress>,
+    ) -> Result<BackfillProgress> {
+        let shutdown = self.supervisor.shutdown_signal();
+        let mut current = BackfillProgress::default();
+
+        for page in 1..=BACKFILL_MAX_PAGES {
+            if current.games >= max_games || shutdown.is_triggered() {
+                break;
+            }
+            if page > 1 {
+                tokio::time::sleep(Duration::from_secs(BACKFILL_PAGE_DELAY)).await;
+            }
+
+            let games = self.api_strategy.get_games_page(summoner_id, page).await?;
+            if games.is_empty() {
+                break;
+            }
+
+            for mut game in games.into_iter().take(max_games - current.games) {
+                game.notified = true;
+                current.games += 1;
+                if self.db.games.insert_or_ignore(&game).await? {
+                    current.inserted += 1;
+                }
+            }
+
+            current.pages = page;
+            progress.send_replace(current);
+        }
+
+        tracing::info!(
+            summoner_id,
+            pages = current.pages,
+            games = current.games,
+            inserted = current.inserted,
+            "Backfill finished"
+        );
+        Ok(current)
     }
 
-    /// - start all workers
-    pub fn start_workers(&mut self, http: Arc<Http>) {
-        let pool = self.pool.clone();
-        let api_strategy = self.api_strategy.clone();
-        self.join_set
-            .spawn(async move { Self::start_summoner_api_worker(api_strategy, pool).await });
+    /// - scrape a tracked summoner right now, the same way `summoner_api_worker` does
+    /// - also look for a live game
+    /// - new games are notified by the running bot like any other
+    pub async fn poll_once(&self, summoner_name: &str) -> Result<PollReport> {
+        let summoner = self
+            .db
+            .summoners
+            .get_all()
+            .await?
+            .into_iter()
+            .find(|s| s.name.eq_ignore_ascii_case(summoner_name))
+            .with_context(|| format!("{} is not tracked", summoner_name))?;
 
-        let pool = self.pool.clone();
-        let http_clone = http.clone();
-        self.join_set
-            .spawn(async move { Self::start_game_watcher_worker(pool, http_clone).await });
+        let (summoner, games, new_games) =
+            Self::poll_summoner(&*self.api_strategy, &self.db, &*self.clock, &summoner).await?;
 
-        let pool = self.pool.clone();
-        let api_strategy = self.api_strategy.clone();
-        self.join_set
-            .spawn(async move { Self::start_active_game_api_worker(api_strategy, pool).await });
+        let active_game = self
+            .api_strategy
+            .get_active_game(&summoner.id, &summoner.name)
+            .await?;
+        if let Some(active_game) = &active_game {
+            self.db.active_games.insert_or_ignore(active_game).await?;
+        }
 
-        let pool = self.pool.clone();
-        let http_clone = http.clone();
-        self.join_set
-            .spawn(async move { Self::start_active_game_watcher_worker(pool, http_clone).await });
+        Ok(PollReport {
+            summoner,
+            games,
+            new_games,
+            active_game,
+        })
     }
 
-    async fn start_game_watcher_worker(pool: Pool<Sqlite>, http: Arc<Http>) -> Result<()> {
-        loop {
-            match Self::game_watcher_worker(&pool, &http).await {
-                Ok(_) => {}
-                Err(e) => {
-                    LogDto::error(
-                        &pool,
-                        format!("start_game_watcher_worker: {}", e.to_string().as_str()).as_str(),
-                    )
-                    .await;
+    /// - call every `ApiStrategy` method for the summoner, or the first tracked one
+    /// - check the results look like something was actually parsed
+    /// - nothing is stored
+    pub async fn check_scraper(&self, summoner_name: Option<&str>) -> Result<Vec<ScraperCheck>> {
+        let (summoner_name, guild_id) = match summoner_name {
+            Some(name) => (name.to_string(), 0),
+            None => {
+                let summoner = self
+                    .db
+                    .summoners
+                    .get_all()
+                    .await?
+                    .into_iter()
+                    .next()
+                    .context("no summoners are tracked, pass the name of one to check against")?;
+                (summoner.name, summoner.guild_id)
+            }
+        };
+
+        let mut checks = vec![];
+
+        let summoner = self
+            .api_strategy
+            .get_summoner(&summoner_name, guild_id)
+            .await
+            .and_then(|s| {
+                if s.id.is_empty() || s.icon_url.is_empty() {
+                    anyhow::bail!("summoner page parsed without an id or icon");
                 }
+                Ok(s)
+            });
+        let summoner = match summoner {
+            Ok(summoner) => {
+                checks.push(ScraperCheck {
+                    name: "summoner",
+                    result: Ok(format!(
+                        "{} ({})",
+                        summoner.name,
+                        rank::format_rank(
+                            summoner.tier.as_deref(),
+                            summoner.division.as_deref(),
+                            summoner.lp
+                        )
+                    )),
+                });
+                summoner
+            }
+            Err(e) => {
+                // Everything else needs the summoner id
+                checks.push(ScraperCheck {
+                    name: "summoner",
+                    result: Err(e),
+                });
+                return Ok(checks);
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(GAME_WATCHER_INTERVAL)).await;
+        };
+
+        let games = self
+            .api_strategy
+            .get_games(&summoner.id)
+            .await
+            .and_then(|games| {
+                let Some(latest) = games.first() else {
+                    anyhow::bail!("no games found, the match history layout may have changed");
+                };
+                if games.iter().any(|g| {
+                    g.champion_name.is_empty() || g.game_mode.is_empty() || g.game_created_at <= 0
+                }) {
+                    anyhow::bail!("games parsed without a champion, queue or date");
+                }
+                Ok(format!(
+                    "{} games, latest {} {} on {}",
+                    games.len(),
+                    latest.game_mode,
+                    String::from(latest.result),
+                    latest.champion_name
+                ))
+            });
+        checks.push(ScraperCheck {
+            name: "match history",
+            result: games,
+        });
+
+        let active_game = self
+            .api_strategy
+            .get_active_game(&summoner.id, &summoner.name)
+            .await
+            .map(|g| match g {
+                Some(g) => format!("in game as {} ({})", g.champion, g.game_mode),
+                None => "not in game".to_string(),
+            });
+        checks.push(ScraperCheck {
+            name: "live game",
+            result: active_game,
+        });
+
+        Ok(checks)
+    }
+
+    /// - delete user of the guild from database
+    pub async fn delete_user(&self, guild_id: i64, summoner_name: &str) -> Result<()> {
+        let summoner = self
+            .db
+            .summoners
+            .get_by_name(guild_id, summoner_name)
+            .await
+            .with_context(|| format!("{} isn't tracked in this server", summoner_name))?;
+        self.db.summoners.delete(&summoner.id, guild_id).await
+    }
+
+    /// Untrack a summoner of the guild for a Discord user. Users who aren't
+    /// managers can only untrack summoners they added themselves.
+    pub async fn untrack_user(
+        &self,
+        guild_id: i64,
+        summoner_name: &str,
+        user_id: i64,
+        is_manager: bool,
+    ) -> Result<()> {
+        let summoner = self
+            .db
+            .summoners
+            .get_by_name(guild_id, summoner_name)
+            .await
+            .with_context(|| format!("{} isn't tracked in this server", summoner_name))?;
+
+        if !is_manager && summoner.added_by != Some(user_id) {
+            anyhow::bail!("you can only remove summoners you added, ask a manager");
         }
+
+        self.db.summoners.delete(&summoner.id, guild_id).await
+    }
+
+    /// Members with this role can run admin commands, `None` leaves it to Manage Server
+    pub async fn set_manager_role(&self, guild_id: i64, role_id: Option<i64>) -> Result<()> {
+        self.db.guilds.set_manager_role(guild_id, role_id).await
     }
 
-    async fn game_watcher_worker(pool: &Pool<Sqlite>, http: &Http) -> Result<()> {
-        let summoners = SummonerDto::get_all(&pool).await?;
+    pub async fn get_guilds(&self) -> Result<Vec<GuildDto>> {
+        self.db.guilds.get_all().await
+    }
+
+    pub async fn get_guild(&self, guild_id: i64) -> Result<GuildDto> {
+        self.db.guilds.get(guild_id).await
+    }
+
+    pub async fn get_summoners_for_guild(&self, guild_id: i64) -> Result<Vec<SummonerDto>> {
+        self.db.summoners.get_all_for_guild(guild_id).await
+    }
+
+    pub async fn get_summoner(&self, summoner_id: &str) -> Result<SummonerDto> {
+        self.db.summoners.get(summoner_id).await
+    }
+
+    /// Guild leaderboard, optionally only for one queue e.g. `solo` or `flex`.
+    ///
+    /// `SEASON_START` (e.g. `2024-01-10`) adds lp gained since the season started.
+    pub async fn get_leaderboard(
+        &self,
+        guild_id: i64,
+        queue: Option<&str>,
+    ) -> Result<Vec<LeaderboardEntry>> {
+        let summoners = self.db.summoners.get_all_for_guild(guild_id).await?;
+        leaderboard::build(
+            &*self.db.rank_snapshots,
+            summoners,
+            queue,
+            season_start()?,
+            self.clock.now(),
+        )
+        .await
+    }
+
+    /// - find the summoner in the guild by name
+    /// - summarize games from the last `days`, optionally only one queue
+    pub async fn get_stats(
+        &self,
+        guild_id: i64,
+        summoner_name: &str,
+        days: i64,
+        queue: Option<&str>,
+    ) -> Result<(SummonerDto, SummonerStats)> {
+        let summoner = self
+            .db
+            .summoners
+            .get_by_name(guild_id, summoner_name)
+            .await
+            .with_context(|| format!("{} is not tracked in this server", summoner_name))?;
+
+        let since = self.clock.now() - days * 60 * 60 * 24;
+        let games = self
+            .db
+            .games
+            .get_for_summoner_since(&summoner.id, since)
+            .await?;
+        let games = stats::filter_games(games, since, queue);
+
+        Ok((summoner, SummonerStats::from_games(&games)))
+    }
+
+    /// Most recent first
+    pub async fn get_recent_games(&self, summoner_id: &str, limit: i64) -> Result<Vec<GameDto>> {
+        self.db
+            .games
+            .get_recent_for_summoner(summoner_id, limit)
+            .await
+    }
+
+    /// Oldest first
+    pub async fn get_rank_history(&self, summoner_id: &str) -> Result<Vec<RankSnapshotDto>> {
+        self.db
+            .rank_snapshots
+            .get_all_for_summoner(summoner_id)
+            .await
+    }
+
+    /// The summoner's current game, if the latest active game hasn't
+    /// shown up in their match history yet.
+    pub async fn get_live_game(&self, summoner_id: &str) -> Result<Option<ActiveGameDto>> {
+        let active_game = match self
+            .db
+            .active_games
+            .get_latest_for_summoner(summoner_id)
+            .await?
+        {
+            Some(active_game) => active_game,
+            None => return Ok(None),
+        };
+
+        if self.clock.now() - active_game.game_created_at > MAX_LIVE_GAME_AGE {
+            return Ok(None);
+        }
+
+        let latest_game = self
+            .db
+            .games
+            .get_recent_for_summoner(summoner_id, 1)
+            .await?;
+        let finished = latest_game
+            .first()
+            .is_some_and(|g| g.game_created_at >= active_game.game_created_at);
+
+        Ok(if finished { None } else { Some(active_game) })
+    }
+
+    /// - copy the whole database to `path`, or a timestamped file in `BACKUP_DIR`
+    /// - safe while the workers are running
+    /// - return where the backup went
+    pub async fn backup(&self, path: Option<&str>) -> Result<String> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let dir = std::env::var("BACKUP_DIR")
+                    .ok()
+                    .filter(|d| !d.is_empty())
+                    .unwrap_or(DEFAULT_BACKUP_DIR.to_string());
+                let now = chrono::DateTime::from_timestamp(self.clock.now(), 0)
+                    .context("invalid clock time")?;
+                Path::new(&dir).join(format!(
+                    "lol-tracker-{}.sqlite",
+                    now.format("%Y%m%d-%H%M%S")
+                ))
+            }
+        };
+
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("unable to create {}", dir.display()))?;
+        }
+
+        let path = path.to_string_lossy().to_string();
+        self.db
+            .backend
+            .backup(&path)
+            .await
+            .with_context(|| format!("unable to back up the database to {}", path))?;
+        tracing::info!(path, "Backed up database");
+
+        Ok(path)
+    }
+
+    /// The guild's summoners with all their games and rank history
+    pub async fn export_guild(&self, guild_id: i64) -> Result<GuildExport> {
+        export::export_guild(&self.db, guild_id, self.clock.now()).await
+    }
+
+    /// Into `guild_id` if given, otherwise the guild the export came from
+    pub async fn import_guild(
+        &self,
+        export: GuildExport,
+        guild_id: Option<i64>,
+    ) -> Result<ImportSummary> {
+        let summary = export::import_guild(&self.db, export, guild_id).await?;
+        tracing::info!(?summary, "Imported guild");
+        Ok(summary)
+    }
+
+    /// - generate a new random token for the guild
+    /// - store its hash, replacing any previous token
+    /// - return the token, it can't be recovered later
+    pub async fn create_api_token(&self, guild_id: i64) -> Result<String> {
+        let token = util::generate_token();
+        self.db
+            .api_tokens
+            .upsert(&ApiTokenDto::new(guild_id, util::sha256_hex(&token)))
+            .await?;
+        Ok(token)
+    }
+
+    pub async fn revoke_api_token(&self, guild_id: i64) -> Result<()> {
+        self.db.api_tokens.delete(guild_id).await
+    }
+
+    /// Guild the token belongs to, if any
+    pub async fn get_guild_id_for_api_token(&self, token: &str) -> Result<Option<i64>> {
+        let api_token = self
+            .db
+            .api_tokens
+            .get_by_hash(&util::sha256_hex(token))
+            .await?;
+        Ok(api_token.map(|t| t.guild_id))
+    }
+
+    /// The guild's overrides, unset values use the default template
+    pub async fn get_template_overrides(&self, guild_id: i64) -> Result<GuildTemplateDto> {
+        self.db.templates.get_for_guild(guild_id).await
+    }
+
+    /// - validate and store a template override
+    /// - `None` resets the key to its default
+    pub async fn set_template_override(
+        &self,
+        guild_id: i64,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        let mut overrides = self.db.templates.get_for_guild(guild_id).await?;
+        render::set_override(&mut overrides, key, value)?;
+        self.db.templates.upsert(&overrides).await
+    }
+
+    pub async fn reset_template(&self, guild_id: i64) -> Result<()> {
+        self.db.templates.delete(guild_id).await
+    }
+
+    /// Every rule and whether it's on for the guild
+    pub async fn get_rules(&self, guild_id: i64) -> Result<Vec<(Box<dyn Rule>, bool)>> {
+        let enabled: Vec<&'static str> = rules::enabled_rules(&*self.db.rules, guild_id)
+            .await?
+            .iter()
+            .map(|r| r.name())
+            .collect();
+
+        Ok(rules::all_rules()
+            .into_iter()
+            .map(|r| {
+                let is_enabled = enabled.contains(&r.name());
+                (r, is_enabled)
+            })
+            .collect())
+    }
+
+    pub async fn set_rule_enabled(&self, guild_id: i64, rule: &str, enabled: bool) -> Result<()> {
+        rules::set_enabled(&*self.db.rules, guild_id, rule, enabled).await
+    }
+
+    pub async fn get_champion_image_url(&self, champion_name: &str) -> Result<String> {
+        self.champion_registry
+            .read()
+            .await
+            .get_image_url(champion_name)
+    }
+
+    /// - start all workers
+    ///
+    /// Only the first call does anything. Serenity can fire `cache_ready`
+    /// more than once e.g. after a reconnect.
+    pub fn start_workers(&self, http: Arc<Http>) {
+        if !self.supervisor.try_start() {
+            return;
+        }
+
+        let sink: Arc<dyn DiscordSink> = Arc::new(HttpSink::new(http));
+        let notifier = self.notifier(sink.clone());
+
+        let db = self.db.clone();
+        let api_strategy = self.api_strategy.clone();
+        let scraper_health = self.scraper_health.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "summoner_api_worker",
+            Duration::from_secs(SUMMONER_API_INTERVAL),
+            move |shutdown| {
+                let db = db.clone();
+                let api_strategy = api_strategy.clone();
+                let scraper_health = scraper_health.clone();
+                let clock = clock.clone();
+                async move {
+                    if !scraper_health.should_scrape(&SUMMONER_API_METHODS, clock.now()) {
+                        return Ok(());
+                    }
+                    Self::summoner_api_worker(api_strategy, &db, &*clock, &shutdown).await
+                }
+            },
+        );
+
+        let db = self.db.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "game_watcher_worker",
+            Duration::from_secs(GAME_WATCHER_INTERVAL),
+            move |shutdown| {
+                let db = db.clone();
+                let clock = clock.clone();
+                async move { Self::game_watcher_worker(&db, &*clock, &shutdown).await }
+            },
+        );
+
+        let db = self.db.clone();
+        let api_strategy = self.api_strategy.clone();
+        let scraper_health = self.scraper_health.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "active_game_api_worker",
+            Duration::from_secs(ACTIVE_GAME_INTERVAL),
+            move |shutdown| {
+                let db = db.clone();
+                let api_strategy = api_strategy.clone();
+                let scraper_health = scraper_health.clone();
+                let clock = clock.clone();
+                async move {
+                    if !scraper_health.should_scrape(&ACTIVE_GAME_API_METHODS, clock.now()) {
+                        return Ok(());
+                    }
+                    Self::active_game_api_worker(api_strategy, &db, &shutdown).await
+                }
+            },
+        );
+
+        let db = self.db.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "active_game_watcher_worker",
+            Duration::from_secs(ACTIVE_GAME_INTERVAL),
+            move |shutdown| {
+                let db = db.clone();
+                let clock = clock.clone();
+                async move { Self::active_game_watcher_worker(&db, &*clock, &shutdown).await }
+            },
+        );
+
+        let db = self.db.clone();
+        let champion_registry = self.champion_registry.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "outbox_worker",
+            Duration::from_secs(OUTBOX_INTERVAL),
+            move |shutdown| {
+                let db = db.clone();
+                let notifier = notifier.clone();
+                let champion_registry = champion_registry.clone();
+                let clock = clock.clone();
+                async move {
+                    Self::outbox_worker(&db, &*notifier, &champion_registry, &*clock, &shutdown)
+                        .await
+                }
+            },
+        );
+
+        let admin_channel_id = admin_channel_id();
+        let scraper_health = self.scraper_health.clone();
+        let clock = self.clock.clone();
+        self.supervisor.spawn(
+            "scraper_alert_worker",
+            Duration::from_secs(SCRAPER_ALERT_INTERVAL),
+            move |_| {
+                let scraper_health = scraper_health.clone();
+                let sink = sink.clone();
+                let clock = clock.clone();
+                async move {
+                    Self::scraper_alert_worker(&scraper_health, &*sink, admin_channel_id, &*clock)
+                        .await
+                }
+            },
+        );
+
+        let db = self.db.clone();
+        let champion_registry = self.champion_registry.clone();
+        self.supervisor.spawn(
+            "data_dragon_worker",
+            Duration::from_secs(DATA_DRAGON_INTERVAL),
+            move |_| {
+                let db = db.clone();
+                let champion_registry = champion_registry.clone();
+                async move { Self::data_dragon_worker(&db, &champion_registry).await }
+            },
+        );
+    }
+
+    /// Discord through `discord`, plus the extra notifiers
+    fn notifier(&self, discord: Arc<dyn DiscordSink>) -> Arc<dyn Notifier> {
+        Arc::new(FanoutNotifier::new(
+            Arc::new(DiscordNotifier::new(discord)),
+            self.notifiers.clone(),
+        ))
+    }
+
+    /// Methods that keep failing to parse, oldest pause first
+    pub fn scraping_paused(&self) -> Vec<Pause> {
+        self.scraper_health.paused()
+    }
+
+    pub fn worker_statuses(&self) -> Vec<WorkerStatus> {
+        self.supervisor.statuses()
+    }
+
+    /// (pending, dead) notifications in the outbox
+    pub async fn get_undelivered_counts(&self) -> Result<(i64, i64)> {
+        self.db.outbox.count_undelivered().await
+    }
+
+    pub async fn is_database_reachable(&self) -> bool {
+        self.db.backend.ping().await.is_ok()
+    }
+
+    pub async fn tracked_summoner_count(&self) -> Result<i64> {
+        self.db.summoners.count().await
+    }
+
+    /// - stop all workers, letting in-flight passes finish
+    /// - close the database
+    pub async fn shutdown(&self) {
+        self.supervisor
+            .shutdown(Duration::from_secs(SHUTDOWN_TIMEOUT))
+            .await;
+        self.db.backend.close().await;
+    }
+
+    /// - fetch unnotified games for every summoner
+    /// - evaluate the guild's rules against each game
+    /// - queue a notification and mark the game as notified in one transaction
+    async fn game_watcher_worker(
+        db: &Database,
+        clock: &dyn Clock,
+        shutdown: &Shutdown,
+    ) -> Result<()> {
+        let summoners = db.summoners.get_all().await?;
 
         for summoner in summoners {
-            let games = GameDto::get_unnotified_games_for_summoner(&pool, &summoner.id).await?;
-            let guild = summoner.get_guild(&pool).await?;
-
-            for mut game in games {
-                if let Some(chat_channel_id) = guild.chat_channel_id {
-                    let mut embed = CreateEmbed::default();
-                    let color = if game.win {
-                        // Green #15e55a
-                        Colour::new(0x15e55a)
-                    } else {
-                        // Red #e55a5a
-                        Colour::new(0xe55a5a)
-                    };
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
 
-                    let champion_image_url = util::get_champion_image_url(&game.champion_name)?;
+            let span = tracing::info_span!(
+                "summoner",
+                summoner_id = %summoner.id,
+                guild_id = summoner.guild_id
+            );
+            async {
+                let games = db
+                    .games
+                    .get_unnotified_games_for_summoner(&summoner.id)
+                    .await?;
+                let template =
+                    Template::from_overrides(&db.templates.get_for_guild(summoner.guild_id).await?);
+                let rules = rules::enabled_rules(&*db.rules, summoner.guild_id).await?;
+                let now = clock.now();
 
-                    let lp_change = game
-                        .lp_change
-                        .map(|lp| {
-                            if lp > 0 {
-                                format!("+{}", lp)
-                            } else {
-                                lp.to_string()
-                            }
-                        })
-                        .map(|lp| format!("{} lp!", lp));
-
-                    let match_url = format!("https://leagueofgraphs.com{}", game.id);
-                    let match_url = Url::parse(&match_url)?.to_string();
-                    let icon_url = Url::parse(&summoner.icon_url)?.to_string();
-                    let title = if game.win { "Victory" } else { "Defeat" };
-                    let author_url = util::get_author_url(&summoner.name)?;
-
-                    embed
-                        .author(|a| a.name(&summoner.name).icon_url(icon_url).url(author_url))
-                        .title(title.to_string())
-                        .url(match_url)
-                        .description(
-                            lp_change
-                                .unwrap_or(game.promotion_text.clone().unwrap_or("".to_string())),
-                        )
-                        .color(color)
-                        .timestamp(Timestamp::from_unix_timestamp(game.game_created_at)?)
-                        .thumbnail(champion_image_url);
-
-                    if let (Some(tier), Some(division), Some(lp)) = (
-                        summoner.tier.clone(),
-                        summoner.division.clone(),
-                        summoner.lp.clone(),
-                    ) {
-                        embed.field(
-                            format!("{} {}", tier, division),
-                            format!("{} lp", lp),
-                            false,
+                for mut game in games {
+                    if shutdown.is_triggered() {
+                        return Ok(());
+                    }
+
+                    if game.lp_pending {
+                        let waited = now - game.created_at.unwrap_or(now);
+                        if waited < LP_PENDING_TIMEOUT {
+                            continue;
+                        }
+                        tracing::warn!(
+                            game_id = game.id,
+                            waited,
+                            "Lp change never showed up, notifying without it"
                         );
                     }
 
-                    embed
-                        .field("Queue", game.game_mode.clone(), true)
-                        .field(
-                            "Score",
-                            format!("{}/{}/{}", game.kills, game.deaths, game.assists),
-                            true,
-                        )
-                        .field("Champion", game.champion_name.clone(), true);
+                    if game.result == GameResult::Remake && template.suppress_remakes {
+                        tracing::debug!(game_id = game.id, "Skipping remake");
+                        game.notified = true;
+                        db.games.upsert(&game).await?;
+                        continue;
+                    }
 
-                    ChannelId(chat_channel_id as u64)
-                        .send_message(http, |m| m.set_embed(embed))
+                    let highlights = rules::evaluate(db, &rules, &summoner, &game).await?;
+                    db.outbox
+                        .enqueue(&NotificationOutboxDto::game_finished(
+                            summoner.guild_id,
+                            &summoner.id,
+                            &game.id,
+                            &highlights,
+                            now,
+                        )?)
                         .await?;
-                } else {
-                    LogDto::error(
-                        &pool,
-                        &format!("No chat channel set for guild: {}", guild.id),
-                    )
-                    .await;
                 }
 
-                game.notified = true;
-                game.upsert(&pool).await?;
+                Ok::<(), anyhow::Error>(())
             }
+            .instrument(span)
+            .await?;
         }
 
         Ok(())
     }
 
-    /// - start summoner worker to run every minute
-    async fn start_summoner_api_worker(
-        api_strategy: Arc<dyn ApiStrategy>,
-        pool: Pool<Sqlite>,
+    /// - deliver every due notification in the outbox
+    /// - on failure retry later with backoff, give up after `OUTBOX_MAX_ATTEMPTS`
+    /// - drop sent notifications after a week
+    async fn outbox_worker(
+        db: &Database,
+        notifier: &dyn Notifier,
+        champion_registry: &RwLock<ChampionRegistry>,
+        clock: &dyn Clock,
+        shutdown: &Shutdown,
     ) -> Result<()> {
-        loop {
-            match Self::summoner_api_worker(api_strategy.clone(), &pool).await {
-                Ok(_) => {}
-                Err(e) => {
-                    LogDto::error(
-                        &pool,
-                        &format!("start_summoner_api_worker: {}", &e.to_string()),
-                    )
-                    .await;
+        let now = clock.now();
+        let due = db.outbox.claim_due(now, now + OUTBOX_CLAIM_LEASE).await?;
+
+        // Missed games go out as one summary per guild, before anything newer
+        let (catch_ups, due): (Vec<_>, Vec<_>) = due
+            .into_iter()
+            .partition(|outbox| outbox.kind == OutboxKind::CatchUp);
+        let mut guilds: BTreeMap<i64, Vec<NotificationOutboxDto>> = BTreeMap::new();
+        for outbox in catch_ups {
+            guilds.entry(outbox.guild_id).or_default().push(outbox);
+        }
+
+        for (guild_id, outboxes) in guilds {
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
+
+            let span = tracing::info_span!("catch_up", guild_id);
+            async {
+                let result = Self::deliver_catch_up(db, notifier, &outboxes).await;
+                for outbox in &outboxes {
+                    Self::finish_delivery(db, clock, now, outbox, &result).await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .instrument(span)
+            .await?;
+        }
+
+        for outbox in due {
+            // Stop between notifications so one is never sent without being marked
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
+
+            let span = tracing::info_span!(
+                "outbox",
+                outbox_id = outbox.id,
+                summoner_id = %outbox.summoner_id,
+                guild_id = outbox.guild_id
+            );
+            async {
+                let result = Self::deliver(db, notifier, champion_registry, &outbox).await;
+                Self::finish_delivery(db, clock, now, &outbox, &result).await
+            }
+            .instrument(span)
+            .await?;
+        }
+
+        db.outbox
+            .delete_sent_before(now - OUTBOX_SENT_RETENTION)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mark as sent, or retry later with backoff
+    async fn finish_delivery(
+        db: &Database,
+        clock: &dyn Clock,
+        now: i64,
+        outbox: &NotificationOutboxDto,
+        result: &Result<Option<String>>,
+    ) -> Result<()> {
+        match result {
+            Ok(message_id) => {
+                db.outbox
+                    .mark_sent(outbox, message_id.as_deref(), clock.now())
+                    .await
+            }
+            Err(e) => {
+                let attempts = outbox.attempts + 1;
+                let next_attempt_at =
+                    (attempts < OUTBOX_MAX_ATTEMPTS).then(|| now + outbox_backoff(attempts));
+                match next_attempt_at {
+                    Some(_) => {
+                        tracing::warn!(attempts, error = %e, "notification failed, retrying")
+                    }
+                    None => {
+                        tracing::error!(attempts, error = %e, "notification failed, giving up")
+                    }
                 }
+                db.outbox
+                    .mark_failed(outbox, &format!("{:#}", e), next_attempt_at)
+                    .await
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(SUMMONER_API_INTERVAL)).await;
         }
     }
 
+    /// Render and send one queued notification with the guild's current template
+    async fn deliver(
+        db: &Database,
+        notifier: &dyn Notifier,
+        champion_registry: &RwLock<ChampionRegistry>,
+        outbox: &NotificationOutboxDto,
+    ) -> Result<Option<String>> {
+        let summoner = db.summoners.get(&outbox.summoner_id).await?;
+        let guild = db.guilds.get(outbox.guild_id).await?;
+        let template = Template::from_overrides(&db.templates.get_for_guild(guild.id).await?);
+
+        let message_id = match outbox.kind {
+            OutboxKind::GameFinished => {
+                let game = db.games.get(&outbox.game_id).await?;
+                let notification = Notification::GameFinished {
+                    summoner: &summoner,
+                    game: &game,
+                    champion_image_url: champion_registry
+                        .read()
+                        .await
+                        .get_image_url(&game.champion_name)?,
+                    highlights: outbox.highlights()?,
+                };
+                let message = render::render(&notification, &template)?;
+                let message_id = notifier.notify(&guild, &notification, &message).await?;
+                metrics().record_notification(notification.kind());
+                message_id
+            }
+            OutboxKind::GameStarted => {
+                let active_game = db.active_games.get(&outbox.game_id).await?;
+                let notification = Notification::GameStarted {
+                    summoner: &summoner,
+                    active_game: &active_game,
+                    champion_image_url: champion_registry
+                        .read()
+                        .await
+                        .get_image_url(&active_game.champion)?,
+                };
+                let message = render::render(&notification, &template)?;
+                let message_id = notifier.notify(&guild, &notification, &message).await?;
+                metrics().record_notification(notification.kind());
+                message_id
+            }
+            OutboxKind::CatchUp => {
+                Self::deliver_catch_up(db, notifier, std::slice::from_ref(outbox)).await?
+            }
+        };
+
+        Ok(message_id)
+    }
+
+    /// One summary for queued catch ups of the same guild, oldest game first
+    async fn deliver_catch_up(
+        db: &Database,
+        notifier: &dyn Notifier,
+        outboxes: &[NotificationOutboxDto],
+    ) -> Result<Option<String>> {
+        let Some(first) = outboxes.first() else {
+            return Ok(None);
+        };
+        let guild = db.guilds.get(first.guild_id).await?;
+        let template = Template::from_overrides(&db.templates.get_for_guild(guild.id).await?);
+
+        let mut missed = vec![];
+        for outbox in outboxes {
+            let summoner = db.summoners.get(&outbox.summoner_id).await?;
+            let game = db.games.get(&outbox.game_id).await?;
+            if game.result == GameResult::Remake && template.suppress_remakes {
+                continue;
+            }
+            missed.push((summoner, game));
+        }
+        if missed.is_empty() {
+            return Ok(None);
+        }
+        missed.sort_by_key(|(_, game)| game.game_created_at);
+
+        let games = missed
+            .iter()
+            .map(|(summoner, game)| MissedGame { summoner, game })
+            .collect();
+        let notification = Notification::CatchUp { games };
+        let message = render::render(&notification, &template)?;
+        let message_id = notifier.notify(&guild, &notification, &message).await?;
+        metrics().record_notification(notification.kind());
+
+        Ok(message_id)
+    }
+
     /// - fetch all summoners from database
+    /// - update summoner rank and record a snapshot if it changed
     /// - fetch all games for each summoner
     /// - insert or ignore games into database
     async fn summoner_api_worker(
         api_strategy: Arc<dyn ApiStrategy>,
-        pool: &Pool<Sqlite>,
+        db: &Database,
+        clock: &dyn Clock,
+        shutdown: &Shutdown,
     ) -> Result<()> {
-        let summoners = SummonerDto::get_all(&pool).await?;
+        let summoners = db.summoners.get_all().await?;
 
         for s in summoners {
-            // Fetch summoner and update stats
-            api_strategy
-                .get_summoner(s.name.as_str(), s.guild_id)
-                .await?
-                .upsert(&pool)
-                .await?;
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
 
-            let games = api_strategy.get_games(s.id.as_str()).await?;
-            for game in games {
-                game.insert_or_ignore(&pool).await?;
+            let span = tracing::info_span!("summoner", summoner_id = %s.id, guild_id = s.guild_id);
+            // One summoner failing, e.g. after a rename, shouldn't hold back the others
+            if let Err(e) = Self::poll_summoner(&*api_strategy, db, clock, &s)
+                .instrument(span)
+                .await
+            {
+                tracing::warn!(summoner_id = %s.id, error = %e, "unable to poll summoner");
             }
         }
 
         Ok(())
     }
 
-    async fn start_active_game_api_worker(
-        api_strategy: Arc<dyn ApiStrategy>,
-        pool: Pool<Sqlite>,
-    ) -> Result<()> {
-        loop {
-            match Self::active_game_api_worker(api_strategy.clone(), &pool).await {
-                Ok(_) => {}
-                Err(e) => {
-                    LogDto::error(
-                        &pool,
-                        &format!("start_active_game_worker: {}", &e.to_string()),
-                    )
-                    .await;
+    /// - update summoner rank and record a snapshot if it changed
+    /// - insert or ignore the summoner's games
+    /// - return the updated summoner, the scraped games and how many were new
+    async fn poll_summoner(
+        api_strategy: &dyn ApiStrategy,
+        db: &Database,
+        clock: &dyn Clock,
+        s: &SummonerDto,
+    ) -> Result<(SummonerDto, Vec<GameDto>, usize)> {
+        // Fetch summoner and update stats
+        let summoner = api_strategy
+            .get_summoner(s.name.as_str(), s.guild_id)
+            .await?;
+        db.summoners.upsert(&summoner).await?;
+        db.rank_snapshots
+            .insert_if_changed(&RankSnapshotDto::from_summoner(&summoner, clock.now()))
+            .await?;
+
+        let games = api_strategy.get_games(s.id.as_str()).await?;
+        let mut new_games = 0;
+        for game in &games {
+            if game.is_missing_lp() {
+                let game = GameDto {
+                    lp_pending: true,
+                    ..game.clone()
+                };
+                if db.games.insert_or_ignore(&game).await? {
+                    new_games += 1;
                 }
+            } else {
+                if db.games.insert_or_ignore(game).await? {
+                    new_games += 1;
+                }
+                // The game may have been stored earlier without lp
+                db.games.resolve_pending_lp(game).await?;
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(ACTIVE_GAME_INTERVAL)).await;
         }
+
+        Ok((summoner, games, new_games))
     }
 
     async fn active_game_api_worker(
         api_strategy: Arc<dyn ApiStrategy>,
-        pool: &Pool<Sqlite>,
+        db: &Database,
+        shutdown: &Shutdown,
     ) -> Result<()> {
-        let summoners = SummonerDto::get_all(&pool).await?;
+        let summoners = db.summoners.get_all().await?;
 
         for s in summoners {
-            if let Some(active_game) = api_strategy
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
+
+            let span = tracing::info_span!("summoner", summoner_id = %s.id, guild_id = s.guild_id);
+            match api_strategy
                 .get_active_game(s.id.as_str(), s.name.as_str())
-                .await?
+                .instrument(span)
+                .await
             {
-                active_game.insert_or_ignore(&pool).await?;
+                Ok(Some(active_game)) => db.active_games.insert_or_ignore(&active_game).await?,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(summoner_id = %s.id, error = %e, "unable to get active game");
+                }
             }
         }
 
         Ok(())
     }
 
-    async fn start_active_game_watcher_worker(pool: Pool<Sqlite>, http: Arc<Http>) -> Result<()> {
-        loop {
-            match Self::active_game_watcher_worker(&pool, &http).await {
-                Ok(_) => {}
-                Err(e) => {
-                    LogDto::error(
-                        &pool,
-                        &format!("start_active_game_watcher_worker: {}", &e.to_string()),
-                    )
-                    .await;
-                }
+    /// Queue a notification for every active game that wasn't notified yet
+    async fn active_game_watcher_worker(
+        db: &Database,
+        clock: &dyn Clock,
+        shutdown: &Shutdown,
+    ) -> Result<()> {
+        let active_games = db.active_games.get_unnotified_active_games().await?;
+
+        for active_game in active_games {
+            if shutdown.is_triggered() {
+                return Ok(());
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(ACTIVE_GAME_INTERVAL)).await;
+
+            let summoner = db.summoners.get(active_game.summoner_id.as_str()).await?;
+            db.outbox
+                .enqueue(&NotificationOutboxDto::game_started(
+                    summoner.guild_id,
+                    &summoner.id,
+                    &active_game.id,
+                    clock.now(),
+                ))
+                .await?;
         }
+
+        Ok(())
     }
 
-    async fn active_game_watcher_worker(pool: &Pool<Sqlite>, http: &Http) -> Result<()> {
-        let active_games = ActiveGameDto::get_unnotified_active_games(&pool).await?;
+    /// Post scraper pauses and resumes to the admin channel, each once.
+    /// Without an admin channel they're only logged.
+    async fn scraper_alert_worker(
+        scraper_health: &ScraperHealth,
+        sink: &dyn DiscordSink,
+        admin_channel_id: Option<u64>,
+        clock: &dyn Clock,
+    ) -> Result<()> {
+        for event in scraper_health.pending_events() {
+            if let Some(channel_id) = admin_channel_id {
+                let embed = notifier::to_embed(&event.to_message(clock.now()))?;
+                sink.send_embed(channel_id, embed).await?;
+            }
+            scraper_health.ack_event();
+        }
 
-        for mut active_game in active_games {
-            let summoner = SummonerDto::get(&pool, active_game.summoner_id.as_str()).await?;
-            let guild = summoner.get_guild(&pool).await?;
+        Ok(())
+    }
 
-            if let Some(chat_channel_id) = guild.chat_channel_id {
-                let mut embed = CreateEmbed::default();
-                // Yello #e5e55a
-                let color = Colour::new(0xe5e55a);
+    /// - fetch latest patch version from data dragon
+    /// - if it's newer than the registry, fetch champions for that patch
+    /// - cache champions in database
+    /// - swap in the new registry
+    async fn data_dragon_worker(
+        db: &Database,
+        champion_registry: &RwLock<ChampionRegistry>,
+    ) -> Result<()> {
+        let version = data_dragon::get_latest_version().await?;
 
-                let champion_image_url = util::get_champion_image_url(&active_game.champion)?;
+        if champion_registry.read().await.version() == version {
+            return Ok(());
+        }
 
-                let match_url = format!("https://porofessor.gg/live/na/{}", summoner.name);
-                let match_url = Url::parse(&match_url)?.to_string();
-                let icon_url = Url::parse(&summoner.icon_url)?.to_string();
-                let author_url = util::get_author_url(&summoner.name)?;
+        let champions = data_dragon::get_champions(&version).await?;
+        for champion in &champions {
+            db.champions.upsert(champion).await?;
+        }
 
-                embed
-                    .author(|a| a.name(summoner.name).icon_url(icon_url).url(author_url))
-                    .title(format!("In game {}", active_game.game_mode))
-                    .url(match_url)
-                    .color(color)
-                    .timestamp(Timestamp::from_unix_timestamp(active_game.game_created_at)?)
-                    // Show champion name in the first column
-                    .field("Champion", active_game.champion.clone(), true)
-                    .thumbnail(champion_image_url);
+        *champion_registry.write().await = ChampionRegistry::new(champions)?;
 
-                // Show role in another column if available
-                if !active_game.role.to_lowercase().contains("unknown") {
-                    embed.field("Role", active_game.role.clone(), true);
-                }
+        Ok(())
+    }
+}
 
-                // Show rank/division/lp in another column if available
-                if let (Some(tier), Some(division), Some(lp)) =
-                    (summoner.tier, summoner.division.clone(), summoner.lp)
-                {
-                    embed.field(format!("{} {}", tier, division), format!("{} lp", lp), true);
-                }
+/// Seconds until the next attempt after `attempts` failures
+fn outbox_backoff(attempts: i64) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    (OUTBOX_INITIAL_BACKOFF * 2_i64.pow(exponent)).min(OUTBOX_MAX_BACKOFF)
+}
 
-                let demotion_text = "⚠️ Demotion Game ⚠️";
-                if let (Some(lp), Some(division)) = (summoner.lp, summoner.division) {
-                    let is_valid_division = ["I", "II", "III"].iter().any(|&x| division == x);
-                    if lp == 0 && is_valid_division {
-                        embed.description(demotion_text);
-                    }
-                }
+/// `ADMIN_CHANNEL_ID`, where scraper alerts go
+fn admin_channel_id() -> Option<u64> {
+    let id = std::env::var("ADMIN_CHANNEL_ID")
+        .ok()
+        .filter(|id| !id.is_empty())?;
 
-                ChannelId(chat_channel_id as u64)
-                    .send_message(http, |m| m.set_embed(embed))
-                    .await?;
+    match id.parse() {
+        Ok(id) => Some(id),
+        Err(_) => {
+            tracing::warn!(
+                id,
+                "unable to parse ADMIN_CHANNEL_ID, scraper alerts are only logged"
+            );
+            None
+        }
+    }
+}
 
-                active_game.notified = true;
-                active_game.upsert(&pool).await?;
-            } else {
-                LogDto::error(
-                    &pool,
-                    &format!("No chat channel set for guild: {}", guild.id),
-                )
-                .await;
+/// Unix timestamp of `SEASON_START`, if set
+fn season_start() -> Result<Option<i64>> {
+    let Some(season_start) = std::env::var("SEASON_START").ok().filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+
+    let date = chrono::NaiveDate::parse_from_str(&season_start, "%Y-%m-%d")
+        .context("unable to parse SEASON_START from env file, expected YYYY-MM-DD")?;
+    Ok(date.and_hms_opt(0, 0, 0).map(|d| d.and_utc().timestamp()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use serenity::builder::CreateEmbed;
+
+    use super::*;
+    use crate::{
+        api_strategy::SelectorError, clock::ManualClock, dtos::summoner_dto::SummonerDto,
+        render::Message,
+    };
+
+    static GUILD_ID: i64 = 1;
+    static CHANNEL_ID: u64 = 10;
+
+    /// Match history and live game as the test scripted them
+    struct ScriptedApi {
+        games: Mutex<Vec<GameDto>>,
+        active_game: Mutex<Option<ActiveGameDto>>,
+        /// Methods that fail to parse, like after the site changed
+        broken: Mutex<Vec<&'static str>>,
+        /// Summoner ids whose pages fail to parse, whatever the method
+        broken_summoners: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedApi {
+        /// Most recent first, like the real match history
+        fn play(&self, game: GameDto) {
+            self.games.lock().unwrap().insert(0, game);
+        }
+
+        fn start(&self, active_game: ActiveGameDto) {
+            *self.active_game.lock().unwrap() = Some(active_game);
+        }
+
+        fn finish(&self, game: GameDto) {
+            *self.active_game.lock().unwrap() = None;
+            self.play(game);
+        }
+
+        fn parse(&self, method: &'static str, summoner_id: &str) -> Result<()> {
+            if self.broken.lock().unwrap().contains(&method)
+                || self
+                    .broken_summoners
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|id| id == summoner_id)
+            {
+                return None.context(SelectorError(method));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ApiStrategy for ScriptedApi {
+        async fn get_active_game(
+            &self,
+            summoner_id: &str,
+            _: &str,
+        ) -> Result<Option<ActiveGameDto>> {
+            self.parse("get_active_game", summoner_id)?;
+            Ok(self
+                .active_game
+                .lock()
+                .unwrap()
+                .clone()
+                .filter(|a| a.summoner_id == summoner_id))
+        }
+
+        async fn get_summoner(&self, summoner_name: &str, _: i64) -> Result<SummonerDto> {
+            let summoner = summoner_named(summoner_name);
+            self.parse("get_summoner", &summoner.id)?;
+            Ok(summoner)
+        }
+
+        async fn get_games(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+            self.parse("get_games", summoner_id)?;
+            let games = self.games.lock().unwrap();
+            Ok(games
+                .iter()
+                .filter(|g| g.summoner_id == summoner_id)
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Remembers (channel id, title) of every embed instead of posting it
+    #[derive(Default)]
+    struct FakeSink {
+        embeds: Mutex<Vec<(u64, String)>>,
+        /// Discord is down while set
+        failing: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl DiscordSink for FakeSink {
+        async fn send_embed(&self, channel_id: u64, embed: CreateEmbed) -> Result<String> {
+            if *self.failing.lock().unwrap() {
+                anyhow::bail!("discord is down");
             }
+            let title = embed.0["title"].as_str().unwrap_or_default().to_string();
+            let mut embeds = self.embeds.lock().unwrap();
+            embeds.push((channel_id, title));
+            Ok(embeds.len().to_string())
+        }
+    }
+
+    /// Stands in for a webhook, remembers the kind of every notification
+    #[derive(Default)]
+    struct RecordingNotifier {
+        kinds: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        fn name(&self) -> &'static str {
+            "recording"
         }
 
+        async fn notify(
+            &self,
+            _: &GuildDto,
+            notification: &Notification<'_>,
+            _: &Message,
+        ) -> Result<Option<String>> {
+            self.kinds.lock().unwrap().push(notification.kind());
+            Ok(None)
+        }
+    }
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "hide-on-bush".to_string(),
+            name: "Hide on bush".to_string(),
+            guild_id: GUILD_ID,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Gold".to_string()),
+            lp: Some(45),
+            division: Some("II".to_string()),
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    /// Another summoner of the guild, the id is the name in kebab case
+    fn summoner_named(name: &str) -> SummonerDto {
+        SummonerDto {
+            id: name.to_lowercase().replace(' ', "-"),
+            name: name.to_string(),
+            ..summoner()
+        }
+    }
+
+    fn game(id: &str, game_created_at: i64, result: GameResult, lp_change: Option<i64>) -> GameDto {
+        GameDto {
+            id: format!("/match/na/{}", id),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at,
+            assists: 7,
+            deaths: 3,
+            kills: 10,
+            result,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Ranked Solo/Duo".to_string(),
+            lp_change,
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: None,
+        }
+    }
+
+    fn active_game(id: &str, game_created_at: i64) -> ActiveGameDto {
+        ActiveGameDto {
+            id: id.to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            game_created_at,
+            champion: "Ahri".to_string(),
+            role: "Mid".to_string(),
+            spectate_link: "https://example.com/spectate".to_string(),
+            notified: false,
+            game_mode: "Ranked Solo/Duo".to_string(),
+        }
+    }
+
+    /// A facade on an in-memory database, driven one poll at a time
+    struct Scenario {
+        db: Database,
+        api: Arc<ScriptedApi>,
+        sink: Arc<FakeSink>,
+        clock: Arc<ManualClock>,
+        facade: Facade,
+        shutdown: Shutdown,
+    }
+
+    impl Scenario {
+        async fn new(chat_channel_id: Option<i64>) -> Result<Self> {
+            let clock = Arc::new(ManualClock::new());
+            let db = Database::in_memory(clock.clone());
+            db.guilds
+                .insert_or_ignore(&GuildDto::new(
+                    GUILD_ID,
+                    chat_channel_id,
+                    "guild".to_string(),
+                ))
+                .await?;
+            db.summoners.insert_or_ignore(&summoner()).await?;
+
+            let api = Arc::new(ScriptedApi {
+                games: Mutex::new(vec![]),
+                active_game: Mutex::new(None),
+                broken: Mutex::new(vec![]),
+                broken_summoners: Mutex::new(vec![]),
+            });
+            let facade = Facade::new(api.clone(), db.clone(), vec![], clock.clone()).await?;
+
+            Ok(Self {
+                db,
+                api,
+                sink: Arc::new(FakeSink::default()),
+                shutdown: Supervisor::new(clock.clone()).shutdown_signal(),
+                clock,
+                facade,
+            })
+        }
+
+        /// One pass of every worker that ends in a notification
+        async fn poll(&self) -> Result<()> {
+            let notifier = self.facade.notifier(self.sink.clone());
+            let api_strategy = self.facade.api_strategy.clone();
+
+            Facade::summoner_api_worker(
+                api_strategy.clone(),
+                &self.db,
+                &*self.clock,
+                &self.shutdown,
+            )
+            .await?;
+            Facade::active_game_api_worker(api_strategy, &self.db, &self.shutdown).await?;
+            Facade::game_watcher_worker(&self.db, &*self.clock, &self.shutdown).await?;
+            Facade::active_game_watcher_worker(&self.db, &*self.clock, &self.shutdown).await?;
+            Facade::outbox_worker(
+                &self.db,
+                &*notifier,
+                &self.facade.champion_registry,
+                &*self.clock,
+                &self.shutdown,
+            )
+            .await
+        }
+
+        /// One pass of the summoner worker, if the scraper isn't paused
+        async fn scrape(&self) -> Result<()> {
+            if !self
+                .facade
+                .scraper_health
+                .should_scrape(&SUMMONER_API_METHODS, self.clock.now())
+            {
+                return Ok(());
+            }
+            Facade::summoner_api_worker(
+                self.facade.api_strategy.clone(),
+                &self.db,
+                &*self.clock,
+                &self.shutdown,
+            )
+            .await
+        }
+
+        /// A fresh facade on the same database, up to where Discord connects
+        async fn restart(&mut self) -> Result<()> {
+            self.facade = Facade::new(
+                self.api.clone(),
+                self.db.clone(),
+                vec![],
+                self.clock.clone(),
+            )
+            .await?;
+            self.facade.startup_tasks().await
+        }
+
+        async fn add_summoner(&self, name: &str) -> Result<()> {
+            self.db
+                .summoners
+                .insert_or_ignore(&summoner_named(name))
+                .await
+        }
+
+        fn embeds(&self) -> Vec<(u64, String)> {
+            self.sink.embeds.lock().unwrap().clone()
+        }
+    }
+
+    fn embed(title: &str) -> (u64, String) {
+        (CHANNEL_ID, title.to_string())
+    }
+
+    #[tokio::test]
+    async fn new_game_is_posted() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, Some(18)));
+
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (0, 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn duplicate_poll_posts_once() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Loss, Some(-16)));
+
+        scenario.poll().await?;
+        scenario.clock.advance(OUTBOX_INTERVAL as i64);
+        scenario.poll().await?;
+        scenario.clock.advance(OUTBOX_CLAIM_LEASE);
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Defeat")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restart_summarizes_missed_games_without_reposting() -> Result<()> {
+        let mut scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 7200, GameResult::Win, Some(18)));
+        scenario.poll().await?;
+
+        // Played while we were down
+        scenario
+            .api
+            .play(game("2", now - 3600, GameResult::Loss, Some(-16)));
+        scenario.restart().await?;
+        scenario.poll().await?;
+
+        scenario.clock.advance(3600);
+        scenario.api.play(game(
+            "3",
+            scenario.clock.now() - 1800,
+            GameResult::Win,
+            Some(17),
+        ));
+        scenario.poll().await?;
+
+        assert_eq!(
+            scenario.embeds(),
+            vec![
+                embed("Victory"),
+                embed("While I was away"),
+                embed("Victory")
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missed_games_summary_survives_restarts_and_failures() -> Result<()> {
+        let mut scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 7200, GameResult::Win, Some(18)));
+        scenario
+            .api
+            .play(game("2", now - 3600, GameResult::Loss, Some(-16)));
+
+        // Down again before Discord connected, then Discord fails once
+        scenario.restart().await?;
+        scenario.restart().await?;
+        *scenario.sink.failing.lock().unwrap() = true;
+        scenario.poll().await?;
+        assert!(scenario.embeds().is_empty());
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (2, 0));
+
+        *scenario.sink.failing.lock().unwrap() = false;
+        scenario.clock.advance(OUTBOX_MAX_BACKOFF);
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("While I was away")]);
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (0, 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restart_leaves_games_of_other_instances_alone() -> Result<()> {
+        let mut scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        // Stored by another instance sharing the database, not posted yet
+        scenario
+            .db
+            .games
+            .insert_or_ignore(&game("1", now - 1800, GameResult::Win, Some(18)))
+            .await?;
+
+        scenario.restart().await?;
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn leaderboard_compares_against_the_clock() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        scenario.poll().await?;
+
+        let leaderboard = scenario.facade.get_leaderboard(GUILD_ID, None).await?;
+        assert_eq!(leaderboard[0].previous_position, None);
+
+        // The rank recorded by the poll is now more than a day old
+        scenario.clock.advance(60 * 60 * 25);
+        let leaderboard = scenario.facade.get_leaderboard(GUILD_ID, None).await?;
+        assert_eq!(leaderboard[0].previous_position, Some(1));
+        assert_eq!(leaderboard[0].lp_since_yesterday, Some(0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn paused_scraper_probes_once_per_interval() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        scenario.add_summoner("Faker").await?;
+        scenario.add_summoner("Chovy").await?;
+        scenario.api.broken.lock().unwrap().push("get_games");
+        scenario.scrape().await?;
+        assert_eq!(scenario.facade.scraping_paused().len(), 1);
+
+        // get_games is never reached, the probe still ends
+        scenario.api.broken.lock().unwrap().push("get_summoner");
+        scenario.clock.advance(60 * 60);
+        scenario.scrape().await?;
+        let health = &scenario.facade.scraper_health;
+        assert!(!health.should_scrape(&SUMMONER_API_METHODS, scenario.clock.now() + 1));
+        // Live games come from another site
+        assert!(health.should_scrape(&ACTIVE_GAME_API_METHODS, scenario.clock.now() + 1));
+
+        scenario.api.broken.lock().unwrap().clear();
+        scenario.clock.advance(60 * 60);
+        scenario.scrape().await?;
+        assert!(scenario.facade.scraping_paused().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn one_broken_summoner_doesnt_pause_scraping() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        // Polled first, ids are in order
+        scenario.add_summoner("Faker").await?;
+        scenario
+            .api
+            .broken_summoners
+            .lock()
+            .unwrap()
+            .push("faker".to_string());
+
+        for _ in 0..10 {
+            scenario.poll().await?;
+            scenario.clock.advance(SUMMONER_API_INTERVAL as i64);
+        }
+        assert!(scenario.facade.scraping_paused().is_empty());
+        assert!(scenario
+            .facade
+            .scraper_health
+            .should_scrape(&SUMMONER_API_METHODS, scenario.clock.now()));
+
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, Some(18)));
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_channel_is_not_retried() -> Result<()> {
+        let scenario = Scenario::new(None).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, Some(18)));
+
+        scenario.poll().await?;
+        scenario.clock.advance(OUTBOX_MAX_BACKOFF);
+        scenario.poll().await?;
+
+        assert!(scenario.embeds().is_empty());
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (0, 0));
+        assert!(scenario.db.games.get("/match/na/1").await?.notified);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn discord_failure_keeps_the_notification_pending() -> Result<()> {
+        let mut scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let webhook = Arc::new(RecordingNotifier::default());
+        scenario.facade.notifiers = vec![webhook.clone()];
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, Some(18)));
+
+        *scenario.sink.failing.lock().unwrap() = true;
+        scenario.poll().await?;
+
+        assert!(scenario.embeds().is_empty());
+        assert!(webhook.kinds.lock().unwrap().is_empty());
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (1, 0));
+
+        *scenario.sink.failing.lock().unwrap() = false;
+        scenario.clock.advance(OUTBOX_MAX_BACKOFF);
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        assert_eq!(*webhook.kinds.lock().unwrap(), vec!["game"]);
+        assert_eq!(scenario.db.outbox.count_undelivered().await?, (0, 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn active_game_then_result() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario.api.start(active_game("na1-1", now));
+
+        scenario.poll().await?;
+        scenario.clock.advance(1800);
+        scenario.poll().await?;
+        scenario
+            .api
+            .finish(game("1", now, GameResult::Win, Some(18)));
+        scenario.clock.advance(GAME_WATCHER_INTERVAL as i64);
+        scenario.poll().await?;
+
+        assert_eq!(
+            scenario.embeds(),
+            vec![embed("In game Ranked Solo/Duo"), embed("Victory")]
+        );
+        assert!(scenario
+            .facade
+            .get_live_game("hide-on-bush")
+            .await?
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ranked_game_waits_for_its_lp() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, None));
+
+        scenario.poll().await?;
+        assert!(scenario.embeds().is_empty());
+        assert!(scenario.db.games.get("/match/na/1").await?.lp_pending);
+
+        // Lp shows up on the next poll
+        *scenario.api.games.lock().unwrap() =
+            vec![game("1", now - 1800, GameResult::Win, Some(18))];
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        assert_eq!(
+            scenario.db.games.get("/match/na/1").await?.lp_change,
+            Some(18)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ranked_game_without_lp_is_posted_after_timeout() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 1800, GameResult::Win, None));
+
+        scenario.poll().await?;
+        scenario.clock.advance(LP_PENDING_TIMEOUT - 60);
+        scenario.poll().await?;
+        assert!(scenario.embeds().is_empty());
+
+        scenario.clock.advance(120);
+        scenario.poll().await?;
+
+        assert_eq!(scenario.embeds(), vec![embed("Victory")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn suppressed_remake_is_not_posted() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        scenario
+            .db
+            .templates
+            .upsert(&GuildTemplateDto {
+                remake_notifications: Some("suppress".to_string()),
+                ..GuildTemplateDto::new(GUILD_ID)
+            })
+            .await?;
+        let now = scenario.clock.now();
+        scenario
+            .api
+            .play(game("1", now - 300, GameResult::Remake, None));
+
+        scenario.poll().await?;
+
+        assert!(scenario.embeds().is_empty());
+        assert!(scenario.db.games.get("/match/na/1").await?.notified);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_managers_untrack_summoners_added_by_others() -> Result<()> {
+        let scenario = Scenario::new(Some(CHANNEL_ID as i64)).await?;
+        let facade = &scenario.facade;
+        scenario
+            .db
+            .summoners
+            .delete("hide-on-bush", GUILD_ID)
+            .await?;
+        scenario
+            .db
+            .summoners
+            .insert_or_ignore(&SummonerDto {
+                added_by: Some(7),
+                ..summoner()
+            })
+            .await?;
+        // Re-scraping keeps who added it
+        scenario.db.summoners.upsert(&summoner()).await?;
+
+        assert!(facade
+            .untrack_user(GUILD_ID, "hide on bush", 8, false)
+            .await
+            .is_err());
+        assert!(facade
+            .untrack_user(GUILD_ID + 1, "hide on bush", 7, true)
+            .await
+            .is_err());
+
+        facade
+            .untrack_user(GUILD_ID, "hide on bush", 7, false)
+            .await?;
+        assert!(scenario.db.summoners.get("hide-on-bush").await.is_err());
         Ok(())
     }
 }
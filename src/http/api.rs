@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dtos::{
+        active_game_dto::ActiveGameDto, game_dto::GameDto, rank_snapshot_dto::RankSnapshotDto,
+        summoner_dto::SummonerDto,
+    },
+    facade::Facade,
+};
+
+static DEFAULT_GAMES_LIMIT: i64 = 20;
+static MAX_GAMES_LIMIT: i64 = 100;
+
+/// JSON api scoped to the guild that owns the bearer token
+pub fn router() -> Router<Arc<Facade>> {
+    Router::new()
+        .route("/api/v1/summoners", get(list_summoners).post(add_summoner))
+        .route(
+            "/api/v1/summoners/:summoner_id",
+            get(get_summoner).delete(delete_summoner),
+        )
+        .route("/api/v1/summoners/:summoner_id/games", get(get_games))
+        .route(
+            "/api/v1/summoners/:summoner_id/rank-snapshots",
+            get(get_rank_snapshots),
+        )
+        .route(
+            "/api/v1/summoners/:summoner_id/active-game",
+            get(get_active_game),
+        )
+        .route("/api/v1/active-games", get(list_active_games))
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not found")
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(sqlx::Error::RowNotFound) = e.downcast_ref::<sqlx::Error>() {
+            return Self::not_found();
+        }
+
+        tracing::error!(error = ?e, "api request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorBody {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Guild id resolved from `Authorization: Bearer <token>`
+struct ApiGuild(i64);
+
+#[async_trait]
+impl FromRequestParts<Arc<Facade>> for ApiGuild {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, facade: &Arc<Facade>) -> ApiResult<Self> {
+        let unauthorized = || ApiError::new(StatusCode::UNAUTHORIZED, "invalid or missing token");
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or_else(unauthorized)?;
+
+        match facade.get_guild_id_for_api_token(token).await? {
+            Some(guild_id) => Ok(ApiGuild(guild_id)),
+            None => Err(unauthorized()),
+        }
+    }
+}
+
+/// Fetch a summoner, hiding summoners from other guilds behind a 404
+async fn get_guild_summoner(
+    facade: &Facade,
+    guild_id: i64,
+    summoner_id: &str,
+) -> ApiResult<SummonerDto> {
+    let summoner = facade.get_summoner(summoner_id).await?;
+    if summoner.guild_id != guild_id {
+        return Err(ApiError::not_found());
+    }
+    Ok(summoner)
+}
+
+async fn list_summoners(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+) -> ApiResult<Json<Vec<SummonerDto>>> {
+    Ok(Json(facade.get_summoners_for_guild(guild_id).await?))
+}
+
+#[derive(Deserialize)]
+struct AddSummoner {
+    name: String,
+}
+
+/// Same as `!addUser`
+async fn add_summoner(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Json(body): Json<AddSummoner>,
+) -> ApiResult<(StatusCode, Json<SummonerDto>)> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "name is required"));
+    }
+
+    let summoner = facade.add_user(name, guild_id, None).await.map_err(|e| {
+        tracing::warn!(guild_id, error = %e, "unable to add summoner from api");
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("unable to add user: {}", e),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(summoner)))
+}
+
+async fn get_summoner(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Path(summoner_id): Path<String>,
+) -> ApiResult<Json<SummonerDto>> {
+    Ok(Json(
+        get_guild_summoner(&facade, guild_id, &summoner_id).await?,
+    ))
+}
+
+/// Same as `!deleteUser`
+async fn delete_summoner(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Path(summoner_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    let summoner = get_guild_summoner(&facade, guild_id, &summoner_id).await?;
+    facade.delete_user(guild_id, &summoner.name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct GamesQuery {
+    limit: Option<i64>,
+}
+
+/// Most recent first, `?limit=` up to 100
+async fn get_games(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Path(summoner_id): Path<String>,
+    Query(query): Query<GamesQuery>,
+) -> ApiResult<Json<Vec<GameDto>>> {
+    let summoner = get_guild_summoner(&facade, guild_id, &summoner_id).await?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_GAMES_LIMIT)
+        .clamp(1, MAX_GAMES_LIMIT);
+    Ok(Json(facade.get_recent_games(&summoner.id, limit).await?))
+}
+
+/// Oldest first
+async fn get_rank_snapshots(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Path(summoner_id): Path<String>,
+) -> ApiResult<Json<Vec<RankSnapshotDto>>> {
+    let summoner = get_guild_summoner(&facade, guild_id, &summoner_id).await?;
+    Ok(Json(facade.get_rank_history(&summoner.id).await?))
+}
+
+/// `null` when the summoner isn't in game
+async fn get_active_game(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+    Path(summoner_id): Path<String>,
+) -> ApiResult<Json<Option<ActiveGameDto>>> {
+    let summoner = get_guild_summoner(&facade, guild_id, &summoner_id).await?;
+    Ok(Json(facade.get_live_game(&summoner.id).await?))
+}
+
+async fn list_active_games(
+    State(facade): State<Arc<Facade>>,
+    ApiGuild(guild_id): ApiGuild,
+) -> ApiResult<Json<Vec<ActiveGameDto>>> {
+    let mut active_games = vec![];
+    for summoner in facade.get_summoners_for_guild(guild_id).await? {
+        if let Some(active_game) = facade.get_live_game(&summoner.id).await? {
+            active_games.push(active_game);
+        }
+    }
+    Ok(Json(active_games))
+}
@@ -0,0 +1,361 @@
+use std::{fmt::Write, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{TimeZone, Utc};
+
+use crate::{
+    dtos::{
+        active_game_dto::ActiveGameDto,
+        game_dto::{GameDto, GameResult},
+        rank_snapshot_dto::RankSnapshotDto,
+        summoner_dto::SummonerDto,
+    },
+    facade::Facade,
+    rank,
+};
+
+/// Games shown on a summoner page
+static RECENT_GAMES_LIMIT: i64 = 30;
+
+static SPARKLINE_WIDTH: f64 = 600.0;
+static SPARKLINE_HEIGHT: f64 = 80.0;
+
+/// Read only pages served alongside the health endpoints
+pub fn router() -> Router<Arc<Facade>> {
+    Router::new()
+        .route("/", get(guilds))
+        .route("/guilds/:guild_id", get(guild_leaderboard))
+        .route("/summoners/:summoner_id", get(summoner_history))
+}
+
+/// Everything the dashboard can fail with. Missing rows are a 404,
+/// anything else is logged and shown as a 500.
+struct DashboardError(anyhow::Error);
+
+impl From<anyhow::Error> for DashboardError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(e)
+    }
+}
+
+impl IntoResponse for DashboardError {
+    fn into_response(self) -> Response {
+        let not_found = matches!(
+            self.0.downcast_ref::<sqlx::Error>(),
+            Some(sqlx::Error::RowNotFound)
+        );
+
+        if not_found {
+            return (
+                StatusCode::NOT_FOUND,
+                Html(page("Not found", "<p>Nothing here.</p>")),
+            )
+                .into_response();
+        }
+
+        tracing::error!(error = ?self.0, "dashboard request failed");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Html(page("Error", "<p>Something went wrong.</p>")),
+        )
+            .into_response()
+    }
+}
+
+type DashboardResult = Result<Html<String>, DashboardError>;
+
+async fn guilds(State(facade): State<Arc<Facade>>) -> DashboardResult {
+    let mut body = String::from("<h1>Guilds</h1><ul>");
+    for guild in facade.get_guilds().await? {
+        let _ = write!(
+            body,
+            r#"<li><a href="/guilds/{}">{}</a></li>"#,
+            guild.id,
+            escape(&guild.name)
+        );
+    }
+    body.push_str("</ul>");
+
+    Ok(Html(page("Guilds", &body)))
+}
+
+/// - fetch guild and its summoners
+/// - sort by rank, unranked at the bottom
+async fn guild_leaderboard(
+    State(facade): State<Arc<Facade>>,
+    Path(guild_id): Path<i64>,
+) -> DashboardResult {
+    let guild = facade.get_guild(guild_id).await?;
+    let mut summoners = facade.get_summoners_for_guild(guild_id).await?;
+    summoners.sort_by_key(|s| std::cmp::Reverse(summoner_ordinal(s)));
+
+    let mut body = format!(
+        r#"<p><a href="/">Guilds</a></p><h1>{}</h1>
+        <table><tr><th>#</th><th></th><th>Summoner</th><th>Rank</th><th>Live</th></tr>"#,
+        escape(&guild.name)
+    );
+
+    for (i, summoner) in summoners.iter().enumerate() {
+        let live = match facade.get_live_game(&summoner.id).await? {
+            Some(game) => format!("In game as {}", escape(&game.champion)),
+            None => String::new(),
+        };
+
+        let _ = write!(
+            body,
+            r#"<tr><td>{}</td><td><img class="icon" src="{}"></td><td><a href="/summoners/{}">{}</a></td><td>{}</td><td>{}</td></tr>"#,
+            i + 1,
+            escape(&summoner.icon_url),
+            encode_path_segment(&summoner.id),
+            escape(&summoner.name),
+            escape(&summoner_rank(summoner)),
+            live
+        );
+    }
+    body.push_str("</table>");
+
+    Ok(Html(page(&guild.name, &body)))
+}
+
+/// - current rank and live game
+/// - rank history with a sparkline
+/// - recent games
+async fn summoner_history(
+    State(facade): State<Arc<Facade>>,
+    Path(summoner_id): Path<String>,
+) -> DashboardResult {
+    let summoner = facade.get_summoner(&summoner_id).await?;
+    let live_game = facade.get_live_game(&summoner_id).await?;
+    let history = facade.get_rank_history(&summoner_id).await?;
+    let games = facade
+        .get_recent_games(&summoner_id, RECENT_GAMES_LIMIT)
+        .await?;
+
+    let mut body = format!(
+        r#"<p><a href="/guilds/{}">Leaderboard</a></p>
+        <h1><img class="icon" src="{}"> {}</h1><p>{}</p>"#,
+        summoner.guild_id,
+        escape(&summoner.icon_url),
+        escape(&summoner.name),
+        escape(&summoner_rank(&summoner))
+    );
+
+    if let Some(live_game) = live_game {
+        body.push_str(&live_game_html(&facade, &live_game).await);
+    }
+
+    body.push_str("<h2>Rank history</h2>");
+    body.push_str(&rank_history_html(&history));
+
+    body.push_str("<h2>Recent games</h2>");
+    body.push_str(&games_html(&facade, &games).await);
+
+    Ok(Html(page(&summoner.name, &body)))
+}
+
+async fn live_game_html(facade: &Facade, game: &ActiveGameDto) -> String {
+    let image = facade
+        .get_champion_image_url(&game.champion)
+        .await
+        .unwrap_or_default();
+
+    format!(
+        r#"<div class="live"><img class="icon" src="{}"> In game as <b>{}</b> ({}, {}) since {} - <a href="{}">spectate</a></div>"#,
+        escape(&image),
+        escape(&game.champion),
+        escape(&game.role),
+        escape(&game.game_mode),
+        format_timestamp(game.game_created_at),
+        escape(&game.spectate_link)
+    )
+}
+
+fn rank_history_html(history: &[RankSnapshotDto]) -> String {
+    if history.is_empty() {
+        return "<p>No rank history yet.</p>".to_string();
+    }
+
+    let mut out = sparkline(history);
+    out.push_str("<table><tr><th>Date</th><th>Rank</th></tr>");
+    for snapshot in history.iter().rev() {
+        let _ = write!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            format_timestamp(snapshot.created_at),
+            escape(&rank::format_rank(
+                snapshot.tier.as_deref(),
+                snapshot.division.as_deref(),
+                snapshot.lp
+            ))
+        );
+    }
+    out.push_str("</table>");
+    out
+}
+
+/// Inline svg of rank over time, only ranked snapshots are plotted
+fn sparkline(history: &[RankSnapshotDto]) -> String {
+    let points: Vec<(i64, i64)> = history
+        .iter()
+        .filter_map(|s| {
+            let ordinal = rank::ordinal(s.tier.as_deref(), s.division.as_deref(), s.lp)?;
+            Some((s.created_at, ordinal))
+        })
+        .collect();
+
+    if points.len() < 2 {
+        return String::new();
+    }
+
+    let (min_x, max_x) = (points[0].0, points[points.len() - 1].0);
+    let min_y = points.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = points.iter().map(|p| p.1).max().unwrap_or(0);
+    let span_x = (max_x - min_x).max(1) as f64;
+    let span_y = (max_y - min_y).max(1) as f64;
+
+    let coords: Vec<String> = points
+        .iter()
+        .map(|(x, y)| {
+            let x = (x - min_x) as f64 / span_x * SPARKLINE_WIDTH;
+            let y = SPARKLINE_HEIGHT - (y - min_y) as f64 / span_y * SPARKLINE_HEIGHT;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect();
+
+    format!(
+        r##"<svg width="{w}" height="{h}" viewBox="0 0 {w} {h}"><polyline fill="none" stroke="#5865f2" stroke-width="2" points="{}"/></svg>"##,
+        coords.join(" "),
+        w = SPARKLINE_WIDTH,
+        h = SPARKLINE_HEIGHT
+    )
+}
+
+async fn games_html(facade: &Facade, games: &[GameDto]) -> String {
+    if games.is_empty() {
+        return "<p>No games yet.</p>".to_string();
+    }
+
+    let mut out = String::from(
+        "<table><tr><th></th><th>Champion</th><th>Result</th><th>KDA</th><th>Mode</th><th>LP</th><th>Played</th></tr>",
+    );
+    for game in games {
+        let image = facade
+            .get_champion_image_url(&game.champion_name)
+            .await
+            .unwrap_or_default();
+        let result = match game.result {
+            GameResult::Win => "Win",
+            GameResult::Loss => "Loss",
+            GameResult::Remake => "Remake",
+        };
+        let lp = match (game.lp_change, game.promotion_text.as_deref()) {
+            (_, Some(promotion)) => escape(promotion),
+            (Some(lp), None) => format!("{:+}", lp),
+            (None, None) if game.lp_pending => "pending".to_string(),
+            (None, None) => String::new(),
+        };
+
+        let _ = write!(
+            out,
+            r#"<tr class="{}"><td><img class="icon" src="{}"></td><td>{}</td><td>{}</td><td>{}/{}/{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            result.to_lowercase(),
+            escape(&image),
+            escape(&game.champion_name),
+            result,
+            game.kills,
+            game.deaths,
+            game.assists,
+            escape(&game.game_mode),
+            lp,
+            format_timestamp(game.game_created_at)
+        );
+    }
+    out.push_str("</table>");
+    out
+}
+
+fn summoner_ordinal(summoner: &SummonerDto) -> Option<i64> {
+    rank::ordinal(
+        summoner.tier.as_deref(),
+        summoner.division.as_deref(),
+        summoner.lp,
+    )
+}
+
+fn summoner_rank(summoner: &SummonerDto) -> String {
+    rank::format_rank(
+        summoner.tier.as_deref(),
+        summoner.division.as_deref(),
+        summoner.lp,
+    )
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.format("%Y-%m-%d %H:%M UTC").to_string(),
+        None => String::new(),
+    }
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{} - LoL Tracker</title>
+<style>
+body {{ font-family: sans-serif; background: #1e1f22; color: #dbdee1; max-width: 900px; margin: 2em auto; padding: 0 1em; }}
+a {{ color: #00a8fc; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 1em; }}
+th, td {{ text-align: left; padding: 4px 8px; border-bottom: 1px solid #313338; }}
+tr.win td:first-child {{ border-left: 3px solid #23a55a; }}
+tr.loss td:first-child {{ border-left: 3px solid #f23f43; }}
+tr.remake td:first-child {{ border-left: 3px solid #80848e; }}
+.icon {{ width: 32px; height: 32px; vertical-align: middle; }}
+.live {{ background: #313338; padding: 8px; border-left: 3px solid #5865f2; margin-bottom: 1em; }}
+</style>
+</head>
+<body>
+{}
+</body>
+</html>"#,
+        escape(title),
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Summoner ids come from the scraper and may contain anything
+fn encode_path_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{:02X}", b);
+        }
+    }
+    out
+}
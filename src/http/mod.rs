@@ -0,0 +1,111 @@
+use std::{env, net::SocketAddr, sync::Arc};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+
+use crate::{facade::Facade, metrics::metrics, util};
+
+mod api;
+mod dashboard;
+
+#[derive(Serialize)]
+struct WorkerHealth {
+    name: &'static str,
+    running: bool,
+    restarts: u32,
+    last_success_at: Option<i64>,
+    last_error_at: Option<i64>,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Health {
+    healthy: bool,
+    database: bool,
+    discord: bool,
+    workers: Vec<WorkerHealth>,
+}
+
+/// Serve the health and metrics endpoints until a shutdown signal is received.
+///
+/// The dashboard and REST api are only mounted when `DASHBOARD_ENABLED=true`
+/// and `API_ENABLED=true` respectively.
+pub async fn serve(facade: Arc<Facade>, addr: SocketAddr) -> Result<()> {
+    let mut app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(prometheus_metrics));
+
+    if is_enabled("DASHBOARD_ENABLED") {
+        app = app.merge(dashboard::router());
+    }
+
+    if is_enabled("API_ENABLED") {
+        app = app.merge(api::router());
+    }
+
+    let app = app.with_state(facade);
+
+    axum::Server::try_bind(&addr)
+        .with_context(|| format!("unable to bind http server to {}", addr))?
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(util::shutdown_signal())
+        .await?;
+
+    Ok(())
+}
+
+fn is_enabled(var: &str) -> bool {
+    env::var(var).is_ok_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// - database is reachable
+/// - discord gateway is connected
+/// - all workers are started and running
+async fn healthz(State(facade): State<Arc<Facade>>) -> impl IntoResponse {
+    let database = facade.is_database_reachable().await;
+    let discord = metrics().is_discord_connected();
+    let workers: Vec<WorkerHealth> = facade
+        .worker_statuses()
+        .into_iter()
+        .map(|s| WorkerHealth {
+            name: s.name,
+            running: s.running,
+            restarts: s.restarts,
+            last_success_at: s.last_success_at,
+            last_error_at: s.last_error_at,
+            last_error: s.last_error,
+        })
+        .collect();
+
+    let healthy = database && discord && !workers.is_empty() && workers.iter().all(|w| w.running);
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(Health {
+            healthy,
+            database,
+            discord,
+            workers,
+        }),
+    )
+}
+
+async fn prometheus_metrics(State(facade): State<Arc<Facade>>) -> impl IntoResponse {
+    // Report -1 rather than failing the whole scrape if the database is down
+    let tracked_summoners = facade.tracked_summoner_count().await.unwrap_or(-1);
+    let body = metrics().render(tracked_summoners, &facade.worker_statuses());
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+
+use crate::{
+    db::repository::RankSnapshotRepository,
+    dtos::{rank_snapshot_dto::RankSnapshotDto, summoner_dto::SummonerDto},
+    rank,
+};
+
+/// How far back "since yesterday" looks
+static MOVEMENT_WINDOW: i64 = 60 * 60 * 24;
+
+#[derive(Debug)]
+pub struct LeaderboardEntry {
+    pub summoner: SummonerDto,
+    /// 1 based, `None` for unranked summoners
+    pub position: Option<usize>,
+    /// Position 24 hours ago if the summoner was ranked then
+    pub previous_position: Option<usize>,
+    /// Change in rank ordinal, i.e. lp including division/tier changes
+    pub lp_since_yesterday: Option<i64>,
+    pub lp_since_season_start: Option<i64>,
+}
+
+impl LeaderboardEntry {
+    /// Positive means the summoner climbed
+    pub fn movement(&self) -> Option<i64> {
+        Some(self.previous_position? as i64 - self.position? as i64)
+    }
+}
+
+/// - keep summoners whose best queue matches `queue`, if given
+/// - order by rank ordinal, unranked last
+/// - compare against ranks 24 hours before `now` and at `season_start`
+pub async fn build(
+    rank_snapshots: &dyn RankSnapshotRepository,
+    summoners: Vec<SummonerDto>,
+    queue: Option<&str>,
+    season_start: Option<i64>,
+    now: i64,
+) -> Result<Vec<LeaderboardEntry>> {
+    let matches_queue = |queue_type: &Option<String>| match queue {
+        Some(queue) => queue_type
+            .as_deref()
+            .is_some_and(|q| q.to_lowercase().contains(&queue.to_lowercase())),
+        None => true,
+    };
+
+    let mut rows = vec![];
+    for summoner in summoners {
+        if !matches_queue(&summoner.queue_type) {
+            continue;
+        }
+
+        let current = summoner_ordinal(&summoner);
+
+        let yesterday = rank_snapshots
+            .get_latest_before(&summoner.id, now - MOVEMENT_WINDOW)
+            .await?
+            .filter(|s| matches_queue(&s.queue_type))
+            .and_then(|s| snapshot_ordinal(&s));
+
+        let season = match season_start {
+            Some(season_start) => rank_snapshots
+                .get_first_after(&summoner.id, season_start)
+                .await?
+                .filter(|s| matches_queue(&s.queue_type))
+                .and_then(|s| snapshot_ordinal(&s)),
+            None => None,
+        };
+
+        rows.push((summoner, current, yesterday, season));
+    }
+
+    let positions = positions_of(rows.iter().map(|r| r.1));
+    let previous_positions = positions_of(rows.iter().map(|r| r.2));
+
+    let mut entries: Vec<LeaderboardEntry> = rows
+        .into_iter()
+        .enumerate()
+        .map(
+            |(i, (summoner, current, yesterday, season))| LeaderboardEntry {
+                summoner,
+                position: positions[i],
+                previous_position: previous_positions[i],
+                lp_since_yesterday: current.zip(yesterday).map(|(c, y)| c - y),
+                lp_since_season_start: current.zip(season).map(|(c, s)| c - s),
+            },
+        )
+        .collect();
+
+    entries.sort_by_key(|e| e.position.unwrap_or(usize::MAX));
+    Ok(entries)
+}
+
+fn summoner_ordinal(summoner: &SummonerDto) -> Option<i64> {
+    rank::ordinal(
+        summoner.tier.as_deref(),
+        summoner.division.as_deref(),
+        summoner.lp,
+    )
+}
+
+fn snapshot_ordinal(snapshot: &RankSnapshotDto) -> Option<i64> {
+    rank::ordinal(
+        snapshot.tier.as_deref(),
+        snapshot.division.as_deref(),
+        snapshot.lp,
+    )
+}
+
+/// 1 based position of each ordinal when sorted highest first
+fn positions_of(ordinals: impl Iterator<Item = Option<i64>>) -> Vec<Option<usize>> {
+    let ordinals: Vec<Option<i64>> = ordinals.collect();
+
+    let mut order: Vec<usize> = (0..ordinals.len())
+        .filter(|&i| ordinals[i].is_some())
+        .collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(ordinals[i]));
+
+    let mut positions = vec![None; ordinals.len()];
+    for (position, i) in order.into_iter().enumerate() {
+        positions[i] = Some(position + 1);
+    }
+    positions
+}
@@ -1,18 +1,21 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 
 use crate::{
-    api_strategy::ApiStrategy,
-    dtos::{active_game_dto::ActiveGameDto, game_dto::GameDto, summoner_dto::SummonerDto},
+    api_strategy::{ApiStrategy, SelectorError},
+    dtos::{
+        active_game_dto::ActiveGameDto,
+        game_dto::{GameDto, GameResult},
+        summoner_dto::SummonerDto,
+    },
 };
 
 // declare global const string user agent
 static USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36";
 
 /// leagueofgraphs.com api
-
 pub struct LeagueOfGraphsApiStrategy;
 
 impl LeagueOfGraphsApiStrategy {
@@ -27,118 +30,28 @@ impl LeagueOfGraphsApiStrategy {
 
         Ok(selector)
     }
-}
-
-#[async_trait]
-impl ApiStrategy for LeagueOfGraphsApiStrategy {
-    async fn get_summoner(&self, summoner_name: &str, guild_id: i64) -> Result<SummonerDto> {
-        let url = format!(
-            "https://www.leagueofgraphs.com/summoner/na/{}",
-            summoner_name
-        );
-
-        let client = reqwest::Client::new();
-        let body = client
-            .get(url)
-            .header("Cache-Control", "max-age=0")
-            .header("User-Agent", USER_AGENT)
-            .send()
-            .await
-            .context("get_summoner failed")?
-            .text()
-            .await
-            .context("get_summoner failed to get text")?;
 
-        let html = Html::parse_document(&body);
-        let best_league_selector = self.get_selector(".best-league");
-        let container = html
-            .select(&best_league_selector?)
-            .next()
-            .context("unable to select .best-league")?;
-
-        let selector = self.get_selector(".leagueTier")?;
-        let val = container
-            .select(&selector)
-            .next()
-            .context("unable to select .leagueTier")?;
-        let league_tier = val.inner_html();
-        let league_tier: Vec<&str> = league_tier.trim().split(" ").collect();
-        let tier = league_tier.get(0).map(|s| s.to_string());
-        let division = league_tier.get(1).map(|s| s.to_string());
-
-        let selector = self.get_selector(".queueLine .queue")?;
-        let queue_type = container
-            .select(&selector)
-            .next()
-            .map(|val| val.inner_html().trim().to_string());
-
-        let selector = self.get_selector(".league-points .leaguePoints")?;
-        let lp = container
-            .select(&selector)
-            .next()
-            .map(|val| val.inner_html().trim().to_string())
-            .map_or(None, |val| val.parse::<i64>().ok());
-
-        let summoner_img_selector = self.get_selector(".pageBanner .img img")?;
-
-        let summoner_name_formatted = html
-            .select(&summoner_img_selector)
-            .next()
-            .context("unable to select .pageBanner .img img")?
-            .attr("title")
-            .context("unable to get title")?;
-
-        let icon_url = html
-            .select(&summoner_img_selector)
-            .next()
-            .context("unable to select .pageBanner .img img")?
-            .attr("src")
-            .context("unable to get src")?
-            .to_string();
-        let icon_url = format!("https:{}", icon_url);
-
-        Ok(SummonerDto {
-            id: summoner_name_formatted.to_string(),
-            name: summoner_name_formatted.to_string(),
-            guild_id,
-            created_at: None,
-            updated_at: None,
-            queue_type,
-            lp,
-            tier,
-            division,
-            icon_url,
-        })
-    }
-
-    async fn get_games(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
-        let url = format!("https://www.leagueofgraphs.com/summoner/na/{}", summoner_id);
-
-        let client = reqwest::Client::new();
-        let body = client
-            .get(url)
-            .header("Cache-Control", "max-age=0")
-            .header("User-Agent", USER_AGENT)
-            .send()
-            .await
-            .context("get_games failed")?
-            .text()
-            .await
-            .context("get_games failed to get text")?;
-
-        let html = Html::parse_document(&body);
+    /// Rows of a match history table, the profile page and the match history
+    /// pages use the same markup
+    fn parse_games(
+        &self,
+        body: &str,
+        summoner_id: &str,
+        table_selector_text: &'static str,
+    ) -> Result<Vec<GameDto>> {
+        let html = Html::parse_document(body);
 
-        let recent_games_table_selector =
-            self.get_selector(".recentGamesBox .recentGamesTable tbody")?;
+        let recent_games_table_selector = self.get_selector(table_selector_text)?;
         let recent_games_table = html
             .select(&recent_games_table_selector)
             .next()
-            .context("unable to select .recentGamesTable")?;
+            .context(SelectorError(table_selector_text))?;
 
         let tr_selector = self.get_selector("tr")?;
 
         let champion_container_selector = self.get_selector(".championContainer img")?;
         let victory_defeat_text_selector = self.get_selector(".victoryDefeatText")?;
+        let game_duration_selector = self.get_selector(".gameDuration")?;
         let kills_selector = self.get_selector(".kda .kills")?;
         let assists_selector = self.get_selector(".kda .assists")?;
         let deaths_selector = self.get_selector(".kda .deaths")?;
@@ -148,34 +61,43 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
             self.get_selector(".lpChange .lpChangePromoteContainer.requireTooltip")?;
         let script_selector = self.get_selector("script")?;
         let id_selector = self.get_selector("td a")?;
+        let multikill_selector = self.get_selector(".multiKill")?;
+
+        let re = Regex::new(r#"new Date\((\d+)\)"#).context("Unable to create regex")?;
 
         let mut games: Vec<GameDto> = vec![];
 
         for ele in recent_games_table.select(&tr_selector) {
             if let Some(val) = ele.select(&champion_container_selector).next() {
-                let champion = val.attr("title").context("Unable to get champion title")?;
-                let win = ele
+                let champion = val
+                    .attr("title")
+                    .context(SelectorError(".championContainer img[title]"))?;
+                let victory_defeat_text = ele
                     .select(&victory_defeat_text_selector)
                     .next()
-                    .context("Unable to get victoryDefeatText")?
-                    .inner_html()
-                    .contains("Victory");
+                    .context(SelectorError(".victoryDefeatText"))?
+                    .inner_html();
+                let game_duration = ele
+                    .select(&game_duration_selector)
+                    .next()
+                    .and_then(|s| parse_game_duration(&s.inner_html()));
+                let result = parse_game_result(&victory_defeat_text, game_duration);
                 let kills: i64 = ele
                     .select(&kills_selector)
                     .next()
-                    .context("Unable to get kills")?
+                    .context(SelectorError(".kda .kills"))?
                     .inner_html()
                     .parse()?;
                 let assists: i64 = ele
                     .select(&assists_selector)
                     .next()
-                    .context("Unable to get assists")?
+                    .context(SelectorError(".kda .assists"))?
                     .inner_html()
                     .parse()?;
                 let deaths: i64 = ele
                     .select(&deaths_selector)
                     .next()
-                    .context("Unable to get deaths")?
+                    .context(SelectorError(".kda .deaths"))?
                     .inner_html()
                     .parse()?;
 
@@ -183,66 +105,54 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
                 let lp = lp_element
                     .map(|s| s.inner_html())
                     .map(|s| s.trim().to_string())
-                    .map_or(None, |s| {
-                        let s = s.split(" ").collect::<Vec<&str>>();
-                        let s = s.get(0).map(|s| s.to_string()).map_or(None, |s| {
-                            let s = if s.starts_with("+") {
-                                s.strip_prefix("+")
+                    .and_then(|s| {
+                        let s = s.split(' ').collect::<Vec<&str>>();
+                        s.first().map(|s| s.to_string()).and_then(|s| {
+                            let s = if s.starts_with('+') {
+                                s.strip_prefix('+')
                                     .map_or("".to_string(), |f| f.to_string())
                             } else {
                                 s
                             };
-                            return s.parse::<i64>().ok();
-                        });
-
-                        return s;
+                            s.parse::<i64>().ok()
+                        })
                     });
                 let promotion_change_text = ele
                     .select(&promotion_change_text_selector)
                     .next()
-                    .map_or(None, |s| s.attr("tooltip"));
+                    .and_then(|s| s.attr("tooltip"));
                 let game_mode = ele
                     .select(&game_mode_selector)
                     .next()
-                    .context("Unable to get gameMode")?
+                    .context(SelectorError(".gameMode"))?
                     .attr("tooltip")
-                    .context("Unable to get gameMode tooltip")?
+                    .context(SelectorError(".gameMode[tooltip]"))?
                     .to_string();
 
-                // Skip if the game is ranked and we can't get the .lpChange element
-                if game_mode.to_lowercase().contains("ranked") && lp_element.is_none() {
-                    // TODO: temp solution - log this in database
-                    println!(
-                        "Unable to get lp from ranked game: {} - {}",
-                        summoner_id, champion
-                    );
-                    continue;
-                }
-
                 let script = ele
                     .select(&script_selector)
                     .next()
-                    .context("Unable to get script")?
+                    .context(SelectorError("script"))?
                     .inner_html();
 
-                let re = Regex::new(r#"new Date\((\d+)\)"#).context("Unable to create regex")?;
-
                 let capture = re
                     .captures_iter(&script)
                     .next()
-                    .context("Unable to get capture")?;
-                let unix_date: i64 = (&capture[1]).parse()?;
+                    .context(SelectorError("script new Date()"))?;
+                let unix_date: i64 = capture[1].parse()?;
                 // Divide because this is in milliseconds
                 let unix_date = unix_date / 1000;
 
                 let id = ele
                     .select(&id_selector)
                     .next()
-                    .context("Unable to get id")?
+                    .context(SelectorError("td a"))?
                     .attr("href")
-                    .context("Unable to get id href")?
+                    .context(SelectorError("td a[href]"))?
                     .to_string();
 
+                let pentakills = parse_pentakills(ele, &multikill_selector);
+
                 games.push(GameDto {
                     id,
                     summoner_id: summoner_id.to_string(),
@@ -252,19 +162,183 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
                     assists,
                     deaths,
                     kills,
-                    win,
+                    result,
                     notified: false,
                     champion_name: champion.to_string(),
                     game_mode,
                     lp_change: lp,
                     promotion_text: promotion_change_text.map(|s| s.to_string()),
+                    game_duration,
+                    lp_pending: false,
+                    pentakills,
                 })
             }
         }
 
         Ok(games)
     }
+}
 
+#[async_trait]
+impl ApiStrategy for LeagueOfGraphsApiStrategy {
+    #[tracing::instrument(
+        skip(self),
+        err(level = "warn"),
+        fields(strategy = "league_of_graphs", url = tracing::field::Empty)
+    )]
+    async fn get_summoner(&self, summoner_name: &str, guild_id: i64) -> Result<SummonerDto> {
+        let url = format!(
+            "https://www.leagueofgraphs.com/summoner/na/{}",
+            summoner_name
+        );
+        tracing::Span::current().record("url", url.as_str());
+
+        let client = reqwest::Client::new();
+        let body = client
+            .get(url)
+            .header("Cache-Control", "max-age=0")
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .context("get_summoner failed")?
+            .text()
+            .await
+            .context("get_summoner failed to get text")?;
+
+        let html = Html::parse_document(&body);
+        let best_league_selector = self.get_selector(".best-league");
+        let container = html
+            .select(&best_league_selector?)
+            .next()
+            .context(SelectorError(".best-league"))?;
+
+        let selector = self.get_selector(".leagueTier")?;
+        let val = container
+            .select(&selector)
+            .next()
+            .context(SelectorError(".leagueTier"))?;
+        let league_tier = val.inner_html();
+        let league_tier: Vec<&str> = league_tier.trim().split(' ').collect();
+        let tier = league_tier.first().map(|s| s.to_string());
+        let division = league_tier.get(1).map(|s| s.to_string());
+
+        let selector = self.get_selector(".queueLine .queue")?;
+        let queue_type = container
+            .select(&selector)
+            .next()
+            .map(|val| val.inner_html().trim().to_string());
+
+        let selector = self.get_selector(".league-points .leaguePoints")?;
+        let lp = container
+            .select(&selector)
+            .next()
+            .map(|val| val.inner_html().trim().to_string())
+            .and_then(|val| val.parse::<i64>().ok());
+
+        let summoner_img_selector = self.get_selector(".pageBanner .img img")?;
+
+        let summoner_name_formatted = html
+            .select(&summoner_img_selector)
+            .next()
+            .context(SelectorError(".pageBanner .img img"))?
+            .attr("title")
+            .context(SelectorError(".pageBanner .img img[title]"))?;
+
+        let icon_url = html
+            .select(&summoner_img_selector)
+            .next()
+            .context(SelectorError(".pageBanner .img img"))?
+            .attr("src")
+            .context(SelectorError(".pageBanner .img img[src]"))?
+            .to_string();
+        let icon_url = format!("https:{}", icon_url);
+
+        Ok(SummonerDto {
+            id: summoner_name_formatted.to_string(),
+            name: summoner_name_formatted.to_string(),
+            guild_id,
+            created_at: None,
+            updated_at: None,
+            queue_type,
+            lp,
+            tier,
+            division,
+            icon_url,
+            added_by: None,
+        })
+    }
+
+    #[tracing::instrument(
+        skip(self),
+        err(level = "warn"),
+        fields(strategy = "league_of_graphs", url = tracing::field::Empty)
+    )]
+    async fn get_games(&self, summoner_id: &str) -> Result<Vec<GameDto>> {
+        let url = format!("https://www.leagueofgraphs.com/summoner/na/{}", summoner_id);
+        tracing::Span::current().record("url", url.as_str());
+
+        let client = reqwest::Client::new();
+        let body = client
+            .get(url)
+            .header("Cache-Control", "max-age=0")
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .context("get_games failed")?
+            .text()
+            .await
+            .context("get_games failed to get text")?;
+
+        self.parse_games(
+            &body,
+            summoner_id,
+            ".recentGamesBox .recentGamesTable tbody",
+        )
+    }
+
+    #[tracing::instrument(
+        skip(self),
+        err(level = "warn"),
+        fields(strategy = "league_of_graphs", url = tracing::field::Empty)
+    )]
+    async fn get_games_page(&self, summoner_id: &str, page: u32) -> Result<Vec<GameDto>> {
+        if page <= 1 {
+            return self.get_games(summoner_id).await;
+        }
+
+        let url = format!(
+            "https://www.leagueofgraphs.com/summoner/matches/na/{}/page-{}",
+            summoner_id, page
+        );
+        tracing::Span::current().record("url", url.as_str());
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(url)
+            .header("Cache-Control", "max-age=0")
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .context("get_games_page failed")?;
+
+        // Asking for a page past the end of the history
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+
+        let body = response
+            .text()
+            .await
+            .context("get_games_page failed to get text")?;
+
+        self.parse_games(&body, summoner_id, ".recentGamesTable tbody")
+    }
+
+    #[tracing::instrument(
+        skip(self),
+        err(level = "warn"),
+        fields(strategy = "league_of_graphs", url = tracing::field::Empty)
+    )]
     async fn get_active_game(
         &self,
         summoner_id: &str,
@@ -274,6 +348,7 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
             "https://porofessor.gg/partial/live-partial/na/{}",
             summoner_name
         );
+        tracing::Span::current().record("url", url.as_str());
 
         let client = reqwest::Client::new();
         let body = client
@@ -303,41 +378,41 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
         let champion = summoner_card
             .select(&champion_selector)
             .next()
-            .context("unable to select champion")?
+            .context(SelectorError(".imgColumn-champion>div img"))?
             .attr("alt")
-            .context("unable to get champion alt")?
+            .context(SelectorError(".imgColumn-champion>div img[alt]"))?
             .to_string();
 
         let role_selector = self.get_selector("div.currentRole>img")?;
         let role = summoner_card
             .select(&role_selector)
             .next()
-            .context("unable to select current role")?
+            .context(SelectorError("div.currentRole>img"))?
             .attr("alt")
-            .context("unable to get current role alt")?
+            .context(SelectorError("div.currentRole>img[alt]"))?
             .to_string();
 
         let game_id_selector = self.get_selector("#spectate_button")?;
         let game_id_link = html
             .select(&game_id_selector)
             .next()
-            .context("unable to select game id link")?;
+            .context(SelectorError("#spectate_button"))?;
         let game_id = game_id_link
             .attr("data-spectate-gameid")
-            .context("unable to get game id")?;
+            .context(SelectorError("#spectate_button[data-spectate-gameid]"))?;
         let spectate_link = game_id_link
             .attr("data-spectate-link")
-            .context("unable to get game spectate link")?
+            .context(SelectorError("#spectate_button[data-spectate-link]"))?
             .to_string();
 
         let game_mode_selector = self.get_selector(".site-content-header>h2")?;
         let game_mode = html
             .select(&game_mode_selector)
             .next()
-            .context("unable to select game mode")?
+            .context(SelectorError(".site-content-header>h2"))?
             .text()
             .next()
-            .context("unable to get game mode text")?
+            .context(SelectorError(".site-content-header>h2"))?
             .trim()
             .to_string();
 
@@ -346,9 +421,9 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
         let game_created_at = html
             .select(&game_created_at_selector)
             .next()
-            .context("unable to select game duration")?
+            .context(SelectorError("[data-game-creation]"))?
             .attr("data-game-creation")
-            .context("unable to get game creation")?
+            .context(SelectorError("[data-game-creation]"))?
             .parse::<i64>()?
             / 1000;
 
@@ -365,3 +440,97 @@ impl ApiStrategy for LeagueOfGraphsApiStrategy {
         }))
     }
 }
+
+/// Games shorter than this are remakes even if the page doesn't say so
+static REMAKE_MAX_DURATION: i64 = 60 * 5;
+
+/// `Victory`, `Defeat` or `Remake`, falls back to the duration for remakes
+fn parse_game_result(victory_defeat_text: &str, game_duration: Option<i64>) -> GameResult {
+    if victory_defeat_text.contains("Remake")
+        || game_duration.is_some_and(|d| d < REMAKE_MAX_DURATION)
+    {
+        GameResult::Remake
+    } else if victory_defeat_text.contains("Victory") {
+        GameResult::Win
+    } else {
+        GameResult::Loss
+    }
+}
+
+/// `24min 3s` to seconds
+fn parse_game_duration(text: &str) -> Option<i64> {
+    let re = Regex::new(r#"(?:(\d+)\s*min)?\s*(?:(\d+)\s*s)?"#).ok()?;
+    let capture = re.captures(text.trim())?;
+    let minutes = capture.get(1).and_then(|m| m.as_str().parse::<i64>().ok());
+    let seconds = capture.get(2).and_then(|s| s.as_str().parse::<i64>().ok());
+    if minutes.is_none() && seconds.is_none() {
+        return None;
+    }
+    Some(minutes.unwrap_or(0) * 60 + seconds.unwrap_or(0))
+}
+
+/// "Penta Kill" badges of a match history row. Rows only have badges when
+/// there was a multikill, so `None` means we can't tell rather than 0.
+fn parse_pentakills(row: ElementRef, multikill_selector: &Selector) -> Option<i64> {
+    let badges: Vec<String> = row
+        .select(multikill_selector)
+        .map(|b| {
+            b.text()
+                .collect::<String>()
+                .to_lowercase()
+                .replace([' ', '-', '\n'], "")
+        })
+        .collect();
+    if badges.is_empty() {
+        return None;
+    }
+
+    Some(badges.iter().filter(|b| b.contains("pentakill")).count() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_game_duration() {
+        assert_eq!(parse_game_duration("24min 3s"), Some(24 * 60 + 3));
+        assert_eq!(parse_game_duration(" 3min "), Some(180));
+        assert_eq!(parse_game_duration("45s"), Some(45));
+        assert_eq!(parse_game_duration(""), None);
+    }
+
+    #[test]
+    fn detects_remakes() {
+        assert_eq!(parse_game_result("Victory", Some(1500)), GameResult::Win);
+        assert_eq!(parse_game_result("Defeat", None), GameResult::Loss);
+        assert_eq!(parse_game_result("Remake", None), GameResult::Remake);
+        assert_eq!(parse_game_result("Defeat", Some(200)), GameResult::Remake);
+    }
+
+    #[test]
+    fn counts_pentakill_badges() {
+        let pentakills = |row: &str| {
+            let html = Html::parse_fragment(&format!("<table><tr>{}</tr></table>", row));
+            let row = html.select(&Selector::parse("tr").unwrap()).next().unwrap();
+            parse_pentakills(row, &Selector::parse(".multiKill").unwrap())
+        };
+
+        assert_eq!(
+            pentakills(r#"<td><div class="multiKill">Penta Kill</div></td>"#),
+            Some(1)
+        );
+        assert_eq!(
+            pentakills(
+                r#"<td><div class="multiKill">Penta-kill</div><div class="multiKill">PENTA KILL</div></td>"#
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            pentakills(r#"<td><div class="multiKill">Quadra Kill</div></td>"#),
+            Some(0)
+        );
+        // No badge, can't tell
+        assert_eq!(pentakills("<td>Victory 21/3/8 Penta Kill</td>"), None);
+    }
+}
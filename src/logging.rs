@@ -0,0 +1,174 @@
+use std::{env, fmt::Debug, sync::Arc};
+
+use tokio::sync::mpsc;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::{
+    db::repository::LogRepository,
+    dtos::log_dto::{ErrorType, LogDto},
+};
+
+/// Events waiting to be written to the log table. If the database
+/// falls this far behind we drop events rather than block workers.
+static LOG_BUFFER_SIZE: usize = 1024;
+
+/// Install the global tracing subscriber.
+///
+/// - `LOG_FORMAT` - `pretty` (default) or `json` for stdout
+/// - `RUST_LOG` - filter directives, defaults to `info`
+///
+/// Warnings and errors are also persisted to the `log` table once
+/// `spawn_log_writer` has been given the log repository.
+pub fn init() -> mpsc::Receiver<LogDto> {
+    let (tx, rx) = mpsc::channel(LOG_BUFFER_SIZE);
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let json = env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json"));
+
+    let stdout = if json {
+        tracing_subscriber::fmt::layer().json().boxed()
+    } else {
+        tracing_subscriber::fmt::layer().pretty().boxed()
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stdout)
+        .with(LogDtoLayer { tx })
+        .init();
+
+    rx
+}
+
+/// Drain persisted log events into the database
+pub fn spawn_log_writer(mut rx: mpsc::Receiver<LogDto>, logs: Arc<dyn LogRepository>) {
+    tokio::spawn(async move {
+        while let Some(log) = rx.recv().await {
+            if let Err(e) = logs.create(&log).await {
+                // Can't use tracing here or we'd feed the error back into ourselves
+                eprintln!("unable to write log: {} - {}", e, log.message);
+            }
+        }
+    });
+}
+
+/// Structured fields we pull out of events and their parent spans
+#[derive(Default, Clone)]
+struct LogFields {
+    message: Option<String>,
+    extra: Vec<(String, String)>,
+    summoner_id: Option<String>,
+    guild_id: Option<i64>,
+    strategy: Option<String>,
+    url: Option<String>,
+}
+
+impl LogFields {
+    /// Fill in anything we don't have yet from a parent span
+    fn inherit(&mut self, parent: &LogFields) {
+        self.summoner_id = self.summoner_id.take().or(parent.summoner_id.clone());
+        self.guild_id = self.guild_id.or(parent.guild_id);
+        self.strategy = self.strategy.take().or(parent.strategy.clone());
+        self.url = self.url.take().or(parent.url.clone());
+    }
+
+    fn record(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = Some(value),
+            "summoner_id" => self.summoner_id = Some(value),
+            "guild_id" => self.guild_id = value.parse().ok(),
+            "strategy" => self.strategy = Some(value),
+            "url" => self.url = Some(value),
+            name => self.extra.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl Visit for LogFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record(field, format!("{:?}", value));
+    }
+}
+
+/// Persists warnings and errors to the `log` table with the
+/// summoner/guild/strategy/url fields broken out into columns.
+struct LogDtoLayer {
+    tx: mpsc::Sender<LogDto>,
+}
+
+impl<S> Layer<S> for LogDtoLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = LogFields::default();
+            attrs.record(&mut fields);
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<LogFields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let error_type = match *event.metadata().level() {
+            Level::ERROR => ErrorType::Error,
+            Level::WARN => ErrorType::Warn,
+            _ => return,
+        };
+
+        let mut fields = LogFields::default();
+        event.record(&mut fields);
+
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(parent) = span.extensions().get::<LogFields>() {
+                    fields.inherit(parent);
+                }
+            }
+        }
+
+        let mut message = fields.message.unwrap_or_default();
+        for (name, value) in fields.extra {
+            message.push_str(&format!(" {}={}", name, value));
+        }
+
+        let log = LogDto {
+            target: Some(event.metadata().target().to_string()),
+            summoner_id: fields.summoner_id,
+            guild_id: fields.guild_id,
+            strategy: fields.strategy,
+            url: fields.url,
+            ..LogDto::new(message.trim(), error_type)
+        };
+
+        let _ = self.tx.try_send(log);
+    }
+}
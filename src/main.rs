@@ -1,28 +1,92 @@
-use std::sync::Arc;
+use std::{env, net::SocketAddr, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 mod api_strategy;
 mod bot;
+mod catch_up;
+mod cli;
+mod clock;
+mod data_dragon;
 mod db;
 mod dtos;
+mod export;
 mod facade;
+mod http;
+mod leaderboard;
 mod league_of_graphs_api;
+mod logging;
+mod metrics;
+mod notifier;
 mod op_gg_api;
+mod rank;
+mod render;
+mod rules;
+mod scraper_health;
+mod stats;
+mod supervisor;
 mod util;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let _ = dotenvy::dotenv();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = cli::Command::parse(&args)?;
+    if command == cli::Command::Help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    let log_rx = logging::init();
+
+    // Migrations run on connect
+    let db = db::create_db().await?;
+    if command == cli::Command::Migrate {
+        println!("Database is up to date");
+        db.backend.close().await;
+        return Ok(());
+    }
+    logging::spawn_log_writer(log_rx, db.logs.clone());
+
     // Replace with your own strategy if necessary
     let strategy = league_of_graphs_api::LeagueOfGraphsApiStrategy;
 
-    let facade = facade::Facade::new(Arc::new(strategy)).await?;
+    let clock: Arc<dyn clock::Clock> = Arc::new(clock::SystemClock);
+
+    // Discord is always notified, these are extra
+    let mut notifiers: Vec<Arc<dyn notifier::Notifier>> = vec![];
+    if let Some(webhook) = notifier::WebhookNotifier::from_env(clock.clone())? {
+        notifiers.push(Arc::new(webhook));
+    }
+
+    let facade = Arc::new(facade::Facade::new(Arc::new(strategy), db, notifiers, clock).await?);
+
+    if command != cli::Command::Run {
+        let result = cli::run(&facade, command).await;
+        facade.shutdown().await;
+        return result;
+    }
 
     facade.startup_tasks().await?;
 
-    bot::start(facade).await?;
+    // Optional health/metrics server
+    if let Some(http_addr) = env::var("HTTP_ADDR").ok().filter(|a| !a.is_empty()) {
+        let addr: SocketAddr = http_addr
+            .parse()
+            .context("unable to parse HTTP_ADDR from env file")?;
+        let facade = facade.clone();
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(facade, addr).await {
+                tracing::error!(error = %e, "http server stopped");
+            }
+        });
+    }
+
+    let result = bot::start(facade.clone()).await;
+
+    // Let workers finish any in-flight database writes before exiting
+    facade.shutdown().await;
 
-    Ok(())
+    result
 }
@@ -0,0 +1,229 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use crate::supervisor::WorkerStatus;
+
+#[derive(Default)]
+struct PassStats {
+    count: u64,
+    sum: f64,
+    last: f64,
+}
+
+/// Process wide counters exposed on `/metrics` in Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    discord_connected: AtomicBool,
+    scraping_paused: AtomicBool,
+    /// (method, success) -> count
+    scrapes: Mutex<BTreeMap<(&'static str, bool), u64>>,
+    /// Scrapes that failed because a selector found nothing, by selector
+    selector_failures: Mutex<BTreeMap<&'static str, u64>>,
+    notifications_sent: Mutex<BTreeMap<&'static str, u64>>,
+    worker_passes: Mutex<BTreeMap<&'static str, PassStats>>,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    pub fn set_discord_connected(&self, connected: bool) {
+        self.discord_connected.store(connected, Ordering::SeqCst);
+    }
+
+    pub fn is_discord_connected(&self) -> bool {
+        self.discord_connected.load(Ordering::SeqCst)
+    }
+
+    pub fn record_scrape(&self, method: &'static str, success: bool) {
+        *self
+            .scrapes
+            .lock()
+            .unwrap()
+            .entry((method, success))
+            .or_default() += 1;
+    }
+
+    pub fn set_scraping_paused(&self, paused: bool) {
+        self.scraping_paused.store(paused, Ordering::SeqCst);
+    }
+
+    pub fn record_selector_failure(&self, selector: &'static str) {
+        *self
+            .selector_failures
+            .lock()
+            .unwrap()
+            .entry(selector)
+            .or_default() += 1;
+    }
+
+    pub fn record_notification(&self, kind: &'static str) {
+        *self
+            .notifications_sent
+            .lock()
+            .unwrap()
+            .entry(kind)
+            .or_default() += 1;
+    }
+
+    pub fn record_worker_pass(&self, worker: &'static str, duration: Duration) {
+        let mut worker_passes = self.worker_passes.lock().unwrap();
+        let stats = worker_passes.entry(worker).or_default();
+        stats.count += 1;
+        stats.sum += duration.as_secs_f64();
+        stats.last = duration.as_secs_f64();
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self, tracked_summoners: i64, workers: &[WorkerStatus]) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "lol_tracker_discord_connected",
+            "gauge",
+            "Whether the Discord gateway is connected.",
+        );
+        out.push_str(&format!(
+            "lol_tracker_discord_connected {}\n",
+            self.is_discord_connected() as u8
+        ));
+
+        header(
+            &mut out,
+            "lol_tracker_tracked_summoners",
+            "gauge",
+            "Number of tracked summoners.",
+        );
+        out.push_str(&format!(
+            "lol_tracker_tracked_summoners {}\n",
+            tracked_summoners
+        ));
+
+        header(
+            &mut out,
+            "lol_tracker_scrape_total",
+            "counter",
+            "Api strategy calls by method and result.",
+        );
+        for ((method, success), count) in self.scrapes.lock().unwrap().iter() {
+            let result = if *success { "success" } else { "failure" };
+            out.push_str(&format!(
+                "lol_tracker_scrape_total{{method=\"{}\",result=\"{}\"}} {}\n",
+                method, result, count
+            ));
+        }
+
+        header(
+            &mut out,
+            "lol_tracker_scrape_selector_failures_total",
+            "counter",
+            "Scrapes that failed because a selector matched nothing.",
+        );
+        for (selector, count) in self.selector_failures.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "lol_tracker_scrape_selector_failures_total{{selector=\"{}\"}} {}\n",
+                selector.replace('\\', "\\\\").replace('"', "\\\""),
+                count
+            ));
+        }
+
+        header(
+            &mut out,
+            "lol_tracker_scraping_paused",
+            "gauge",
+            "Whether scraping is paused after repeated selector failures.",
+        );
+        out.push_str(&format!(
+            "lol_tracker_scraping_paused {}\n",
+            self.scraping_paused.load(Ordering::SeqCst) as u8
+        ));
+
+        header(
+            &mut out,
+            "lol_tracker_notifications_sent_total",
+            "counter",
+            "Notifications sent by kind.",
+        );
+        for (kind, count) in self.notifications_sent.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "lol_tracker_notifications_sent_total{{kind=\"{}\"}} {}\n",
+                kind, count
+            ));
+        }
+
+        let worker_passes = self.worker_passes.lock().unwrap();
+
+        header(
+            &mut out,
+            "lol_tracker_worker_pass_duration_seconds",
+            "summary",
+            "Time spent in worker passes.",
+        );
+        for (worker, stats) in worker_passes.iter() {
+            out.push_str(&format!(
+                "lol_tracker_worker_pass_duration_seconds_sum{{worker=\"{}\"}} {}\n",
+                worker, stats.sum
+            ));
+            out.push_str(&format!(
+                "lol_tracker_worker_pass_duration_seconds_count{{worker=\"{}\"}} {}\n",
+                worker, stats.count
+            ));
+        }
+
+        header(
+            &mut out,
+            "lol_tracker_worker_last_pass_duration_seconds",
+            "gauge",
+            "Duration of the most recent worker pass.",
+        );
+        for (worker, stats) in worker_passes.iter() {
+            out.push_str(&format!(
+                "lol_tracker_worker_last_pass_duration_seconds{{worker=\"{}\"}} {}\n",
+                worker, stats.last
+            ));
+        }
+
+        header(
+            &mut out,
+            "lol_tracker_worker_up",
+            "gauge",
+            "Whether the worker task is running.",
+        );
+        for worker in workers {
+            out.push_str(&format!(
+                "lol_tracker_worker_up{{worker=\"{}\"}} {}\n",
+                worker.name, worker.running as u8
+            ));
+        }
+
+        header(
+            &mut out,
+            "lol_tracker_worker_restarts_total",
+            "counter",
+            "Times the worker was restarted after a panic.",
+        );
+        for worker in workers {
+            out.push_str(&format!(
+                "lol_tracker_worker_restarts_total{{worker=\"{}\"}} {}\n",
+                worker.name, worker.restarts
+            ));
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    out.push_str(&format!("# HELP {} {}\n", name, help));
+    out.push_str(&format!("# TYPE {} {}\n", name, metric_type));
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serenity::{
+    builder::CreateEmbed,
+    http::Http,
+    model::{prelude::ChannelId, Timestamp},
+    utils::Colour,
+};
+
+use super::{Notification, Notifier};
+use crate::{dtos::guild_dto::GuildDto, render::Message};
+
+/// Where embeds end up, tests swap in one that just records them
+#[async_trait]
+pub trait DiscordSink
+where
+    Self: Send + Sync,
+{
+    /// Returns the id of the sent message
+    async fn send_embed(&self, channel_id: u64, embed: CreateEmbed) -> Result<String>;
+}
+
+/// Sends through serenity's http client
+pub struct HttpSink {
+    http: Arc<Http>,
+}
+
+impl HttpSink {
+    pub fn new(http: Arc<Http>) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl DiscordSink for HttpSink {
+    async fn send_embed(&self, channel_id: u64, embed: CreateEmbed) -> Result<String> {
+        let sent = ChannelId(channel_id)
+            .send_message(&self.http, |m| m.set_embed(embed))
+            .await?;
+        Ok(sent.id.to_string())
+    }
+}
+
+/// Posts embeds to the guild's chat channel set with `!init`
+pub struct DiscordNotifier {
+    sink: Arc<dyn DiscordSink>,
+}
+
+impl DiscordNotifier {
+    pub fn new(sink: Arc<dyn DiscordSink>) -> Self {
+        Self { sink }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn notify(
+        &self,
+        guild: &GuildDto,
+        _notification: &Notification<'_>,
+        message: &Message,
+    ) -> Result<Option<String>> {
+        let Some(chat_channel_id) = guild.chat_channel_id else {
+            tracing::warn!(guild_id = guild.id, "no chat channel set for guild");
+            return Ok(None);
+        };
+
+        let embed = to_embed(message)?;
+        let message_id = self.sink.send_embed(chat_channel_id as u64, embed).await?;
+
+        Ok(Some(message_id))
+    }
+}
+
+pub fn to_embed(message: &Message) -> Result<CreateEmbed> {
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .title(&message.title)
+        .color(Colour::new(message.color))
+        .timestamp(Timestamp::from_unix_timestamp(message.timestamp)?);
+
+    if let Some(author) = &message.author {
+        embed.author(|a| {
+            a.name(&author.name)
+                .icon_url(&author.icon_url)
+                .url(&author.url)
+        });
+    }
+    if let Some(url) = &message.url {
+        embed.url(url);
+    }
+    if let Some(thumbnail) = &message.thumbnail {
+        embed.thumbnail(thumbnail);
+    }
+    if let Some(description) = &message.description {
+        embed.description(description);
+    }
+
+    for field in &message.fields {
+        embed.field(&field.name, &field.value, field.inline);
+    }
+
+    Ok(embed)
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::{
+    dtos::{
+        active_game_dto::ActiveGameDto, game_dto::GameDto, guild_dto::GuildDto,
+        summoner_dto::SummonerDto,
+    },
+    render::Message,
+};
+
+mod discord;
+mod webhook;
+
+pub use discord::{to_embed, DiscordNotifier, DiscordSink, HttpSink};
+pub use webhook::WebhookNotifier;
+
+/// Something worth telling a guild about
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification<'a> {
+    GameFinished {
+        summoner: &'a SummonerDto,
+        game: &'a GameDto,
+        champion_image_url: String,
+        /// Streaks and milestones from the guild's rules
+        highlights: Vec<String>,
+    },
+    GameStarted {
+        summoner: &'a SummonerDto,
+        active_game: &'a ActiveGameDto,
+        champion_image_url: String,
+    },
+    /// Games played while the bot was offline, sent once per guild on startup
+    CatchUp { games: Vec<MissedGame<'a>> },
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissedGame<'a> {
+    pub summoner: &'a SummonerDto,
+    pub game: &'a GameDto,
+}
+
+impl Notification<'_> {
+    /// Used for metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::GameFinished { .. } => "game",
+            Notification::GameStarted { .. } => "active_game",
+            Notification::CatchUp { .. } => "catch_up",
+        }
+    }
+}
+
+#[async_trait]
+pub trait Notifier
+where
+    Self: Send + Sync,
+{
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+    /// `message` is the notification already rendered with the guild's template.
+    /// Returns the id of the sent message if the notifier has one.
+    async fn notify(
+        &self,
+        guild: &GuildDto,
+        notification: &Notification<'_>,
+        message: &Message,
+    ) -> Result<Option<String>>;
+}
+
+/// Sends every notification to Discord, then to the extra notifiers.
+///
+/// - Discord failing fails the delivery, so the outbox retries it
+/// - extra notifiers, e.g. webhooks, are best effort and only run once
+///   Discord got the message. A retry would send them a duplicate.
+pub struct FanoutNotifier {
+    discord: Arc<dyn Notifier>,
+    extra: Vec<Arc<dyn Notifier>>,
+}
+
+impl FanoutNotifier {
+    pub fn new(discord: Arc<dyn Notifier>, extra: Vec<Arc<dyn Notifier>>) -> Self {
+        Self { discord, extra }
+    }
+}
+
+#[async_trait]
+impl Notifier for FanoutNotifier {
+    fn name(&self) -> &'static str {
+        "fanout"
+    }
+
+    async fn notify(
+        &self,
+        guild: &GuildDto,
+        notification: &Notification<'_>,
+        message: &Message,
+    ) -> Result<Option<String>> {
+        let message_id = self
+            .discord
+            .notify(guild, notification, message)
+            .await
+            .with_context(|| format!("{} notifier failed", self.discord.name()))?;
+
+        for notifier in &self.extra {
+            if let Err(e) = notifier.notify(guild, notification, message).await {
+                tracing::error!(
+                    notifier = notifier.name(),
+                    guild_id = guild.id,
+                    error = %e,
+                    "unable to send notification"
+                );
+            }
+        }
+
+        Ok(message_id)
+    }
+}
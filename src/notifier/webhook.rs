@@ -0,0 +1,375 @@
+use std::{env, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::time::Duration;
+use url::Url;
+
+use super::{Notification, Notifier};
+use crate::{clock::Clock, dtos::guild_dto::GuildDto, render::Message};
+
+static MAX_ATTEMPTS: u32 = 4;
+/// Doubled after every failed attempt
+static INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+static REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub static SIGNATURE_HEADER: &str = "X-Lol-Tracker-Signature";
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    guild_id: i64,
+    guild_name: &'a str,
+    sent_at: i64,
+    #[serde(flatten)]
+    notification: &'a Notification<'a>,
+    /// Rendered with the guild's template, handy for other chat tools
+    message: &'a Message,
+}
+
+/// POSTs every notification as JSON to a url.
+///
+/// When a secret is set the body is signed with HMAC-SHA256 and sent as
+/// `X-Lol-Tracker-Signature: sha256=<hex>`. Network errors, 429 and 5xx
+/// responses are retried with exponential backoff.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: Url,
+    secret: Option<String>,
+    /// For `sent_at`
+    clock: Arc<dyn Clock>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: Url, secret: Option<String>, clock: Arc<dyn Clock>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            url,
+            secret,
+            clock,
+            max_attempts: MAX_ATTEMPTS,
+            initial_backoff: INITIAL_BACKOFF,
+        })
+    }
+
+    /// - `WEBHOOK_URL` - enables the webhook
+    /// - `WEBHOOK_SECRET` - optional signing secret
+    pub fn from_env(clock: Arc<dyn Clock>) -> Result<Option<Self>> {
+        let Some(url) = env::var("WEBHOOK_URL").ok().filter(|u| !u.is_empty()) else {
+            return Ok(None);
+        };
+        let url = Url::parse(&url).context("unable to parse WEBHOOK_URL from env file")?;
+        let secret = env::var("WEBHOOK_SECRET").ok().filter(|s| !s.is_empty());
+
+        Ok(Some(Self::new(url, secret, clock)?))
+    }
+
+    fn sign(&self, body: &[u8]) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+        mac.update(body);
+        let signature: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some(format!("sha256={}", signature))
+    }
+
+    async fn send(&self, body: &[u8]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+
+        if let Some(signature) = self.sign(body) {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let error = anyhow!("webhook responded with {}", status);
+        if is_retryable(status) {
+            Err(error)
+        } else {
+            Err(error.context(Permanent))
+        }
+    }
+}
+
+/// Marks errors that retrying won't fix
+#[derive(Debug)]
+struct Permanent;
+
+impl std::fmt::Display for Permanent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not retrying")
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn notify(
+        &self,
+        guild: &GuildDto,
+        notification: &Notification<'_>,
+        message: &Message,
+    ) -> Result<Option<String>> {
+        let payload = WebhookPayload {
+            guild_id: guild.id,
+            guild_name: &guild.name,
+            sent_at: self.clock.now(),
+            notification,
+            message,
+        };
+        let body = serde_json::to_vec(&payload)?;
+
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match self.send(&body).await {
+                Ok(_) => return Ok(None),
+                Err(e) if e.downcast_ref::<Permanent>().is_some() => return Err(e),
+                Err(e) if attempt >= self.max_attempts => {
+                    return Err(e.context(format!("gave up after {} attempts", attempt)));
+                }
+                Err(e) => {
+                    tracing::warn!(attempt, error = %e, "webhook failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, sync::Mutex};
+
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        dtos::{
+            game_dto::{GameDto, GameResult},
+            summoner_dto::SummonerDto,
+        },
+        render::{self, Template},
+    };
+
+    /// Requests received by the stand-in server and the statuses it replies with
+    #[derive(Default)]
+    struct StandIn {
+        requests: Mutex<Vec<(HeaderMap, Bytes)>>,
+        statuses: Mutex<Vec<StatusCode>>,
+    }
+
+    async fn receive(
+        State(stand_in): State<Arc<StandIn>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        stand_in.requests.lock().unwrap().push((headers, body));
+        let mut statuses = stand_in.statuses.lock().unwrap();
+        if statuses.is_empty() {
+            StatusCode::OK
+        } else {
+            statuses.remove(0)
+        }
+    }
+
+    /// Local http server that replies with `statuses` in order, then 200
+    fn serve(statuses: Vec<StatusCode>) -> (Url, Arc<StandIn>) {
+        let stand_in = Arc::new(StandIn {
+            statuses: Mutex::new(statuses),
+            ..Default::default()
+        });
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(stand_in.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        (url, stand_in)
+    }
+
+    fn notifier(url: Url, secret: Option<&str>) -> WebhookNotifier {
+        WebhookNotifier {
+            initial_backoff: Duration::from_millis(1),
+            ..WebhookNotifier::new(
+                url,
+                secret.map(str::to_string),
+                Arc::new(ManualClock::at(1700003600)),
+            )
+            .unwrap()
+        }
+    }
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "faker".to_string(),
+            name: "Faker".to_string(),
+            guild_id: 1,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Challenger".to_string()),
+            lp: Some(1200),
+            division: None,
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    fn game() -> GameDto {
+        GameDto {
+            id: "/match/kr/1".to_string(),
+            summoner_id: "faker".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 7,
+            deaths: 1,
+            kills: 10,
+            result: GameResult::Win,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Ranked Solo/Duo".to_string(),
+            lp_change: Some(18),
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: None,
+        }
+    }
+
+    async fn notify(notifier: &WebhookNotifier) -> Result<()> {
+        let (summoner, game) = (summoner(), game());
+        let notification = Notification::GameFinished {
+            summoner: &summoner,
+            game: &game,
+            champion_image_url: "https://example.com/Ahri.png".to_string(),
+            highlights: vec![],
+        };
+        let message = render::render(&notification, &Template::default())?;
+        notifier
+            .notify(
+                &GuildDto::new(1, None, "Guild".to_string()),
+                &notification,
+                &message,
+            )
+            .await
+            .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn sends_signed_json_payload() {
+        let (url, stand_in) = serve(vec![]);
+
+        notify(&notifier(url, Some("secret"))).await.unwrap();
+
+        let requests = stand_in.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = &requests[0];
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(body);
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        let signature = hex_decode(signature.strip_prefix("sha256=").unwrap());
+        mac.verify_slice(&signature).unwrap();
+
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event"], "game_finished");
+        assert_eq!(payload["guild_id"], 1);
+        assert_eq!(payload["sent_at"], 1700003600);
+        assert_eq!(payload["summoner"]["name"], "Faker");
+        assert_eq!(payload["game"]["lp_change"], 18);
+        assert_eq!(payload["message"]["title"], "Victory");
+    }
+
+    #[tokio::test]
+    async fn omits_signature_without_secret() {
+        let (url, stand_in) = serve(vec![]);
+
+        notify(&notifier(url, None)).await.unwrap();
+
+        let requests = stand_in.requests.lock().unwrap();
+        assert!(!requests[0].0.contains_key(SIGNATURE_HEADER));
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (url, stand_in) = serve(vec![
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::TOO_MANY_REQUESTS,
+        ]);
+
+        notify(&notifier(url, None)).await.unwrap();
+
+        assert_eq!(stand_in.requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (url, stand_in) = serve(vec![StatusCode::BAD_GATEWAY; 10]);
+
+        assert!(notify(&notifier(url, None)).await.is_err());
+        assert_eq!(
+            stand_in.requests.lock().unwrap().len(),
+            MAX_ATTEMPTS as usize
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, stand_in) = serve(vec![StatusCode::BAD_REQUEST]);
+
+        assert!(notify(&notifier(url, None)).await.is_err());
+        assert_eq!(stand_in.requests.lock().unwrap().len(), 1);
+    }
+
+    fn hex_decode(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,101 @@
+/// Tiers from lowest to highest. Master and above are "apex" tiers
+/// which have no divisions and share one continuous LP ladder.
+static TIERS: [&str; 10] = [
+    "iron",
+    "bronze",
+    "silver",
+    "gold",
+    "platinum",
+    "emerald",
+    "diamond",
+    "master",
+    "grandmaster",
+    "challenger",
+];
+
+static FIRST_APEX_TIER: usize = 7;
+
+/// LP span of a single division
+static DIVISION_LP: i64 = 100;
+
+/// Number that sorts ranks correctly: tier, then division, then lp.
+///
+/// Returns None for unranked summoners.
+pub fn ordinal(tier: Option<&str>, division: Option<&str>, lp: Option<i64>) -> Option<i64> {
+    let tier = tier?.to_lowercase();
+    let tier_index = TIERS.iter().position(|t| *t == tier)?;
+    let lp = lp.unwrap_or(0);
+
+    // Every non apex tier has 4 divisions
+    let apex_base = FIRST_APEX_TIER as i64 * 4 * DIVISION_LP;
+
+    if tier_index >= FIRST_APEX_TIER {
+        // Master, Grandmaster and Challenger are ordered by LP alone
+        return Some(apex_base + lp);
+    }
+
+    let division = match division.map(|d| d.to_uppercase()).as_deref() {
+        Some("IV") | Some("4") => 0,
+        Some("III") | Some("3") => 1,
+        Some("II") | Some("2") => 2,
+        Some("I") | Some("1") => 3,
+        _ => 0,
+    };
+
+    Some((tier_index as i64 * 4 + division) * DIVISION_LP + lp.min(DIVISION_LP))
+}
+
+/// e.g. "Gold II 45 lp", "Master 312 lp" or "Unranked"
+pub fn format_rank(tier: Option<&str>, division: Option<&str>, lp: Option<i64>) -> String {
+    match (tier, division, lp) {
+        (Some(tier), Some(division), Some(lp)) => format!("{} {} {} lp", tier, division, lp),
+        (Some(tier), None, Some(lp)) => format!("{} {} lp", tier, lp),
+        (Some(tier), Some(division), None) => format!("{} {}", tier, division),
+        (Some(tier), None, None) => tier.to_string(),
+        _ => "Unranked".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_by_tier_division_then_lp() {
+        let gold_2 = ordinal(Some("Gold"), Some("II"), Some(99));
+        let gold_1 = ordinal(Some("gold"), Some("I"), Some(0));
+        let platinum_4 = ordinal(Some("Platinum"), Some("IV"), Some(0));
+        assert!(gold_2 < gold_1);
+        assert!(gold_1 < platinum_4);
+        assert_eq!(ordinal(None, None, None), None);
+        assert_eq!(ordinal(Some("Wood"), Some("I"), Some(0)), None);
+    }
+
+    #[test]
+    fn apex_tiers_share_one_lp_ladder() {
+        let diamond_1 = ordinal(Some("Diamond"), Some("I"), Some(99));
+        let master = ordinal(Some("Master"), None, Some(0));
+        let master_high = ordinal(Some("Master"), None, Some(450));
+        let grandmaster = ordinal(Some("Grandmaster"), None, Some(300));
+        let challenger = ordinal(Some("Challenger"), None, Some(1200));
+
+        assert!(diamond_1 < master);
+        assert!(master < grandmaster);
+        // Apex tiers sort by lp alone, tier doesn't matter
+        assert!(grandmaster < master_high);
+        assert!(master_high < challenger);
+    }
+
+    #[test]
+    fn formats_ranks() {
+        assert_eq!(
+            format_rank(Some("Gold"), Some("II"), Some(45)),
+            "Gold II 45 lp"
+        );
+        assert_eq!(
+            format_rank(Some("Master"), None, Some(312)),
+            "Master 312 lp"
+        );
+        assert_eq!(format_rank(None, None, Some(10)), "Unranked");
+    }
+}
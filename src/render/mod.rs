@@ -0,0 +1,581 @@
+use anyhow::Result;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    dtos::{
+        active_game_dto::ActiveGameDto,
+        game_dto::{GameDto, GameResult},
+        summoner_dto::SummonerDto,
+    },
+    notifier::{MissedGame, Notification},
+    util,
+};
+
+mod template;
+
+pub use template::{set_override, FieldKind, Template, TEMPLATE_KEYS};
+
+static CATCH_UP_TITLE: &str = "While I was away";
+// Blurple #5865f2
+static CATCH_UP_COLOR: u32 = 0x5865f2;
+/// Games listed in a catch up message, the rest are only counted
+static CATCH_UP_MAX_LINES: usize = 20;
+/// Discord's limit
+static MAX_FIELDS: usize = 25;
+
+/// Chat agnostic message, the Discord notifier turns this into an embed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Message {
+    pub author: Option<Author>,
+    pub title: String,
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub color: u32,
+    pub timestamp: i64,
+    pub thumbnail: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Author {
+    pub name: String,
+    pub icon_url: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+impl Field {
+    fn new(name: impl Into<String>, value: impl Into<String>, inline: bool) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            inline,
+        }
+    }
+}
+
+pub fn render(notification: &Notification<'_>, template: &Template) -> Result<Message> {
+    match notification {
+        Notification::GameFinished {
+            summoner,
+            game,
+            champion_image_url,
+            highlights,
+        } => render_game_finished(summoner, game, champion_image_url, highlights, template),
+        Notification::GameStarted {
+            summoner,
+            active_game,
+            champion_image_url,
+        } => render_game_started(summoner, active_game, champion_image_url, template),
+        Notification::CatchUp { games } => Ok(render_catch_up(games, template)),
+    }
+}
+
+fn render_game_finished(
+    summoner: &SummonerDto,
+    game: &GameDto,
+    champion_image_url: &str,
+    highlights: &[String],
+    template: &Template,
+) -> Result<Message> {
+    let (title, emoji, color) = match game.result {
+        GameResult::Win => (
+            &template.victory_title,
+            &template.victory_emoji,
+            template.victory_color,
+        ),
+        GameResult::Loss => (
+            &template.defeat_title,
+            &template.defeat_emoji,
+            template.defeat_color,
+        ),
+        GameResult::Remake => (
+            &template.remake_title,
+            &template.remake_emoji,
+            template.remake_color,
+        ),
+    };
+
+    let lp_change = game
+        .lp_change
+        .map(|lp| {
+            if lp > 0 {
+                format!("+{}", lp)
+            } else {
+                lp.to_string()
+            }
+        })
+        .map(|lp| format!("{} lp!", lp));
+
+    // Highlights go on their own lines under the lp change
+    let description: Vec<String> = lp_change
+        .or(game.promotion_text.clone())
+        .into_iter()
+        .chain(highlights.iter().cloned())
+        .collect();
+
+    let match_url = format!("https://leagueofgraphs.com{}", game.id);
+
+    let fields = template
+        .game_fields
+        .iter()
+        .filter_map(|kind| match kind {
+            // Rank gets a row of its own
+            FieldKind::Rank => rank_field(summoner, false),
+            FieldKind::Queue => Some(Field::new("Queue", &game.game_mode, true)),
+            FieldKind::Score => Some(Field::new(
+                "Score",
+                format!("{}/{}/{}", game.kills, game.deaths, game.assists),
+                true,
+            )),
+            FieldKind::Champion => Some(Field::new("Champion", &game.champion_name, true)),
+            FieldKind::Role => None,
+        })
+        .collect();
+
+    Ok(Message {
+        author: Some(author(summoner)?),
+        title: title_text(title, emoji, summoner, &game.champion_name, &game.game_mode),
+        url: Some(Url::parse(&match_url)?.to_string()),
+        description: Some(description.join("\n")).filter(|d| !d.is_empty()),
+        color,
+        timestamp: game.game_created_at,
+        thumbnail: Some(champion_image_url.to_string()),
+        fields,
+    })
+}
+
+fn render_game_started(
+    summoner: &SummonerDto,
+    active_game: &ActiveGameDto,
+    champion_image_url: &str,
+    template: &Template,
+) -> Result<Message> {
+    let match_url = format!("https://porofessor.gg/live/na/{}", summoner.name);
+
+    let fields = template
+        .active_game_fields
+        .iter()
+        .filter_map(|kind| match kind {
+            FieldKind::Champion => Some(Field::new("Champion", &active_game.champion, true)),
+            // Only if available
+            FieldKind::Role if active_game.role.to_lowercase().contains("unknown") => None,
+            FieldKind::Role => Some(Field::new("Role", &active_game.role, true)),
+            FieldKind::Rank => rank_field(summoner, true),
+            FieldKind::Queue => Some(Field::new("Queue", &active_game.game_mode, true)),
+            FieldKind::Score => None,
+        })
+        .collect();
+
+    Ok(Message {
+        author: Some(author(summoner)?),
+        title: title_text(
+            &template.in_game_title,
+            &template.in_game_emoji,
+            summoner,
+            &active_game.champion,
+            &active_game.game_mode,
+        ),
+        url: Some(Url::parse(&match_url)?.to_string()),
+        description: is_demotion_game(summoner).then(|| template.demotion_text.clone()),
+        color: template.in_game_color,
+        timestamp: active_game.game_created_at,
+        thumbnail: Some(champion_image_url.to_string()),
+        fields,
+    })
+}
+
+/// - one line per game, oldest first
+/// - a field per summoner with their record and lp over all games
+fn render_catch_up(games: &[MissedGame<'_>], template: &Template) -> Message {
+    let mut lines: Vec<String> = games
+        .iter()
+        .take(CATCH_UP_MAX_LINES)
+        .map(|MissedGame { summoner, game }| {
+            let (title, emoji) = match game.result {
+                GameResult::Win => (&template.victory_title, &template.victory_emoji),
+                GameResult::Loss => (&template.defeat_title, &template.defeat_emoji),
+                GameResult::Remake => (&template.remake_title, &template.remake_emoji),
+            };
+            let mut line = format!(
+                "**{}** {} on {} ({}/{}/{})",
+                summoner.name,
+                title_text(title, emoji, summoner, &game.champion_name, &game.game_mode),
+                game.champion_name,
+                game.kills,
+                game.deaths,
+                game.assists
+            );
+            if let Some(lp) = game.lp_change {
+                line.push_str(&format!(", {:+} lp", lp));
+            }
+            line
+        })
+        .collect();
+    if games.len() > CATCH_UP_MAX_LINES {
+        lines.push(format!("...and {} more", games.len() - CATCH_UP_MAX_LINES));
+    }
+
+    // (name, wins, losses, lp) in order of first appearance
+    let mut records: Vec<(&str, usize, usize, i64)> = vec![];
+    for MissedGame { summoner, game } in games {
+        let index = match records.iter().position(|r| r.0 == summoner.name) {
+            Some(index) => index,
+            None => {
+                records.push((&summoner.name, 0, 0, 0));
+                records.len() - 1
+            }
+        };
+        let record = &mut records[index];
+        match game.result {
+            GameResult::Win => record.1 += 1,
+            GameResult::Loss => record.2 += 1,
+            GameResult::Remake => {}
+        }
+        record.3 += game.lp_change.unwrap_or(0);
+    }
+
+    let fields = records
+        .into_iter()
+        .take(MAX_FIELDS)
+        .map(|(name, wins, losses, lp)| {
+            Field::new(name, format!("{}W {}L, {:+} lp", wins, losses, lp), true)
+        })
+        .collect();
+
+    Message {
+        author: None,
+        title: CATCH_UP_TITLE.to_string(),
+        url: None,
+        description: Some(lines.join("\n")).filter(|d| !d.is_empty()),
+        color: CATCH_UP_COLOR,
+        timestamp: games
+            .last()
+            .map(|g| g.game.game_created_at)
+            .unwrap_or_default(),
+        thumbnail: None,
+        fields,
+    }
+}
+
+fn author(summoner: &SummonerDto) -> Result<Author> {
+    Ok(Author {
+        name: summoner.name.clone(),
+        icon_url: Url::parse(&summoner.icon_url)?.to_string(),
+        url: util::get_author_url(&summoner.name)?,
+    })
+}
+
+fn title_text(
+    title: &str,
+    emoji: &Option<String>,
+    summoner: &SummonerDto,
+    champion: &str,
+    queue: &str,
+) -> String {
+    let title = title
+        .replace("{summoner}", &summoner.name)
+        .replace("{champion}", champion)
+        .replace("{queue}", queue);
+
+    match emoji {
+        Some(emoji) => format!("{} {}", emoji, title),
+        None => title,
+    }
+}
+
+/// e.g. "Gold II" / "45 lp", only if the summoner is ranked
+fn rank_field(summoner: &SummonerDto, inline: bool) -> Option<Field> {
+    match (&summoner.tier, &summoner.division, summoner.lp) {
+        (Some(tier), Some(division), Some(lp)) => Some(Field::new(
+            format!("{} {}", tier, division),
+            format!("{} lp", lp),
+            inline,
+        )),
+        _ => None,
+    }
+}
+
+/// 0 lp in division I-III, losing drops a division
+fn is_demotion_game(summoner: &SummonerDto) -> bool {
+    match (summoner.lp, summoner.division.as_deref()) {
+        (Some(lp), Some(division)) => lp == 0 && ["I", "II", "III"].contains(&division),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dtos::guild_template_dto::GuildTemplateDto;
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "hide-on-bush".to_string(),
+            name: "Hide on bush".to_string(),
+            guild_id: 1,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Gold".to_string()),
+            lp: Some(45),
+            division: Some("II".to_string()),
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    fn game(result: GameResult) -> GameDto {
+        GameDto {
+            id: "/match/na/4812345678".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 7,
+            deaths: 3,
+            kills: 10,
+            result,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Soloqueue".to_string(),
+            lp_change: match result {
+                GameResult::Win => Some(18),
+                GameResult::Loss => Some(-16),
+                GameResult::Remake => None,
+            },
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: None,
+        }
+    }
+
+    fn active_game() -> ActiveGameDto {
+        ActiveGameDto {
+            id: "na1-4812345679".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            game_created_at: 1700003600,
+            champion: "Ahri".to_string(),
+            role: "Mid".to_string(),
+            spectate_link: "https://example.com/spectate".to_string(),
+            notified: false,
+            game_mode: "Soloqueue".to_string(),
+        }
+    }
+
+    fn render_game(summoner: &SummonerDto, game: &GameDto, template: &Template) -> Message {
+        render_game_with_highlights(summoner, game, template, vec![])
+    }
+
+    fn render_game_with_highlights(
+        summoner: &SummonerDto,
+        game: &GameDto,
+        template: &Template,
+        highlights: Vec<String>,
+    ) -> Message {
+        let notification = Notification::GameFinished {
+            summoner,
+            game,
+            champion_image_url: "https://example.com/Ahri.png".to_string(),
+            highlights,
+        };
+        render(&notification, template).unwrap()
+    }
+
+    fn render_active_game(
+        summoner: &SummonerDto,
+        active_game: &ActiveGameDto,
+        template: &Template,
+    ) -> Message {
+        let notification = Notification::GameStarted {
+            summoner,
+            active_game,
+            champion_image_url: "https://example.com/Ahri.png".to_string(),
+        };
+        render(&notification, template).unwrap()
+    }
+
+    fn custom_template() -> Template {
+        let mut overrides = GuildTemplateDto::new(1);
+        set_override(
+            &mut overrides,
+            "victory_title",
+            Some("{summoner} won on {champion}"),
+        )
+        .unwrap();
+        set_override(
+            &mut overrides,
+            "in_game_title",
+            Some("{summoner} is playing {queue}"),
+        )
+        .unwrap();
+        set_override(&mut overrides, "victory_emoji", Some("🏆")).unwrap();
+        set_override(&mut overrides, "in_game_emoji", Some("🎮")).unwrap();
+        set_override(&mut overrides, "victory_color", Some("#00ff00")).unwrap();
+        set_override(&mut overrides, "game_fields", Some("score, rank")).unwrap();
+        set_override(&mut overrides, "active_game_fields", Some("queue,champion")).unwrap();
+        Template::from_overrides(&overrides)
+    }
+
+    #[test]
+    fn victory() {
+        let message = render_game(&summoner(), &game(GameResult::Win), &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn defeat() {
+        let message = render_game(&summoner(), &game(GameResult::Loss), &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn remake() {
+        let game = GameDto {
+            game_duration: Some(200),
+            ..game(GameResult::Remake)
+        };
+        let message = render_game(&summoner(), &game, &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn promotion_without_lp() {
+        let game = GameDto {
+            lp_change: None,
+            promotion_text: Some("Promoted to Gold I".to_string()),
+            ..game(GameResult::Win)
+        };
+        let message = render_game(&summoner(), &game, &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn unranked_game() {
+        let summoner = SummonerDto {
+            tier: None,
+            division: None,
+            lp: None,
+            ..summoner()
+        };
+        let game = GameDto {
+            lp_change: None,
+            game_mode: "ARAM".to_string(),
+            ..game(GameResult::Win)
+        };
+        let message = render_game(&summoner, &game, &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn victory_with_highlights() {
+        let message = render_game_with_highlights(
+            &summoner(),
+            &game(GameResult::Win),
+            &Template::default(),
+            vec![
+                "🔥 5 wins in a row!".to_string(),
+                "✨ Perfect KDA 10/0/7".to_string(),
+            ],
+        );
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn in_game() {
+        let message = render_active_game(&summoner(), &active_game(), &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn in_game_demotion_unknown_role() {
+        let summoner = SummonerDto {
+            lp: Some(0),
+            ..summoner()
+        };
+        let active_game = ActiveGameDto {
+            role: "Unknown".to_string(),
+            ..active_game()
+        };
+        let message = render_active_game(&summoner, &active_game, &Template::default());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn custom_victory() {
+        let message = render_game(&summoner(), &game(GameResult::Win), &custom_template());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn custom_in_game() {
+        let message = render_active_game(&summoner(), &active_game(), &custom_template());
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn catch_up() {
+        let faker = SummonerDto {
+            id: "faker".to_string(),
+            name: "Faker".to_string(),
+            ..summoner()
+        };
+        let (win, loss, remake) = (
+            game(GameResult::Win),
+            game(GameResult::Loss),
+            game(GameResult::Remake),
+        );
+        let summoner = summoner();
+        let games = vec![
+            MissedGame {
+                summoner: &summoner,
+                game: &win,
+            },
+            MissedGame {
+                summoner: &faker,
+                game: &remake,
+            },
+            MissedGame {
+                summoner: &summoner,
+                game: &loss,
+            },
+        ];
+        let notification = Notification::CatchUp { games };
+        let message = render(&notification, &Template::default()).unwrap();
+        insta::assert_json_snapshot!(message);
+    }
+
+    #[test]
+    fn rejects_invalid_overrides() {
+        let mut overrides = GuildTemplateDto::new(1);
+        assert!(set_override(&mut overrides, "victory_color", Some("green")).is_err());
+        assert!(set_override(&mut overrides, "game_fields", Some("role")).is_err());
+        assert!(set_override(&mut overrides, "remake_notifications", Some("hide")).is_err());
+        assert!(set_override(&mut overrides, "nope", Some("x")).is_err());
+        assert_eq!(Template::from_overrides(&overrides), Template::default());
+    }
+
+    #[test]
+    fn suppress_remakes() {
+        let mut overrides = GuildTemplateDto::new(1);
+        set_override(&mut overrides, "remake_notifications", Some("suppress")).unwrap();
+        assert!(Template::from_overrides(&overrides).suppress_remakes);
+    }
+
+    #[test]
+    fn reset_override() {
+        let mut overrides = GuildTemplateDto::new(1);
+        set_override(&mut overrides, "defeat_title", Some("gg")).unwrap();
+        set_override(&mut overrides, "defeat_title", None).unwrap();
+        assert_eq!(Template::from_overrides(&overrides), Template::default());
+    }
+}
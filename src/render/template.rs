@@ -0,0 +1,281 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::dtos::guild_template_dto::GuildTemplateDto;
+
+/// Fields that can be shown on a message, in the order given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Rank,
+    Queue,
+    Score,
+    Champion,
+    Role,
+}
+
+impl FieldKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldKind::Rank => "rank",
+            FieldKind::Queue => "queue",
+            FieldKind::Score => "score",
+            FieldKind::Champion => "champion",
+            FieldKind::Role => "role",
+        }
+    }
+}
+
+impl FromStr for FieldKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "rank" => Ok(FieldKind::Rank),
+            "queue" => Ok(FieldKind::Queue),
+            "score" => Ok(FieldKind::Score),
+            "champion" => Ok(FieldKind::Champion),
+            "role" => Ok(FieldKind::Role),
+            other => Err(anyhow!("unknown field: {}", other)),
+        }
+    }
+}
+
+static GAME_FIELDS: [FieldKind; 4] = [
+    FieldKind::Rank,
+    FieldKind::Queue,
+    FieldKind::Score,
+    FieldKind::Champion,
+];
+
+static ACTIVE_GAME_FIELDS: [FieldKind; 4] = [
+    FieldKind::Champion,
+    FieldKind::Role,
+    FieldKind::Rank,
+    FieldKind::Queue,
+];
+
+/// Keys accepted by `!template set`
+pub static TEMPLATE_KEYS: [&str; 16] = [
+    "victory_title",
+    "defeat_title",
+    "in_game_title",
+    "victory_color",
+    "defeat_color",
+    "in_game_color",
+    "victory_emoji",
+    "defeat_emoji",
+    "in_game_emoji",
+    "game_fields",
+    "active_game_fields",
+    "demotion_text",
+    "remake_title",
+    "remake_color",
+    "remake_emoji",
+    "remake_notifications",
+];
+
+/// How a guild's notifications look.
+///
+/// Titles can use `{summoner}`, `{champion}` and `{queue}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub victory_title: String,
+    pub defeat_title: String,
+    pub in_game_title: String,
+    pub victory_color: u32,
+    pub defeat_color: u32,
+    pub in_game_color: u32,
+    pub victory_emoji: Option<String>,
+    pub defeat_emoji: Option<String>,
+    pub in_game_emoji: Option<String>,
+    pub game_fields: Vec<FieldKind>,
+    pub active_game_fields: Vec<FieldKind>,
+    pub demotion_text: String,
+    pub remake_title: String,
+    pub remake_color: u32,
+    pub remake_emoji: Option<String>,
+    /// Skip remakes instead of posting them
+    pub suppress_remakes: bool,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            victory_title: "Victory".to_string(),
+            defeat_title: "Defeat".to_string(),
+            in_game_title: "In game {queue}".to_string(),
+            // Green #15e55a
+            victory_color: 0x15e55a,
+            // Red #e55a5a
+            defeat_color: 0xe55a5a,
+            // Yello #e5e55a
+            in_game_color: 0xe5e55a,
+            victory_emoji: None,
+            defeat_emoji: None,
+            in_game_emoji: None,
+            game_fields: GAME_FIELDS.to_vec(),
+            active_game_fields: vec![FieldKind::Champion, FieldKind::Role, FieldKind::Rank],
+            demotion_text: "⚠️ Demotion Game ⚠️".to_string(),
+            remake_title: "Remake".to_string(),
+            // Gray #80848e
+            remake_color: 0x80848e,
+            remake_emoji: None,
+            suppress_remakes: false,
+        }
+    }
+}
+
+impl Template {
+    /// Defaults with the guild's overrides applied. Values are validated
+    /// before they are saved, anything invalid here falls back to the default.
+    pub fn from_overrides(overrides: &GuildTemplateDto) -> Self {
+        let default = Self::default();
+        let color = |value: &Option<String>, default: u32| {
+            value
+                .as_deref()
+                .and_then(|c| parse_color(c).ok())
+                .unwrap_or(default)
+        };
+        let fields = |value: &Option<String>, allowed: &[FieldKind], default: Vec<FieldKind>| {
+            value
+                .as_deref()
+                .and_then(|f| parse_fields(f, allowed).ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            victory_title: overrides
+                .victory_title
+                .clone()
+                .unwrap_or(default.victory_title),
+            defeat_title: overrides
+                .defeat_title
+                .clone()
+                .unwrap_or(default.defeat_title),
+            in_game_title: overrides
+                .in_game_title
+                .clone()
+                .unwrap_or(default.in_game_title),
+            victory_color: color(&overrides.victory_color, default.victory_color),
+            defeat_color: color(&overrides.defeat_color, default.defeat_color),
+            in_game_color: color(&overrides.in_game_color, default.in_game_color),
+            victory_emoji: overrides.victory_emoji.clone().or(default.victory_emoji),
+            defeat_emoji: overrides.defeat_emoji.clone().or(default.defeat_emoji),
+            in_game_emoji: overrides.in_game_emoji.clone().or(default.in_game_emoji),
+            game_fields: fields(&overrides.game_fields, &GAME_FIELDS, default.game_fields),
+            active_game_fields: fields(
+                &overrides.active_game_fields,
+                &ACTIVE_GAME_FIELDS,
+                default.active_game_fields,
+            ),
+            demotion_text: overrides
+                .demotion_text
+                .clone()
+                .unwrap_or(default.demotion_text),
+            remake_title: overrides
+                .remake_title
+                .clone()
+                .unwrap_or(default.remake_title),
+            remake_color: color(&overrides.remake_color, default.remake_color),
+            remake_emoji: overrides.remake_emoji.clone().or(default.remake_emoji),
+            suppress_remakes: overrides
+                .remake_notifications
+                .as_deref()
+                .and_then(|r| parse_remake_notifications(r).ok())
+                .unwrap_or(default.suppress_remakes),
+        }
+    }
+}
+
+/// Validate and store an override, `None` resets it to the default
+pub fn set_override(
+    overrides: &mut GuildTemplateDto,
+    key: &str,
+    value: Option<&str>,
+) -> Result<()> {
+    let value = value.map(str::trim).filter(|v| !v.is_empty());
+
+    if let Some(value) = value {
+        match key {
+            "victory_color" | "defeat_color" | "in_game_color" | "remake_color" => {
+                parse_color(value)?;
+            }
+            "game_fields" => {
+                parse_fields(value, &GAME_FIELDS)?;
+            }
+            "active_game_fields" => {
+                parse_fields(value, &ACTIVE_GAME_FIELDS)?;
+            }
+            "remake_notifications" => {
+                parse_remake_notifications(value)?;
+            }
+            _ => {}
+        }
+    }
+
+    let value = value.map(str::to_string);
+    match key {
+        "victory_title" => overrides.victory_title = value,
+        "defeat_title" => overrides.defeat_title = value,
+        "in_game_title" => overrides.in_game_title = value,
+        "victory_color" => overrides.victory_color = value,
+        "defeat_color" => overrides.defeat_color = value,
+        "in_game_color" => overrides.in_game_color = value,
+        "victory_emoji" => overrides.victory_emoji = value,
+        "defeat_emoji" => overrides.defeat_emoji = value,
+        "in_game_emoji" => overrides.in_game_emoji = value,
+        "game_fields" => overrides.game_fields = value,
+        "active_game_fields" => overrides.active_game_fields = value,
+        "demotion_text" => overrides.demotion_text = value,
+        "remake_title" => overrides.remake_title = value,
+        "remake_color" => overrides.remake_color = value,
+        "remake_emoji" => overrides.remake_emoji = value,
+        "remake_notifications" => overrides.remake_notifications = value,
+        other => bail!(
+            "unknown template key: {}. Valid keys: {}",
+            other,
+            TEMPLATE_KEYS.join(", ")
+        ),
+    }
+
+    Ok(())
+}
+
+/// `#15e55a` or `15e55a`
+pub fn parse_color(value: &str) -> Result<u32> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        bail!("colors must look like #15e55a");
+    }
+    u32::from_str_radix(hex, 16).map_err(|_| anyhow!("colors must look like #15e55a"))
+}
+
+/// `label` or `suppress`, true when remakes should be skipped
+fn parse_remake_notifications(value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "label" => Ok(false),
+        "suppress" => Ok(true),
+        _ => bail!("remake_notifications must be label or suppress"),
+    }
+}
+
+/// Comma separated, e.g. `rank,score`
+fn parse_fields(value: &str, allowed: &[FieldKind]) -> Result<Vec<FieldKind>> {
+    value
+        .split(',')
+        .filter(|f| !f.trim().is_empty())
+        .map(|f| {
+            let field = f.parse::<FieldKind>()?;
+            if !allowed.contains(&field) {
+                let allowed: Vec<&str> = allowed.iter().map(|f| f.name()).collect();
+                bail!(
+                    "{} can't be used here. Valid fields: {}",
+                    field.name(),
+                    allowed.join(", ")
+                );
+            }
+            Ok(field)
+        })
+        .collect()
+}
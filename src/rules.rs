@@ -0,0 +1,513 @@
+use anyhow::{bail, Result};
+
+use crate::{
+    db::{repository::GuildRuleRepository, Database},
+    dtos::{
+        game_dto::{GameDto, GameResult},
+        guild_rule_dto::GuildRuleDto,
+        summoner_dto::SummonerDto,
+    },
+    rank,
+};
+
+/// Previous games loaded to work out streaks
+static HISTORY_LIMIT: i64 = 50;
+
+/// Everything a rule can look at for one finished game
+pub struct RuleContext<'a> {
+    pub summoner: &'a SummonerDto,
+    pub game: &'a GameDto,
+    /// Games before this one, most recent first
+    pub history: &'a [GameDto],
+    /// Highest rank ordinal recorded before the summoner's current rank
+    pub previous_best: Option<i64>,
+    /// Pentakills in all earlier games, not just `history`
+    pub previous_pentakills: i64,
+    /// No newer game of the summoner is stored. The summoner's rank is only
+    /// known after their latest game, a poll can store several.
+    pub is_latest: bool,
+}
+
+impl RuleContext<'_> {
+    /// Games in a row with the same result as this one, including it.
+    /// Remakes don't break a streak.
+    fn streak(&self) -> usize {
+        1 + self
+            .history
+            .iter()
+            .filter(|g| g.result != GameResult::Remake)
+            .take_while(|g| g.result == self.game.result)
+            .count()
+    }
+}
+
+/// Looks at a finished game and maybe returns a highlight line
+pub trait Rule
+where
+    Self: Send + Sync,
+{
+    /// Used to toggle the rule with `!rules`
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String>;
+}
+
+/// 3, 5, 10, 15...
+fn is_streak_milestone(streak: usize) -> bool {
+    streak == 3 || (streak >= 5 && streak.rem_euclid(5) == 0)
+}
+
+struct WinStreak;
+
+impl Rule for WinStreak {
+    fn name(&self) -> &'static str {
+        "win_streak"
+    }
+
+    fn description(&self) -> &'static str {
+        "3, 5, 10... wins in a row"
+    }
+
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String> {
+        let streak = ctx.streak();
+        (ctx.game.result == GameResult::Win && is_streak_milestone(streak))
+            .then(|| format!("🔥 {} wins in a row!", streak))
+    }
+}
+
+struct LossStreak;
+
+impl Rule for LossStreak {
+    fn name(&self) -> &'static str {
+        "loss_streak"
+    }
+
+    fn description(&self) -> &'static str {
+        "3, 5, 10... losses in a row"
+    }
+
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String> {
+        let streak = ctx.streak();
+        (ctx.game.result == GameResult::Loss && is_streak_milestone(streak))
+            .then(|| format!("🥶 {} losses in a row, go touch grass", streak))
+    }
+}
+
+struct PerfectKda;
+
+impl Rule for PerfectKda {
+    fn name(&self) -> &'static str {
+        "perfect_kda"
+    }
+
+    fn description(&self) -> &'static str {
+        "a game without dying"
+    }
+
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String> {
+        let game = ctx.game;
+        (game.deaths == 0 && game.kills + game.assists > 0).then(|| {
+            format!(
+                "✨ Perfect KDA {}/{}/{}",
+                game.kills, game.deaths, game.assists
+            )
+        })
+    }
+}
+
+struct FirstPentakill;
+
+impl Rule for FirstPentakill {
+    fn name(&self) -> &'static str {
+        "first_pentakill"
+    }
+
+    fn description(&self) -> &'static str {
+        "the first pentakill we've seen"
+    }
+
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String> {
+        let pentakills = ctx.game.pentakills?;
+        (pentakills > 0 && ctx.previous_pentakills == 0).then(|| "💥 First pentakill!".to_string())
+    }
+}
+
+struct PersonalBest;
+
+impl Rule for PersonalBest {
+    fn name(&self) -> &'static str {
+        "personal_best"
+    }
+
+    fn description(&self) -> &'static str {
+        "climbing to a new highest rank"
+    }
+
+    fn evaluate(&self, ctx: &RuleContext) -> Option<String> {
+        if !ctx.is_latest {
+            return None;
+        }
+
+        let summoner = ctx.summoner;
+        let current = rank::ordinal(
+            summoner.tier.as_deref(),
+            summoner.division.as_deref(),
+            summoner.lp,
+        )?;
+
+        // Only after gaining lp, otherwise every game at the peak would count
+        let gained_lp = ctx.game.lp_change.is_some_and(|lp| lp > 0);
+        (gained_lp && current > ctx.previous_best?).then(|| {
+            format!(
+                "📈 New personal best: {}",
+                rank::format_rank(
+                    summoner.tier.as_deref(),
+                    summoner.division.as_deref(),
+                    summoner.lp
+                )
+            )
+        })
+    }
+}
+
+pub fn all_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(WinStreak),
+        Box::new(LossStreak),
+        Box::new(PerfectKda),
+        Box::new(FirstPentakill),
+        Box::new(PersonalBest),
+    ]
+}
+
+/// Rules are on unless the guild turned them off
+pub async fn enabled_rules(
+    rules: &dyn GuildRuleRepository,
+    guild_id: i64,
+) -> Result<Vec<Box<dyn Rule>>> {
+    let toggles = rules.get_for_guild(guild_id).await?;
+    let is_enabled = |name: &str| !toggles.iter().any(|t| t.rule == name && !t.enabled);
+
+    Ok(all_rules()
+        .into_iter()
+        .filter(|r| is_enabled(r.name()))
+        .collect())
+}
+
+pub async fn set_enabled(
+    rules: &dyn GuildRuleRepository,
+    guild_id: i64,
+    rule_name: &str,
+    enabled: bool,
+) -> Result<()> {
+    let Some(rule) = all_rules().into_iter().find(|r| r.name() == rule_name) else {
+        let names: Vec<&str> = all_rules().iter().map(|r| r.name()).collect();
+        bail!(
+            "unknown rule: {}. Valid rules: {}",
+            rule_name,
+            names.join(", ")
+        );
+    };
+
+    rules
+        .upsert(&GuildRuleDto::new(guild_id, rule.name(), enabled))
+        .await
+}
+
+/// - load the summoner's previous games and rank history
+/// - run every rule against the game
+pub async fn evaluate(
+    db: &Database,
+    rules: &[Box<dyn Rule>],
+    summoner: &SummonerDto,
+    game: &GameDto,
+) -> Result<Vec<String>> {
+    // Nothing worth highlighting happens in a remake
+    if rules.is_empty() || game.result == GameResult::Remake {
+        return Ok(vec![]);
+    }
+
+    let history = db
+        .games
+        .get_before_for_summoner(&summoner.id, game.game_created_at, HISTORY_LIMIT)
+        .await?;
+
+    // The latest snapshot is the current rank, so the best is over the ones before it
+    let snapshots = db.rank_snapshots.get_all_for_summoner(&summoner.id).await?;
+    let previous_best = snapshots
+        .iter()
+        .rev()
+        .skip(1)
+        .filter_map(|s| rank::ordinal(s.tier.as_deref(), s.division.as_deref(), s.lp))
+        .max();
+
+    let previous_pentakills = db
+        .games
+        .count_pentakills_before(&summoner.id, game.game_created_at)
+        .await?;
+
+    let is_latest = db
+        .games
+        .get_recent_for_summoner(&summoner.id, 1)
+        .await?
+        .iter()
+        .all(|latest| latest.game_created_at <= game.game_created_at);
+
+    let ctx = RuleContext {
+        summoner,
+        game,
+        history: &history,
+        previous_best,
+        previous_pentakills,
+        is_latest,
+    };
+
+    Ok(rules.iter().filter_map(|r| r.evaluate(&ctx)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        dtos::{guild_dto::GuildDto, rank_snapshot_dto::RankSnapshotDto},
+    };
+
+    fn summoner() -> SummonerDto {
+        SummonerDto {
+            id: "hide-on-bush".to_string(),
+            name: "Hide on bush".to_string(),
+            guild_id: 1,
+            created_at: None,
+            updated_at: None,
+            queue_type: Some("Soloqueue".to_string()),
+            tier: Some("Gold".to_string()),
+            lp: Some(45),
+            division: Some("II".to_string()),
+            icon_url: "https://example.com/icon.png".to_string(),
+            added_by: None,
+        }
+    }
+
+    fn game(result: GameResult) -> GameDto {
+        GameDto {
+            id: "/match/na/1".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 4,
+            deaths: 5,
+            kills: 6,
+            result,
+            notified: false,
+            champion_name: "Ahri".to_string(),
+            game_mode: "Ranked Solo/Duo".to_string(),
+            lp_change: Some(if result == GameResult::Win { 18 } else { -17 }),
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: Some(0),
+        }
+    }
+
+    /// `game` after `history`, most recent first
+    fn evaluate_rule(rule: &dyn Rule, game: &GameDto, history: &[GameDto]) -> Option<String> {
+        rule.evaluate(&RuleContext {
+            summoner: &summoner(),
+            game,
+            history,
+            previous_best: None,
+            previous_pentakills: 0,
+            is_latest: true,
+        })
+    }
+
+    #[test]
+    fn streaks_fire_on_milestones() {
+        let wins = vec![game(GameResult::Win); 9];
+        let win = game(GameResult::Win);
+
+        assert_eq!(evaluate_rule(&WinStreak, &win, &wins[..1]), None);
+        assert_eq!(
+            evaluate_rule(&WinStreak, &win, &wins[..2]).as_deref(),
+            Some("🔥 3 wins in a row!")
+        );
+        assert_eq!(evaluate_rule(&WinStreak, &win, &wins[..3]), None);
+        assert!(evaluate_rule(&WinStreak, &win, &wins[..4]).is_some());
+        assert!(evaluate_rule(&WinStreak, &win, &wins[..9]).is_some());
+        // A loss doesn't continue a win streak
+        assert_eq!(
+            evaluate_rule(&WinStreak, &game(GameResult::Loss), &wins[..2]),
+            None
+        );
+
+        // Remakes don't break a streak, a win does
+        let history = [
+            game(GameResult::Loss),
+            game(GameResult::Remake),
+            game(GameResult::Loss),
+            game(GameResult::Win),
+        ];
+        let loss = game(GameResult::Loss);
+        assert_eq!(
+            evaluate_rule(&LossStreak, &loss, &history).as_deref(),
+            Some("🥶 3 losses in a row, go touch grass")
+        );
+        assert_eq!(evaluate_rule(&LossStreak, &loss, &history[3..]), None);
+    }
+
+    #[test]
+    fn perfect_kda_needs_a_contribution() {
+        let flawless = GameDto {
+            deaths: 0,
+            ..game(GameResult::Win)
+        };
+        assert_eq!(
+            evaluate_rule(&PerfectKda, &flawless, &[]).as_deref(),
+            Some("✨ Perfect KDA 6/0/4")
+        );
+
+        let afk = GameDto {
+            kills: 0,
+            deaths: 0,
+            assists: 0,
+            ..game(GameResult::Loss)
+        };
+        assert_eq!(evaluate_rule(&PerfectKda, &afk, &[]), None);
+        assert_eq!(
+            evaluate_rule(&PerfectKda, &game(GameResult::Win), &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn first_pentakill_fires_once() {
+        let penta = GameDto {
+            pentakills: Some(1),
+            ..game(GameResult::Win)
+        };
+        let summoner = summoner();
+        let ctx = |previous_pentakills| RuleContext {
+            summoner: &summoner,
+            game: &penta,
+            history: &[],
+            previous_best: None,
+            previous_pentakills,
+            is_latest: true,
+        };
+
+        assert!(FirstPentakill.evaluate(&ctx(0)).is_some());
+        assert_eq!(FirstPentakill.evaluate(&ctx(1)), None);
+        assert_eq!(
+            evaluate_rule(&FirstPentakill, &game(GameResult::Win), &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn personal_best_needs_a_higher_rank_and_lp_gain() {
+        let win = game(GameResult::Win);
+        let best = |previous_best| {
+            PersonalBest.evaluate(&RuleContext {
+                summoner: &summoner(),
+                game: &win,
+                history: &[],
+                previous_best,
+                previous_pentakills: 0,
+                is_latest: true,
+            })
+        };
+        let gold_2_45 = rank::ordinal(Some("Gold"), Some("II"), Some(45));
+        let gold_2_40 = rank::ordinal(Some("Gold"), Some("II"), Some(40));
+
+        assert_eq!(
+            best(gold_2_40).as_deref(),
+            Some("📈 New personal best: Gold II 45 lp")
+        );
+        assert_eq!(best(gold_2_45), None);
+        // Nothing to compare with yet
+        assert_eq!(best(None), None);
+
+        let loss = game(GameResult::Loss);
+        let ctx = RuleContext {
+            summoner: &summoner(),
+            game: &loss,
+            history: &[],
+            previous_best: gold_2_40,
+            previous_pentakills: 0,
+            is_latest: true,
+        };
+        assert_eq!(PersonalBest.evaluate(&ctx), None);
+    }
+
+    #[tokio::test]
+    async fn guilds_toggle_rules() -> Result<()> {
+        let db = Database::in_memory(Arc::new(ManualClock::new()));
+        for guild_id in [1, 2] {
+            db.guilds
+                .insert_or_ignore(&GuildDto::new(guild_id, None, "guild".to_string()))
+                .await?;
+        }
+
+        set_enabled(&*db.rules, 1, "loss_streak", false).await?;
+        assert!(set_enabled(&*db.rules, 1, "pentakill", false)
+            .await
+            .is_err());
+
+        let names = |rules: Vec<Box<dyn Rule>>| -> Vec<&'static str> {
+            rules.iter().map(|r| r.name()).collect()
+        };
+        let guild_1 = names(enabled_rules(&*db.rules, 1).await?);
+        assert!(!guild_1.contains(&"loss_streak"));
+        assert!(guild_1.contains(&"win_streak"));
+        assert_eq!(
+            names(enabled_rules(&*db.rules, 2).await?),
+            names(all_rules())
+        );
+
+        set_enabled(&*db.rules, 1, "loss_streak", true).await?;
+        assert_eq!(
+            names(enabled_rules(&*db.rules, 1).await?),
+            names(all_rules())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn personal_best_only_fires_on_the_latest_game_of_a_poll() -> Result<()> {
+        let db = Database::in_memory(Arc::new(ManualClock::new()));
+        db.guilds
+            .insert_or_ignore(&GuildDto::new(1, None, "guild".to_string()))
+            .await?;
+        db.summoners.insert_or_ignore(&summoner()).await?;
+        let earlier = RankSnapshotDto {
+            lp: Some(10),
+            ..RankSnapshotDto::from_summoner(&summoner(), 1600000000)
+        };
+        db.rank_snapshots.insert(&earlier).await?;
+        db.rank_snapshots
+            .insert(&RankSnapshotDto::from_summoner(&summoner(), 1700003600))
+            .await?;
+
+        // Both games showed up in the same poll, the rank is the one after the second
+        let first = game(GameResult::Win);
+        let second = GameDto {
+            id: "/match/na/2".to_string(),
+            game_created_at: first.game_created_at + 1800,
+            ..game(GameResult::Win)
+        };
+        db.games.insert_or_ignore(&first).await?;
+        db.games.insert_or_ignore(&second).await?;
+
+        let rules: Vec<Box<dyn Rule>> = vec![Box::new(PersonalBest)];
+        assert!(evaluate(&db, &rules, &summoner(), &first).await?.is_empty());
+        assert_eq!(
+            evaluate(&db, &rules, &summoner(), &second).await?,
+            vec!["📈 New personal best: Gold II 45 lp"]
+        );
+        Ok(())
+    }
+}
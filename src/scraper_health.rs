@@ -0,0 +1,291 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+
+use crate::{api_strategy::SelectorError, metrics::metrics, render::Message};
+
+/// Different summoners a method has to fail to parse for before it pauses. One
+/// summoner with an odd page shouldn't pause anything.
+static FAILING_SUMMONERS_THRESHOLD: usize = 3;
+/// How often a paused scraper gets one pass to check if parsing works again
+static PROBE_INTERVAL: i64 = 60 * 15;
+// Red #e55a5a
+static PAUSED_COLOR: u32 = 0xe55a5a;
+// Green #15e55a
+static RESUMED_COLOR: u32 = 0x15e55a;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pause {
+    /// `ApiStrategy` method that kept failing
+    pub method: &'static str,
+    pub error: String,
+    pub since: i64,
+}
+
+/// Something admins hear about once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScraperEvent {
+    Paused(Pause),
+    Resumed {
+        method: &'static str,
+        paused_since: i64,
+    },
+}
+
+#[derive(Default)]
+struct State {
+    /// Summoners each method failed to parse for since it last parsed for anyone
+    failing: BTreeMap<&'static str, BTreeSet<String>>,
+    paused: BTreeMap<&'static str, Paused>,
+    unannounced: Vec<ScraperEvent>,
+}
+
+struct Paused {
+    pause: Pause,
+    /// Last pass that got through to check if it parses again
+    last_probe_at: i64,
+}
+
+/// Circuit breaker for the scraper, per `ApiStrategy` method.
+///
+/// When a site changes its markup every request fails the same way, so once
+/// a method failed to parse for a few different summoners the workers calling
+/// it pause instead of failing for every summoner until someone notices. It
+/// resumes by itself once the method parses again.
+#[derive(Default)]
+pub struct ScraperHealth {
+    state: Mutex<State>,
+}
+
+impl ScraperHealth {
+    /// - success forgets the method's failures, and resumes it if it was paused
+    /// - selector failures count towards pausing, once per summoner
+    /// - other errors, e.g. timeouts, don't count either way
+    pub fn record<T>(&self, method: &'static str, summoner: &str, result: &Result<T>, now: i64) {
+        let mut state = self.state.lock().unwrap();
+
+        match result {
+            Ok(_) => {
+                state.failing.remove(method);
+                let Some(paused) = state.paused.remove(method) else {
+                    return;
+                };
+
+                tracing::info!(method, "Parsing works again, resuming scraping");
+                metrics().set_scraping_paused(!state.paused.is_empty());
+                state.unannounced.push(ScraperEvent::Resumed {
+                    method,
+                    paused_since: paused.pause.since,
+                });
+            }
+            Err(e) => {
+                if e.downcast_ref::<SelectorError>().is_none() {
+                    return;
+                }
+
+                let failing = state.failing.entry(method).or_default();
+                failing.insert(summoner.to_string());
+                if failing.len() < FAILING_SUMMONERS_THRESHOLD || state.paused.contains_key(method)
+                {
+                    return;
+                }
+
+                let pause = Pause {
+                    method,
+                    error: format!("{:#}", e),
+                    since: now,
+                };
+                tracing::error!(
+                    method,
+                    error = pause.error,
+                    "The site's markup seems to have changed, pausing scraping"
+                );
+                metrics().set_scraping_paused(true);
+                state.paused.insert(
+                    method,
+                    Paused {
+                        pause: pause.clone(),
+                        last_probe_at: now,
+                    },
+                );
+                state.unannounced.push(ScraperEvent::Paused(pause));
+            }
+        }
+    }
+
+    /// Whether a worker calling `methods` should scrape this pass. While one of
+    /// them is paused a pass gets through every `PROBE_INTERVAL`, that pass is
+    /// the probe however far it gets.
+    pub fn should_scrape(&self, methods: &[&'static str], now: i64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let mut paused: Vec<&mut Paused> = state
+            .paused
+            .iter_mut()
+            .filter(|(method, _)| methods.contains(method))
+            .map(|(_, paused)| paused)
+            .collect();
+
+        if paused
+            .iter()
+            .any(|p| now - p.last_probe_at < PROBE_INTERVAL)
+        {
+            return false;
+        }
+        for p in paused.iter_mut() {
+            p.last_probe_at = now;
+        }
+        true
+    }
+
+    /// Oldest first
+    pub fn paused(&self) -> Vec<Pause> {
+        let state = self.state.lock().unwrap();
+        let mut paused: Vec<Pause> = state.paused.values().map(|p| p.pause.clone()).collect();
+        paused.sort_by_key(|p| p.since);
+        paused
+    }
+
+    /// Oldest first, stay queued until `ack_event`
+    pub fn pending_events(&self) -> Vec<ScraperEvent> {
+        self.state.lock().unwrap().unannounced.clone()
+    }
+
+    /// Drop the oldest pending event once it was announced
+    pub fn ack_event(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.unannounced.is_empty() {
+            state.unannounced.remove(0);
+        }
+    }
+}
+
+impl ScraperEvent {
+    pub fn to_message(&self, now: i64) -> Message {
+        let (title, description, color) = match self {
+            ScraperEvent::Paused(pause) => (
+                "Scraping paused",
+                format!(
+                    "`{}` failed to parse for {} different summoners, the site's markup probably changed.\n> {}\n\
+                     Scraping is retried every {} minutes and resumes by itself once parsing works again.",
+                    pause.method,
+                    FAILING_SUMMONERS_THRESHOLD,
+                    pause.error,
+                    PROBE_INTERVAL / 60
+                ),
+                PAUSED_COLOR,
+            ),
+            ScraperEvent::Resumed {
+                method,
+                paused_since,
+            } => (
+                "Scraping resumed",
+                format!(
+                    "`{}` parses again, scraping was paused since <t:{}:f>.",
+                    method, paused_since
+                ),
+                RESUMED_COLOR,
+            ),
+        };
+
+        Message {
+            author: None,
+            title: title.to_string(),
+            url: None,
+            description: Some(description),
+            color,
+            timestamp: now,
+            thumbnail: None,
+            fields: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+
+    fn selector_failure() -> Result<()> {
+        None.context(SelectorError(".recentGamesTable"))
+            .context("get_games failed")
+    }
+
+    fn summoner(i: usize) -> String {
+        format!("summoner-{}", i)
+    }
+
+    #[test]
+    fn pauses_after_selector_failures_for_different_summoners() {
+        let health = ScraperHealth::default();
+
+        // The same summoner over and over is just that summoner
+        for _ in 0..FAILING_SUMMONERS_THRESHOLD {
+            health.record("get_games", &summoner(0), &selector_failure(), 100);
+        }
+        for i in 1..FAILING_SUMMONERS_THRESHOLD - 1 {
+            health.record("get_games", &summoner(i), &selector_failure(), 100);
+        }
+        // Network errors don't count
+        health.record::<()>("get_games", &summoner(9), &Err(anyhow!("timed out")), 100);
+        assert!(health.paused().is_empty());
+
+        // Parsing for anyone means the markup is fine
+        health.record("get_games", &summoner(9), &Ok(()), 100);
+        for i in 1..FAILING_SUMMONERS_THRESHOLD {
+            health.record("get_games", &summoner(i), &selector_failure(), 100);
+        }
+        assert!(health.paused().is_empty());
+
+        health.record("get_games", &summoner(0), &selector_failure(), 100);
+        assert_eq!(
+            health.paused().iter().map(|p| p.method).collect::<Vec<_>>(),
+            vec!["get_games"]
+        );
+        assert!(!health.should_scrape(&["get_summoner", "get_games"], 100 + PROBE_INTERVAL - 1));
+        // Workers that don't call it carry on
+        assert!(health.should_scrape(&["get_active_game"], 100));
+
+        // More failures don't announce it again
+        health.record("get_games", &summoner(5), &selector_failure(), 200);
+        assert_eq!(health.pending_events().len(), 1);
+    }
+
+    #[test]
+    fn probes_until_the_broken_method_parses() {
+        let health = ScraperHealth::default();
+        let methods = ["get_summoner", "get_games"];
+        for i in 0..FAILING_SUMMONERS_THRESHOLD {
+            health.record("get_games", &summoner(i), &selector_failure(), 100);
+        }
+
+        let probe_at = 100 + PROBE_INTERVAL;
+        assert!(health.should_scrape(&methods, probe_at));
+        // Other methods working doesn't mean the broken one does
+        health.record("get_summoner", &summoner(0), &Ok(()), probe_at);
+        health.record("get_games", &summoner(0), &selector_failure(), probe_at);
+        // One probe per interval, however far it got
+        assert!(!health.should_scrape(&methods, probe_at + 1));
+
+        let probe_at = probe_at + PROBE_INTERVAL;
+        assert!(health.should_scrape(&methods, probe_at));
+        health.record("get_games", &summoner(0), &Ok(()), probe_at);
+
+        assert!(health.paused().is_empty());
+        assert!(health.should_scrape(&methods, probe_at + 1));
+        assert_eq!(
+            health.pending_events()[1],
+            ScraperEvent::Resumed {
+                method: "get_games",
+                paused_since: 100
+            }
+        );
+
+        health.ack_event();
+        health.ack_event();
+        assert!(health.pending_events().is_empty());
+    }
+}
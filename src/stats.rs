@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crate::dtos::game_dto::{GameDto, GameResult};
+
+/// Champions shown in `!stats`
+static TOP_CHAMPIONS: usize = 3;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SummonerStats {
+    pub games: usize,
+    pub wins: usize,
+    pub losses: usize,
+    /// Not counted anywhere else
+    pub remakes: usize,
+    pub kills: i64,
+    pub deaths: i64,
+    pub assists: i64,
+    /// Most played first
+    pub champions: Vec<ChampionStats>,
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+    /// Sum of lp changes, games without lp are skipped
+    pub net_lp: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ChampionStats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+}
+
+impl SummonerStats {
+    /// `games` must be oldest first for streaks to be correct
+    pub fn from_games(games: &[GameDto]) -> Self {
+        let mut stats = Self::default();
+        let mut champions: HashMap<&str, ChampionStats> = HashMap::new();
+        let (mut win_streak, mut loss_streak) = (0, 0);
+
+        for game in games {
+            // Remakes say nothing about how the summoner played
+            if game.result == GameResult::Remake {
+                stats.remakes += 1;
+                continue;
+            }
+
+            stats.games += 1;
+            stats.kills += game.kills;
+            stats.deaths += game.deaths;
+            stats.assists += game.assists;
+            stats.net_lp += game.lp_change.unwrap_or(0);
+
+            let champion = champions
+                .entry(game.champion_name.as_str())
+                .or_insert_with(|| ChampionStats {
+                    name: game.champion_name.clone(),
+                    games: 0,
+                    wins: 0,
+                });
+            champion.games += 1;
+
+            if game.result == GameResult::Win {
+                stats.wins += 1;
+                champion.wins += 1;
+                win_streak += 1;
+                loss_streak = 0;
+            } else {
+                stats.losses += 1;
+                loss_streak += 1;
+                win_streak = 0;
+            }
+
+            stats.longest_win_streak = stats.longest_win_streak.max(win_streak);
+            stats.longest_loss_streak = stats.longest_loss_streak.max(loss_streak);
+        }
+
+        let mut champions: Vec<ChampionStats> = champions.into_values().collect();
+        champions.sort_by(|a, b| b.games.cmp(&a.games).then(a.name.cmp(&b.name)));
+        champions.truncate(TOP_CHAMPIONS);
+        stats.champions = champions;
+
+        stats
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        win_rate(self.wins, self.games)
+    }
+
+    /// (kills + assists) / deaths, deaths count as at least 1
+    pub fn kda(&self) -> f64 {
+        (self.kills + self.assists) as f64 / self.deaths.max(1) as f64
+    }
+
+    /// Average kills, deaths and assists per game
+    pub fn average_kda(&self) -> (f64, f64, f64) {
+        let games = self.games.max(1) as f64;
+        (
+            self.kills as f64 / games,
+            self.deaths as f64 / games,
+            self.assists as f64 / games,
+        )
+    }
+}
+
+impl ChampionStats {
+    pub fn win_rate(&self) -> f64 {
+        win_rate(self.wins, self.games)
+    }
+}
+
+/// Games played since `since`, only the ones of `queue` if given,
+/// e.g. `solo` matches "Ranked Solo/Duo"
+pub fn filter_games(games: Vec<GameDto>, since: i64, queue: Option<&str>) -> Vec<GameDto> {
+    let queue = queue.map(|q| q.to_lowercase());
+    games
+        .into_iter()
+        .filter(|g| g.game_created_at >= since)
+        .filter(|g| match &queue {
+            Some(queue) => g.game_mode.to_lowercase().contains(queue),
+            None => true,
+        })
+        .collect()
+}
+
+/// Percentage, 0 when there are no games
+fn win_rate(wins: usize, games: usize) -> f64 {
+    if games == 0 {
+        return 0.0;
+    }
+    wins as f64 / games as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(champion: &str, result: GameResult, lp_change: Option<i64>) -> GameDto {
+        GameDto {
+            id: "/match/na/1".to_string(),
+            summoner_id: "hide-on-bush".to_string(),
+            created_at: None,
+            updated_at: None,
+            game_created_at: 1700000000,
+            assists: 4,
+            deaths: 2,
+            kills: 6,
+            result,
+            notified: true,
+            champion_name: champion.to_string(),
+            game_mode: "Ranked Solo/Duo".to_string(),
+            lp_change,
+            promotion_text: None,
+            game_duration: Some(1800),
+            lp_pending: false,
+            pentakills: None,
+        }
+    }
+
+    #[test]
+    fn remakes_dont_count_towards_the_win_rate() {
+        let stats = SummonerStats::from_games(&[
+            game("Ahri", GameResult::Win, Some(18)),
+            game("Ahri", GameResult::Remake, None),
+            game("Ahri", GameResult::Loss, Some(-16)),
+        ]);
+
+        assert_eq!((stats.games, stats.wins, stats.losses), (2, 1, 1));
+        assert_eq!(stats.remakes, 1);
+        assert_eq!(stats.win_rate(), 50.0);
+        assert_eq!(stats.kills, 12);
+    }
+
+    #[test]
+    fn remakes_dont_break_streaks() {
+        let stats = SummonerStats::from_games(&[
+            game("Ahri", GameResult::Win, None),
+            game("Ahri", GameResult::Win, None),
+            game("Ahri", GameResult::Remake, None),
+            game("Ahri", GameResult::Win, None),
+            game("Ahri", GameResult::Loss, None),
+            game("Ahri", GameResult::Remake, None),
+            game("Ahri", GameResult::Loss, None),
+        ]);
+
+        assert_eq!(stats.longest_win_streak, 3);
+        assert_eq!(stats.longest_loss_streak, 2);
+    }
+
+    #[test]
+    fn games_without_lp_are_skipped_in_net_lp() {
+        let stats = SummonerStats::from_games(&[
+            game("Ahri", GameResult::Win, Some(18)),
+            game("Ahri", GameResult::Win, None),
+            game("Ahri", GameResult::Loss, Some(-16)),
+        ]);
+
+        assert_eq!(stats.net_lp, 2);
+    }
+
+    #[test]
+    fn champions_are_ordered_by_games_then_name() {
+        let stats = SummonerStats::from_games(&[
+            game("Zed", GameResult::Win, None),
+            game("Yasuo", GameResult::Loss, None),
+            game("Ahri", GameResult::Loss, None),
+            game("Zed", GameResult::Win, None),
+            game("Lux", GameResult::Win, None),
+            game("Ahri", GameResult::Win, None),
+        ]);
+
+        let champions: Vec<(&str, usize, f64)> = stats
+            .champions
+            .iter()
+            .map(|c| (c.name.as_str(), c.games, c.win_rate()))
+            .collect();
+        assert_eq!(
+            champions,
+            vec![("Ahri", 2, 50.0), ("Zed", 2, 100.0), ("Lux", 1, 100.0)]
+        );
+    }
+
+    #[test]
+    fn filters_by_age_and_queue() {
+        let old = GameDto {
+            id: "/match/na/old".to_string(),
+            game_created_at: 1600000000,
+            ..game("Ahri", GameResult::Win, Some(18))
+        };
+        let flex = GameDto {
+            id: "/match/na/flex".to_string(),
+            game_mode: "Ranked Flex".to_string(),
+            ..game("Ahri", GameResult::Win, Some(18))
+        };
+        let solo = game("Ahri", GameResult::Loss, Some(-16));
+        let games = vec![old, flex, solo];
+
+        let ids =
+            |games: Vec<GameDto>| -> Vec<String> { games.into_iter().map(|g| g.id).collect() };
+        assert_eq!(
+            ids(filter_games(games.clone(), 1700000000, None)),
+            vec!["/match/na/flex", "/match/na/1"]
+        );
+        assert_eq!(
+            ids(filter_games(games.clone(), 1700000000, Some("SOLO"))),
+            vec!["/match/na/1"]
+        );
+        assert_eq!(
+            ids(filter_games(games, 0, Some("flex"))),
+            vec!["/match/na/flex"]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use tokio::{
+    sync::watch,
+    task::{JoinHandle, JoinSet},
+};
+
+use tracing::Instrument;
+
+use crate::{clock::Clock, metrics::metrics};
+
+static MIN_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+static MAX_RESTART_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Snapshot of a worker's health. Timestamps are unix seconds.
+#[derive(Debug, Clone, Default)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub running: bool,
+    pub restarts: u32,
+    pub last_success_at: Option<i64>,
+    pub last_error_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// Handed to worker passes so long running passes can bail out
+/// between units of work once shutdown has been requested.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    async fn wait(&mut self) {
+        // An Err means the sender was dropped which is as good as a shutdown
+        let _ = self.0.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Aborts the worker task if the supervising task is itself aborted
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+type Statuses = Arc<Mutex<HashMap<&'static str, WorkerStatus>>>;
+
+/// Runs workers on an interval, restarting them with backoff if they panic.
+///
+/// A pass that returns an error is logged and retried on the next interval.
+/// A pass that panics takes down the worker task, which is restarted after
+/// an exponential backoff.
+///
+/// Status timestamps come from the clock. The intervals and backoff are
+/// durations measured by tokio, tests can pause its time instead.
+pub struct Supervisor {
+    clock: Arc<dyn Clock>,
+    started: AtomicBool,
+    shutdown_tx: watch::Sender<bool>,
+    statuses: Statuses,
+    join_set: Mutex<JoinSet<()>>,
+}
+
+impl Supervisor {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            clock,
+            started: AtomicBool::new(false),
+            shutdown_tx,
+            statuses: Arc::new(Mutex::new(HashMap::new())),
+            join_set: Mutex::new(JoinSet::new()),
+        }
+    }
+
+    /// Returns true the first time it is called, false after that.
+    /// Used to make sure workers are only ever started once.
+    pub fn try_start(&self) -> bool {
+        self.started
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// For one-off tasks outside the workers that should also stop on shutdown
+    pub fn shutdown_signal(&self) -> Shutdown {
+        Shutdown(self.shutdown_tx.subscribe())
+    }
+
+    /// Run `pass` every `interval` until shutdown
+    pub fn spawn<F, Fut>(&self, name: &'static str, interval: Duration, pass: F)
+    where
+        F: Fn(Shutdown) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.statuses.lock().unwrap().insert(
+            name,
+            WorkerStatus {
+                name,
+                ..Default::default()
+            },
+        );
+
+        let statuses = self.statuses.clone();
+        let clock = self.clock.clone();
+        let mut shutdown = Shutdown(self.shutdown_tx.subscribe());
+        let pass = Arc::new(pass);
+
+        self.join_set.lock().unwrap().spawn(async move {
+            let mut backoff = MIN_RESTART_BACKOFF;
+
+            loop {
+                let started_at = Instant::now();
+                let mut handle = AbortOnDrop(tokio::spawn(Self::run(
+                    name,
+                    interval,
+                    pass.clone(),
+                    statuses.clone(),
+                    clock.clone(),
+                    shutdown.clone(),
+                )));
+
+                let e = match (&mut handle.0).await {
+                    // Worker only returns once shutdown has been requested
+                    Ok(_) => break,
+                    Err(e) if e.is_panic() => e,
+                    // Cancelled - we are being aborted
+                    Err(_) => break,
+                };
+
+                let message = format!("{} panicked: {}", name, e);
+                Self::update_status(&statuses, name, |s| {
+                    s.running = false;
+                    s.restarts += 1;
+                    s.last_error_at = Some(clock.now());
+                    s.last_error = Some(message.clone());
+                });
+                tracing::error!(
+                    worker = name,
+                    backoff_secs = backoff.as_secs(),
+                    "{}, restarting",
+                    message
+                );
+
+                // Reset the backoff if the worker was healthy for a while
+                if started_at.elapsed() > MAX_RESTART_BACKOFF {
+                    backoff = MIN_RESTART_BACKOFF;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = shutdown.wait() => break,
+                }
+
+                backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+            }
+
+            Self::update_status(&statuses, name, |s| s.running = false);
+        });
+    }
+
+    async fn run<F, Fut>(
+        name: &'static str,
+        interval: Duration,
+        pass: Arc<F>,
+        statuses: Statuses,
+        clock: Arc<dyn Clock>,
+        mut shutdown: Shutdown,
+    ) where
+        F: Fn(Shutdown) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        Self::update_status(&statuses, name, |s| s.running = true);
+
+        while !shutdown.is_triggered() {
+            let started_at = Instant::now();
+            let span = tracing::info_span!("worker_pass", worker = name);
+            let result = pass(shutdown.clone()).instrument(span).await;
+            metrics().record_worker_pass(name, started_at.elapsed());
+
+            let now = clock.now();
+
+            match result {
+                Ok(_) => Self::update_status(&statuses, name, |s| s.last_success_at = Some(now)),
+                Err(e) => {
+                    Self::update_status(&statuses, name, |s| {
+                        s.last_error_at = Some(now);
+                        s.last_error = Some(e.to_string());
+                    });
+                    tracing::error!(worker = name, error = %e, "worker pass failed");
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.wait() => {}
+            }
+        }
+    }
+
+    fn update_status(statuses: &Statuses, name: &'static str, f: impl FnOnce(&mut WorkerStatus)) {
+        if let Some(status) = statuses.lock().unwrap().get_mut(name) {
+            f(status);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        let mut statuses: Vec<WorkerStatus> =
+            self.statuses.lock().unwrap().values().cloned().collect();
+        statuses.sort_by_key(|s| s.name);
+        statuses
+    }
+
+    /// Signal all workers to stop and wait for in-flight passes to finish.
+    /// Workers still running after `timeout` are aborted.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.shutdown_tx.send_replace(true);
+
+        let mut join_set = std::mem::take(&mut *self.join_set.lock().unwrap());
+        let drain = async { while join_set.join_next().await.is_some() {} };
+
+        if tokio::time::timeout(timeout, drain).await.is_err() {
+            join_set.shutdown().await;
+        }
+    }
+}
@@ -1,21 +1,10 @@
 use anyhow::Result;
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
 use url::Url;
 
-static CURRENT_LOL_PATCH: &str = "13.21.1";
-
-pub fn get_champion_image_url(champion_name: &str) -> Result<String> {
-    let re = regex::Regex::new(r"[' ]")?;
-
-    // remove space and single quotes from champion str
-    let champion_name = re.replace_all(champion_name, "");
-
-    let thumbnail_url = format!(
-        "https://cdn.communitydragon.org/{}/champion/{}/square",
-        CURRENT_LOL_PATCH, champion_name
-    );
-
-    Ok(Url::parse(&thumbnail_url)?.to_string())
-}
+/// Length of generated api tokens
+static TOKEN_LENGTH: usize = 40;
 
 pub fn get_author_url(summoner_name: &str) -> Result<String> {
     let author_url = Url::parse(&format!(
@@ -25,3 +14,44 @@ pub fn get_author_url(summoner_name: &str) -> Result<String> {
     .to_string();
     Ok(author_url)
 }
+
+/// Random alphanumeric token
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub fn sha256_hex(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Resolves on SIGTERM (docker stop) or ctrl-c
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}